@@ -0,0 +1,225 @@
+use std::{fmt::Display, str::FromStr};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseError {
+    pub line: usize,
+    pub msg: String,
+}
+
+impl Display for ParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "line {}: {}", self.line, self.msg)
+    }
+}
+
+impl ParseError {
+    fn new(msg: String) -> Self {
+        Self { line: 0, msg }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Register(pub usize);
+
+impl Register {
+    pub const A: Register = Register(0);
+    pub const B: Register = Register(1);
+    pub const C: Register = Register(2);
+    pub const D: Register = Register(3);
+}
+
+impl TryFrom<&str> for Register {
+    type Error = ParseError;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        match value {
+            "a" => Ok(Self::A),
+            "b" => Ok(Self::B),
+            "c" => Ok(Self::C),
+            "d" => Ok(Self::D),
+            _ => Err(ParseError::new(format!("Not a valid register: '{value}'!"))),
+        }
+    }
+}
+
+impl Display for Register {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", (b'a' + self.0 as u8) as char)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Value(pub i64);
+
+impl Display for Value {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Operand {
+    Register(Register),
+    Value(Value),
+}
+
+impl TryFrom<&str> for Operand {
+    type Error = ParseError;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        if let Ok(v) = value.parse() {
+            return Ok(Self::Value(Value(v)));
+        }
+        Register::try_from(value).map(Self::Register).map_err(|_| {
+            ParseError::new(format!(
+                "Neither an integer value nor a register: '{value}'!"
+            ))
+        })
+    }
+}
+
+impl Display for Operand {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Operand::Register(r) => r.fmt(f),
+            Operand::Value(v) => v.fmt(f),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Instruction {
+    Cpy(Operand, Operand),
+    Inc(Operand),
+    Dec(Operand),
+    Jnz(Operand, Operand),
+    Tgl(Operand),
+    Out(Operand),
+}
+
+impl FromStr for Instruction {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let split: Vec<&str> = s.split_whitespace().collect();
+        let Some((name, operands)) = split.split_first() else {
+            return Err(ParseError::new("Empty instruction!".to_string()));
+        };
+
+        let arity = match *name {
+            "cpy" | "jnz" => 2,
+            "inc" | "dec" | "tgl" | "out" => 1,
+            _ => return Err(ParseError::new(format!("Illegal instruction '{name}'!"))),
+        };
+        if operands.len() != arity {
+            return Err(ParseError::new(format!(
+                "'{name}' expects {arity} operands, got {}!",
+                operands.len()
+            )));
+        }
+
+        let operand = |idx: usize| Operand::try_from(operands[idx]);
+        let register = |idx: usize| Register::try_from(operands[idx]).map(Operand::Register);
+        Ok(match *name {
+            "cpy" => Self::Cpy(operand(0)?, register(1)?),
+            "inc" => Self::Inc(register(0)?),
+            "dec" => Self::Dec(register(0)?),
+            "jnz" => Self::Jnz(operand(0)?, operand(1)?),
+            "tgl" => Self::Tgl(operand(0)?),
+            _ => Self::Out(operand(0)?),
+        })
+    }
+}
+
+impl Display for Instruction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Instruction::Cpy(a, b) => write!(f, "cpy {a} {b}"),
+            Instruction::Inc(a) => write!(f, "inc {a}"),
+            Instruction::Dec(a) => write!(f, "dec {a}"),
+            Instruction::Jnz(a, b) => write!(f, "jnz {a} {b}"),
+            Instruction::Tgl(a) => write!(f, "tgl {a}"),
+            Instruction::Out(a) => write!(f, "out {a}"),
+        }
+    }
+}
+
+impl Instruction {
+    // One argument instructions become `inc`, or `dec` for `inc`, two argument ones become `jnz`,
+    // or `cpy` for `jnz`.
+    pub fn toggle(&self) -> Self {
+        match *self {
+            Instruction::Inc(a) => Instruction::Dec(a),
+            Instruction::Dec(a) | Instruction::Tgl(a) | Instruction::Out(a) => Instruction::Inc(a),
+            Instruction::Jnz(a, b) => Instruction::Cpy(a, b),
+            Instruction::Cpy(a, b) => Instruction::Jnz(a, b),
+        }
+    }
+}
+
+// Blank lines and everything after a '#' are ignored, line numbers in errors count from 1.
+pub fn parse_program(s: &str) -> Result<Vec<Instruction>, ParseError> {
+    s.lines()
+        .enumerate()
+        .map(|(idx, line)| (idx + 1, line.split('#').next().unwrap_or_default().trim()))
+        .filter(|(_, line)| !line.is_empty())
+        .map(|(line, code)| {
+            code.parse()
+                .map_err(|e: ParseError| ParseError { line, ..e })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{parse_program, Instruction, Operand, Register, Value};
+
+    #[test]
+    fn test_examples() {
+        let program = parse_program(
+            "\
+            cpy 41 a\n\
+            \n\
+            # a comment\n\
+            jnz a -2 # trailing comment\n\
+            ",
+        )
+        .unwrap();
+        assert_eq!(
+            program,
+            [
+                Instruction::Cpy(Operand::Value(Value(41)), Operand::Register(Register::A)),
+                Instruction::Jnz(Operand::Register(Register::A), Operand::Value(Value(-2))),
+            ]
+        );
+
+        let text = "cpy 2 a\ntgl a\nout b\njnz c d\ninc a\ndec d\n";
+        let program = parse_program(text).unwrap();
+        let printed: Vec<String> = program.iter().map(|i| i.to_string()).collect();
+        assert_eq!(printed.join("\n") + "\n", text);
+
+        let toggled: Vec<String> = program.iter().map(|i| i.toggle().to_string()).collect();
+        assert_eq!(
+            toggled,
+            ["jnz 2 a", "inc a", "inc b", "cpy c d", "dec a", "inc d"]
+        );
+    }
+
+    #[test]
+    fn test_errors() {
+        let error = |s: &str| parse_program(s).unwrap_err().to_string();
+
+        assert_eq!(
+            error("inc a\n\nfoo a"),
+            "line 3: Illegal instruction 'foo'!"
+        );
+        assert_eq!(error("cpy 1"), "line 1: 'cpy' expects 2 operands, got 1!");
+        assert_eq!(error("inc a\ninc 1"), "line 2: Not a valid register: '1'!");
+        assert_eq!(error("cpy 1 2"), "line 1: Not a valid register: '2'!");
+        assert_eq!(
+            error("jnz e 2"),
+            "line 1: Neither an integer value nor a register: 'e'!"
+        );
+        assert_eq!(error("out 1 2"), "line 1: 'out' expects 1 operands, got 2!");
+    }
+}
@@ -0,0 +1,7 @@
+mod instruction;
+mod machine;
+mod optimizer;
+
+pub use instruction::{parse_program, Instruction, Operand, ParseError, Register, Value};
+pub use machine::{Machine, Output, State};
+pub use optimizer::{optimize, MacroOp, Region};
@@ -0,0 +1,176 @@
+use crate::{optimize, Instruction, Operand, Region};
+
+pub trait Output {
+    // Returning false stops the machine.
+    fn write(&mut self, value: i64) -> bool;
+}
+
+impl Output for Vec<i64> {
+    fn write(&mut self, value: i64) -> bool {
+        self.push(value);
+        true
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum State {
+    Initialized,
+    Running,
+    Stopped,
+    Terminated,
+}
+
+#[derive(Debug, Clone)]
+pub struct Machine<T: Output = Vec<i64>> {
+    pub registers: [i64; 4],
+    pub instructions: Vec<Instruction>,
+    pub program_counter: usize,
+    pub state: State,
+    pub steps: usize,
+    pub output: T,
+    pub regions: Vec<Region>,
+    optimized: bool,
+}
+
+impl Machine {
+    pub fn new(instructions: Vec<Instruction>) -> Self {
+        Self::with_output(instructions, Vec::new())
+    }
+}
+
+impl<T: Output> Machine<T> {
+    pub fn with_output(instructions: Vec<Instruction>, output: T) -> Self {
+        Self {
+            registers: [0; 4],
+            instructions,
+            program_counter: 0,
+            state: State::Initialized,
+            steps: 0,
+            output,
+            regions: Vec::new(),
+            optimized: false,
+        }
+    }
+
+    // Replaces counting loops by macro-ops, see `optimize`. Toggles re-run the analysis, so
+    // regions hit by a `tgl` fall back to plain instructions.
+    pub fn optimize(&mut self) {
+        self.optimized = true;
+        self.regions = optimize(&self.instructions);
+    }
+
+    pub fn value(&self, operand: Operand) -> i64 {
+        match operand {
+            Operand::Register(r) => self.registers[r.0],
+            Operand::Value(v) => v.0,
+        }
+    }
+
+    // Toggling can produce nonsense like `cpy 1 2`, which is skipped.
+    pub fn step(&mut self) {
+        let Some(instruction) = self.instructions.get(self.program_counter).copied() else {
+            self.state = State::Terminated;
+            return;
+        };
+        self.steps += 1;
+
+        if let Ok(idx) = self
+            .regions
+            .binary_search_by_key(&self.program_counter, |r| r.start)
+        {
+            let region = self.regions[idx];
+            if region.applies(&self.registers) {
+                region.apply(&mut self.registers);
+                self.program_counter += region.len;
+                return;
+            }
+        }
+
+        let mut offset = 1;
+        match instruction {
+            Instruction::Cpy(src, Operand::Register(dst)) => {
+                self.registers[dst.0] = self.value(src);
+            }
+            Instruction::Inc(Operand::Register(r)) => self.registers[r.0] += 1,
+            Instruction::Dec(Operand::Register(r)) => self.registers[r.0] -= 1,
+            Instruction::Jnz(condition, jump) if self.value(condition) != 0 => {
+                offset = self.value(jump);
+            }
+            Instruction::Tgl(op) => {
+                let target = self.program_counter as i64 + self.value(op);
+                if let Some(instruction) = usize::try_from(target)
+                    .ok()
+                    .and_then(|target| self.instructions.get_mut(target))
+                {
+                    *instruction = instruction.toggle();
+                    if self.optimized {
+                        self.regions = optimize(&self.instructions);
+                    }
+                }
+            }
+            Instruction::Out(op) => {
+                let value = self.value(op);
+                if !self.output.write(value) {
+                    self.state = State::Stopped;
+                }
+            }
+            _ => (),
+        }
+
+        match usize::try_from(self.program_counter as i64 + offset) {
+            Ok(program_counter) => self.program_counter = program_counter,
+            Err(_) => self.state = State::Terminated,
+        }
+    }
+
+    pub fn run(&mut self) {
+        self.state = State::Running;
+        while self.state == State::Running {
+            self.step();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{parse_program, Machine, Output, Register, State};
+
+    #[derive(Debug, Default)]
+    struct FirstThree(Vec<i64>);
+
+    impl Output for FirstThree {
+        fn write(&mut self, value: i64) -> bool {
+            self.0.push(value);
+            self.0.len() < 3
+        }
+    }
+
+    #[test]
+    fn test_examples() {
+        let program = parse_program("cpy 41 a\ninc a\ninc a\ndec a\njnz a 2\ndec a").unwrap();
+        let mut machine = Machine::new(program);
+        machine.run();
+        assert_eq!(machine.state, State::Terminated);
+        assert_eq!(machine.registers[Register::A.0], 42);
+        assert_eq!(machine.steps, 5);
+
+        let program = parse_program("cpy 2 a\ntgl a\ntgl a\ntgl a\ncpy 1 a\ndec a\ndec a").unwrap();
+        let mut machine = Machine::new(program);
+        machine.run();
+        assert_eq!(machine.state, State::Terminated);
+        assert_eq!(machine.registers[Register::A.0], 3);
+        assert_eq!(machine.instructions[3].to_string(), "inc a");
+        assert_eq!(machine.instructions[4].to_string(), "jnz 1 a");
+
+        let program = parse_program("cpy 10 b\nout b\njnz 1 -1").unwrap();
+        let mut machine = Machine::with_output(program, FirstThree::default());
+        machine.run();
+        assert_eq!(machine.state, State::Stopped);
+        assert_eq!(machine.output.0, [10, 10, 10]);
+
+        let mut machine = Machine::new(parse_program("out 7\njnz 1 -2").unwrap());
+        machine.run();
+        assert_eq!(machine.state, State::Terminated);
+        assert_eq!(machine.output, [7]);
+    }
+}
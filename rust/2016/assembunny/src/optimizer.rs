@@ -0,0 +1,315 @@
+use std::fmt::Display;
+
+use crate::{Instruction, Operand, Register, Value};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MacroOp {
+    // target += sign * counter, counter = 0
+    Add {
+        target: Register,
+        counter: Register,
+        sign: i64,
+    },
+    // target += sign * factor * outer, inner = 0, outer = 0
+    Mul {
+        target: Register,
+        factor: Operand,
+        inner: Register,
+        outer: Register,
+        sign: i64,
+    },
+}
+
+impl Display for MacroOp {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let op = |sign: i64| if sign < 0 { "-=" } else { "+=" };
+        match self {
+            MacroOp::Add {
+                target,
+                counter,
+                sign,
+            } => write!(f, "{target} {} {counter}", op(*sign)),
+            MacroOp::Mul {
+                target,
+                factor,
+                outer,
+                sign,
+                ..
+            } => write!(f, "{target} {} {factor} * {outer}", op(*sign)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Region {
+    pub start: usize,
+    pub len: usize,
+    pub op: MacroOp,
+}
+
+impl Display for Region {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{:04}..{:04}: {}",
+            self.start,
+            self.start + self.len,
+            self.op
+        )
+    }
+}
+
+impl Region {
+    // The loops only end if their counters start out positive, otherwise the region runs as is.
+    pub fn applies(&self, registers: &[i64; 4]) -> bool {
+        let value = |operand: Operand| match operand {
+            Operand::Register(r) => registers[r.0],
+            Operand::Value(v) => v.0,
+        };
+        match self.op {
+            MacroOp::Add { counter, .. } => registers[counter.0] > 0,
+            MacroOp::Mul { factor, outer, .. } => value(factor) > 0 && registers[outer.0] > 0,
+        }
+    }
+
+    pub fn apply(&self, registers: &mut [i64; 4]) {
+        match self.op {
+            MacroOp::Add {
+                target,
+                counter,
+                sign,
+            } => {
+                registers[target.0] += sign * registers[counter.0];
+                registers[counter.0] = 0;
+            }
+            MacroOp::Mul {
+                target,
+                factor,
+                inner,
+                outer,
+                sign,
+            } => {
+                let factor = match factor {
+                    Operand::Register(r) => registers[r.0],
+                    Operand::Value(v) => v.0,
+                };
+                registers[target.0] += sign * factor * registers[outer.0];
+                registers[inner.0] = 0;
+                registers[outer.0] = 0;
+            }
+        }
+    }
+}
+
+fn jump_back(instruction: Instruction, offset: i64) -> Option<Register> {
+    match instruction {
+        Instruction::Jnz(Operand::Register(r), Operand::Value(Value(o))) if o == -offset => Some(r),
+        _ => None,
+    }
+}
+
+// inc/dec target, dec counter, jnz counter -2, in either order of the first two.
+fn find_add(instructions: &[Instruction]) -> Option<MacroOp> {
+    let [a, b, jump, ..] = instructions else {
+        return None;
+    };
+    let counter = jump_back(*jump, 2)?;
+    let counted =
+        |instruction: &Instruction| *instruction == Instruction::Dec(Operand::Register(counter));
+    let step = match (counted(a), counted(b)) {
+        (true, _) => b,
+        (_, true) => a,
+        _ => return None,
+    };
+
+    let (target, sign) = match *step {
+        Instruction::Inc(Operand::Register(r)) => (r, 1),
+        Instruction::Dec(Operand::Register(r)) => (r, -1),
+        _ => return None,
+    };
+    (target != counter).then_some(MacroOp::Add {
+        target,
+        counter,
+        sign,
+    })
+}
+
+// cpy factor inner, an add loop counting inner down, dec outer, jnz outer -5.
+fn find_mul(instructions: &[Instruction]) -> Option<MacroOp> {
+    let [Instruction::Cpy(factor, Operand::Register(inner)), _, _, _, Instruction::Dec(Operand::Register(outer)), jump, ..] =
+        *instructions
+    else {
+        return None;
+    };
+    let MacroOp::Add {
+        target,
+        counter,
+        sign,
+    } = find_add(&instructions[1..])?
+    else {
+        return None;
+    };
+    if counter != inner || jump_back(jump, 5)? != outer {
+        return None;
+    }
+
+    let registers = [target, inner, outer];
+    let distinct = target != inner && target != outer && inner != outer;
+    let independent = match factor {
+        Operand::Register(r) => !registers.contains(&r),
+        Operand::Value(_) => true,
+    };
+    (distinct && independent).then_some(MacroOp::Mul {
+        target,
+        factor,
+        inner,
+        outer,
+        sign,
+    })
+}
+
+pub fn optimize(instructions: &[Instruction]) -> Vec<Region> {
+    (0..instructions.len())
+        .filter_map(|start| {
+            let rest = &instructions[start..];
+            find_mul(rest)
+                .map(|op| Region { start, len: 6, op })
+                .or_else(|| find_add(rest).map(|op| Region { start, len: 3, op }))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{optimize, parse_program, Machine, Output, Register, State};
+
+    #[derive(Debug, Default, PartialEq)]
+    struct Take(usize, Vec<i64>);
+
+    impl Output for Take {
+        fn write(&mut self, value: i64) -> bool {
+            self.1.push(value);
+            self.1.len() < self.0
+        }
+    }
+
+    fn compare(program: &str, register: Register, value: i64) {
+        let instructions = parse_program(program).unwrap();
+        let run = |optimized: bool| {
+            let mut machine = Machine::with_output(instructions.clone(), Take(20, Vec::new()));
+            if optimized {
+                machine.optimize();
+            }
+            machine.registers[register.0] = value;
+            machine.run();
+            machine
+        };
+
+        let (plain, optimized) = (run(false), run(true));
+        assert_eq!(plain.registers, optimized.registers);
+        assert_eq!(plain.instructions, optimized.instructions);
+        assert_eq!(plain.program_counter, optimized.program_counter);
+        assert_eq!(plain.state, optimized.state);
+        assert_eq!(plain.output, optimized.output);
+        assert!(plain.steps >= optimized.steps);
+    }
+
+    #[test]
+    fn test_examples() {
+        let program = "\
+            cpy 3 b\n\
+            cpy 4 d\n\
+            cpy b c\n\
+            inc a\n\
+            dec c\n\
+            jnz c -2\n\
+            dec d\n\
+            jnz d -5\n\
+            dec a\n\
+            dec b\n\
+            jnz b -2\n\
+        ";
+        let regions: Vec<String> = optimize(&parse_program(program).unwrap())
+            .iter()
+            .map(|r| r.to_string())
+            .collect();
+        assert_eq!(
+            regions,
+            [
+                "0002..0008: a += b * d",
+                "0003..0006: a += c",
+                "0008..0011: a -= b"
+            ]
+        );
+        let mut machine = Machine::new(parse_program(program).unwrap());
+        machine.optimize();
+        machine.run();
+        assert_eq!(machine.registers, [9, 0, 0, 0]);
+        assert_eq!(machine.steps, 4);
+
+        compare(program, Register::A, 0);
+        compare(program, Register::A, 5);
+
+        // Entering a loop halfway runs it as is until it gets back to the start.
+        compare("cpy 2 b\njnz 1 2\ninc a\ndec b\njnz b -2", Register::A, 0);
+
+        // Counters that don't start out positive would underflow, so those loops aren't replaced.
+        let regions = optimize(&parse_program(program).unwrap());
+        assert!(!regions[0].applies(&[0, 3, 0, 0]));
+        assert!(regions[0].applies(&[0, 3, 0, 1]));
+        assert!(!regions[2].applies(&[0, 0, 0, 0]));
+        assert!(!regions[2].applies(&[0, -1, 0, 0]));
+
+        // Not loops: jumping on the target, counting the target or multiplying by the counter.
+        let program = "inc a\ndec b\njnz a -2\ninc a\ndec a\njnz a -2\ncpy c c\ninc a\ndec c\njnz c -2\ndec d\njnz d -5";
+        let regions = optimize(&parse_program(program).unwrap());
+        assert_eq!(regions.len(), 1);
+        assert_eq!(regions[0].to_string(), "0007..0010: a += c");
+
+        // Toggling an instruction inside a region turns it back into plain code.
+        let program = "\
+            cpy 3 a\n\
+            cpy 3 b\n\
+            tgl a\n\
+            inc c\n\
+            dec b\n\
+            jnz b -2\n\
+            out c\n\
+        ";
+        compare(program, Register::A, 0);
+        let mut machine = Machine::new(parse_program(program).unwrap());
+        machine.optimize();
+        assert_eq!(machine.regions.len(), 1);
+        machine.run();
+        assert!(machine.regions.is_empty());
+        assert_eq!(machine.output, [1]);
+
+        // And a toggle can just as well create a new region.
+        let program = "cpy 3 b\ntgl b\ncpy 5 c\ninc a\ninc c\njnz c -2";
+        compare(program, Register::A, 0);
+        let mut machine = Machine::new(parse_program(program).unwrap());
+        machine.optimize();
+        assert!(machine.regions.is_empty());
+        machine.run();
+        assert_eq!(machine.regions.len(), 1);
+        assert_eq!(machine.registers, [5, 3, 0, 0]);
+        assert_eq!(machine.state, State::Terminated);
+    }
+
+    #[test]
+    fn test_input() {
+        let program = |day: &str| {
+            std::fs::read_to_string(format!("../{day}/input/instructions.txt")).unwrap()
+        };
+
+        compare(&program("day_12"), Register::C, 0);
+        compare(&program("day_12"), Register::C, 1);
+        // Smaller eggs leave the last loop untoggled, which never ends.
+        for a in 6..=9 {
+            compare(&program("day_23"), Register::A, a);
+        }
+        for a in [0, 1, 2, 189] {
+            compare(&program("day_25"), Register::A, a);
+        }
+    }
+}
@@ -0,0 +1,23 @@
+use elfcode::Pseudocode;
+
+fn main() {
+    let Some(path) = std::env::args().nth(1) else {
+        eprintln!("Usage: decompile <program file>");
+        std::process::exit(1);
+    };
+    let program = match std::fs::read_to_string(&path) {
+        Ok(program) => program,
+        Err(e) => {
+            eprintln!("ERR: Failed to read '{path}': {e}");
+            std::process::exit(1);
+        }
+    };
+
+    match program.parse::<Pseudocode>() {
+        Ok(pseudocode) => print!("{pseudocode}"),
+        Err(e) => {
+            eprintln!("ERR: {e}");
+            std::process::exit(1);
+        }
+    }
+}
@@ -0,0 +1,190 @@
+use std::str::FromStr;
+
+use crate::{OpCode, ParseError, Register};
+
+pub const NUM_REGISTERS: usize = 6;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Computer {
+    pub binding: Option<Register>,
+    pub ip: i64,
+    pub registers: [i64; NUM_REGISTERS],
+    pub instructions: Vec<OpCode>,
+}
+
+// One instruction per line, optionally bound to a register by an `#ip <register>` directive.
+// Blank lines are skipped, line numbers in errors count from 1.
+impl FromStr for Computer {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut binding = None;
+        let mut instructions = Vec::new();
+
+        for (idx, line) in s.lines().enumerate() {
+            let with_line = |e: ParseError| ParseError { line: idx + 1, ..e };
+            let line = line.trim();
+            if line.is_empty() {
+                continue;
+            }
+
+            if let Some(register) = line.strip_prefix("#ip ") {
+                if binding.is_some() {
+                    return Err(with_line(ParseError::new(
+                        "Duplicate #ip directive!".to_string(),
+                    )));
+                }
+                let register = register.trim().parse::<i64>().map_err(|_| {
+                    with_line(ParseError::new(format!("Not an integer: '{register}'!")))
+                })?;
+                binding = Some(Register::try_from(register).map_err(with_line)?);
+            } else {
+                instructions.push(line.parse().map_err(with_line)?);
+            }
+        }
+
+        Ok(Self::new(binding, instructions))
+    }
+}
+
+impl Computer {
+    pub fn new(binding: Option<Register>, instructions: Vec<OpCode>) -> Self {
+        Self {
+            binding,
+            ip: 0,
+            registers: [0; NUM_REGISTERS],
+            instructions,
+        }
+    }
+
+    pub fn reset(&mut self, r0: i64) {
+        self.ip = 0;
+        self.registers = [0; NUM_REGISTERS];
+        self.registers[0] = r0;
+    }
+
+    // A negative instruction pointer wraps around to a huge index, which halts the program as well.
+    pub fn current(&self) -> Option<OpCode> {
+        self.instructions.get(self.ip as usize).copied()
+    }
+
+    pub fn step(&mut self) -> bool {
+        match self.current() {
+            Some(op) => {
+                self.execute(op);
+                true
+            }
+            None => false,
+        }
+    }
+
+    // The bound register mirrors the instruction pointer, so writing to it is a jump.
+    pub(crate) fn execute(&mut self, op: OpCode) {
+        match self.binding {
+            Some(r) => {
+                self.registers[r.0] = self.ip;
+                op.eval(&mut self.registers);
+                self.ip = self.registers[r.0] + 1;
+                self.registers[r.0] = self.ip;
+            }
+            None => {
+                op.eval(&mut self.registers);
+                self.ip += 1;
+            }
+        }
+    }
+
+    // Runs until the program halts or `watch` returns true, which is checked before every
+    // instruction. Returns the number of executed instructions.
+    pub fn eval<F>(&mut self, mut watch: F) -> usize
+    where
+        F: FnMut(&[i64; NUM_REGISTERS]) -> bool,
+    {
+        let mut steps = 0;
+        while let Some(&op) = self.instructions.get(self.ip as usize) {
+            if watch(&self.registers) {
+                break;
+            }
+            self.execute(op);
+            steps += 1;
+        }
+        steps
+    }
+
+    pub fn run(&mut self) -> usize {
+        self.eval(|_| false)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Computer, Register};
+
+    const EXAMPLE: &str = "\
+        #ip 0\n\
+        seti 5 0 1\n\
+        seti 6 0 2\n\
+        addi 0 1 0\n\
+        addr 1 2 3\n\
+        setr 1 0 0\n\
+        seti 8 0 4\n\
+        seti 9 0 5\
+    ";
+
+    #[test]
+    fn test_examples() {
+        let mut com: Computer = EXAMPLE.parse().unwrap();
+        assert_eq!(com.binding, Some(Register(0)));
+        assert_eq!(com.instructions.len(), 7);
+
+        assert_eq!(com.run(), 5);
+        assert_eq!(com.registers, [7, 5, 6, 0, 0, 9]);
+        assert_eq!(com.ip, 7);
+
+        com.reset(0);
+        let mut seen = Vec::new();
+        let steps = com.eval(|regs| {
+            seen.push(regs[0]);
+            regs[0] == 6
+        });
+        assert_eq!(steps, 4);
+        assert_eq!(seen, [0, 1, 2, 4, 6]);
+        assert_eq!(com.current().unwrap().to_string(), "seti 9 0 5");
+
+        // Without a binding the instructions simply run in order.
+        let mut com: Computer = "seti 5 0 1\naddi 1 1 0\n\nmulr 0 1 0".parse().unwrap();
+        assert_eq!(com.binding, None);
+        assert_eq!(com.run(), 3);
+        assert_eq!(com.registers, [30, 5, 0, 0, 0, 0]);
+        assert!(!com.step());
+    }
+
+    #[test]
+    fn test_errors() {
+        let error = |s: &str| s.parse::<Computer>().unwrap_err().to_string();
+
+        assert_eq!(error("#ip 0\n#ip 1"), "line 2: Duplicate #ip directive!");
+        assert_eq!(error("#ip x"), "line 1: Not an integer: 'x'!");
+        assert_eq!(
+            error("#ip 6"),
+            "line 1: Register 6 is not a legal register!"
+        );
+        assert_eq!(
+            error("seti 1 2 3\n\nfoo 1 2 3"),
+            "line 3: Illegal instruction: 'foo'!"
+        );
+    }
+
+    #[test]
+    fn test_input() {
+        let program = std::fs::read_to_string("../day_19/input/program.txt").unwrap();
+        let mut com: Computer = program.parse().unwrap();
+        com.run();
+        assert_eq!(com.registers[0], 878);
+
+        let program = std::fs::read_to_string("../day_21/input/program.txt").unwrap();
+        let mut com: Computer = program.parse().unwrap();
+        com.reset(2159153);
+        assert_eq!(com.run(), 1848);
+    }
+}
@@ -0,0 +1,1065 @@
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap, HashSet},
+    fmt::Display,
+    str::FromStr,
+};
+
+use crate::{Computer, OpCode, ParseError, Register, Value, NUM_REGISTERS};
+
+const PSEUDOCODE_INDENT: usize = 4;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Operand {
+    Register(Register),
+    Value(Value),
+}
+
+impl Display for Operand {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Operand::Register(r) => write!(f, "r{}", r.0),
+            Operand::Value(v) => write!(f, "{}", v.0),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Expression {
+    pub lhs: Operand,
+    pub op: Option<&'static str>,
+    pub rhs: Operand,
+}
+
+impl Display for Expression {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match (self.op, self.rhs) {
+            (Some("+"), Operand::Value(Value(v))) if v < 0 => write!(f, "{} - {}", self.lhs, -v),
+            (Some(op), _) => write!(f, "{} {op} {}", self.lhs, self.rhs),
+            (None, _) => write!(f, "{}", self.lhs),
+        }
+    }
+}
+
+impl Expression {
+    fn new(lhs: Operand, op: &'static str, rhs: Operand) -> Self {
+        Self {
+            lhs,
+            op: Some(op),
+            rhs,
+        }
+    }
+
+    fn copy(lhs: Operand) -> Self {
+        Self {
+            lhs,
+            op: None,
+            rhs: lhs,
+        }
+    }
+
+    fn is_comparison(&self) -> bool {
+        matches!(self.op, Some(">" | "==" | "<=" | "!="))
+    }
+
+    fn negate(&self) -> Self {
+        let op = match self.op {
+            Some(">") => "<=",
+            Some("<=") => ">",
+            Some("==") => "!=",
+            Some("!=") => "==",
+            _ => panic!("Can't negate '{self}'!"),
+        };
+        Self::new(self.lhs, op, self.rhs)
+    }
+
+    fn registers(&self) -> impl Iterator<Item = usize> {
+        [self.lhs, self.rhs].into_iter().filter_map(|o| match o {
+            Operand::Register(r) => Some(r.0),
+            Operand::Value(_) => None,
+        })
+    }
+
+    fn evaluate(&self) -> Option<i64> {
+        let (Operand::Value(a), Operand::Value(b)) = (self.lhs, self.rhs) else {
+            return None;
+        };
+        Some(match self.op {
+            None => a.0,
+            Some("+") => a.0 + b.0,
+            Some("*") => a.0 * b.0,
+            Some("&") => a.0 & b.0,
+            Some("|") => a.0 | b.0,
+            Some(">") => i64::from(a.0 > b.0),
+            Some("==") => i64::from(a.0 == b.0),
+            Some(op) => panic!("Unknown operator '{op}'!"),
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Assignment {
+    pub target: Register,
+    pub expression: Expression,
+}
+
+impl Display for Assignment {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.expression.is_comparison() {
+            write!(f, "r{} = {} ? 1 : 0", self.target.0, self.expression)
+        } else {
+            write!(f, "r{} = {}", self.target.0, self.expression)
+        }
+    }
+}
+
+// Block addresses, None stands for leaving the program.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Exit {
+    Goto(Option<usize>),
+    // Condition, the target if it holds and the one if it doesn't.
+    Branch(Expression, Option<usize>, Option<usize>),
+    // The instruction pointer is computed at runtime, one past the expression.
+    Indirect(Expression),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Block {
+    pub start: usize,
+    pub end: usize,
+    pub assignments: Vec<Assignment>,
+    pub exit: Exit,
+}
+
+impl Block {
+    fn successors(&self) -> Vec<usize> {
+        match self.exit {
+            Exit::Goto(t) => t.into_iter().collect(),
+            Exit::Branch(_, a, b) => a.into_iter().chain(b).collect(),
+            Exit::Indirect(_) => Vec::new(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Statement {
+    Assign(Assignment),
+    If(Expression, Vec<Statement>, Vec<Statement>),
+    // Loops are known by the address of their header block.
+    Loop(usize, Vec<Statement>),
+    Break(usize),
+    Continue(usize),
+    Label(usize),
+    Goto(usize),
+    Jump(Expression),
+    Halt,
+}
+
+impl Statement {
+    fn diverges(&self) -> bool {
+        matches!(
+            self,
+            Statement::Break(_)
+                | Statement::Continue(_)
+                | Statement::Goto(_)
+                | Statement::Jump(_)
+                | Statement::Halt
+        )
+    }
+}
+
+enum Control {
+    Next,
+    Jump(i64),
+    Skip(Register),
+    Indirect(Expression),
+}
+
+// Reads of the bound register are replaced by the address of the instruction, writes to it are
+// turned into control flow.
+fn translate(op: OpCode, pc: usize, binding: Option<Register>) -> (Option<Assignment>, Control) {
+    let read = |r: Register| match binding {
+        Some(ip) if ip == r => Operand::Value(Value(pc as i64)),
+        _ => Operand::Register(r),
+    };
+    let (expression, target) = match op {
+        OpCode::Addr(a, b, c) => (Expression::new(read(a), "+", read(b)), c),
+        OpCode::Addi(a, b, c) => (Expression::new(read(a), "+", Operand::Value(b)), c),
+        OpCode::Mulr(a, b, c) => (Expression::new(read(a), "*", read(b)), c),
+        OpCode::Muli(a, b, c) => (Expression::new(read(a), "*", Operand::Value(b)), c),
+        OpCode::Banr(a, b, c) => (Expression::new(read(a), "&", read(b)), c),
+        OpCode::Bani(a, b, c) => (Expression::new(read(a), "&", Operand::Value(b)), c),
+        OpCode::Borr(a, b, c) => (Expression::new(read(a), "|", read(b)), c),
+        OpCode::Bori(a, b, c) => (Expression::new(read(a), "|", Operand::Value(b)), c),
+        OpCode::Setr(a, _, c) => (Expression::copy(read(a)), c),
+        OpCode::Seti(a, _, c) => (Expression::copy(Operand::Value(a)), c),
+        OpCode::Gtir(a, b, c) => (Expression::new(Operand::Value(a), ">", read(b)), c),
+        OpCode::Gtri(a, b, c) => (Expression::new(read(a), ">", Operand::Value(b)), c),
+        OpCode::Gtrr(a, b, c) => (Expression::new(read(a), ">", read(b)), c),
+        OpCode::Eqir(a, b, c) => (Expression::new(Operand::Value(a), "==", read(b)), c),
+        OpCode::Eqri(a, b, c) => (Expression::new(read(a), "==", Operand::Value(b)), c),
+        OpCode::Eqrr(a, b, c) => (Expression::new(read(a), "==", read(b)), c),
+    };
+
+    if binding != Some(target) {
+        return (Some(Assignment { target, expression }), Control::Next);
+    }
+    // `ip = ip + flag` skips the next instruction if the flag comes from a comparison.
+    if let OpCode::Addr(a, b, _) = op {
+        match (binding == Some(a), binding == Some(b)) {
+            (true, false) => return (None, Control::Skip(b)),
+            (false, true) => return (None, Control::Skip(a)),
+            _ => (),
+        }
+    }
+    match expression.evaluate() {
+        Some(value) => (None, Control::Jump(value + 1)),
+        None => (None, Control::Indirect(expression)),
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Pseudocode {
+    pub entry: Option<usize>,
+    pub blocks: BTreeMap<usize, Block>,
+    statements: Vec<Statement>,
+}
+
+impl From<&Computer> for Pseudocode {
+    fn from(computer: &Computer) -> Self {
+        let blocks = basic_blocks(computer);
+        let (entry, blocks) = thread_jumps(Some(0).filter(|_| !blocks.is_empty()), blocks);
+        let blocks = fold_conditions(blocks);
+
+        let mut structurer = Structurer::new(entry, &blocks);
+        let mut statements = Vec::new();
+        structurer.sequence(entry, None, &mut Vec::new(), &mut statements);
+        for fragment in structurer.fragments.clone() {
+            if !structurer.emitted.contains(&fragment) {
+                structurer.sequence(Some(fragment), None, &mut Vec::new(), &mut statements);
+            }
+        }
+
+        let mut gotos: HashSet<usize> = structurer.fragments.iter().copied().collect();
+        collect_gotos(&statements, &mut gotos);
+        let statements = simplify(statements, &gotos);
+
+        Self {
+            entry,
+            blocks,
+            statements,
+        }
+    }
+}
+
+impl FromStr for Pseudocode {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(Self::from(&s.parse::<Computer>()?))
+    }
+}
+
+fn basic_blocks(computer: &Computer) -> BTreeMap<usize, Block> {
+    let len = computer.instructions.len();
+    let translated: Vec<(Option<Assignment>, Control)> = computer
+        .instructions
+        .iter()
+        .enumerate()
+        .map(|(pc, op)| translate(*op, pc, computer.binding))
+        .collect();
+    let address = |target: i64| usize::try_from(target).ok().filter(|t| *t < len);
+
+    let mut leaders = BTreeSet::from([0]);
+    for (pc, (_, control)) in translated.iter().enumerate() {
+        match control {
+            Control::Next => continue,
+            Control::Jump(target) => leaders.extend(address(*target)),
+            Control::Skip(_) => leaders.extend([pc + 2]),
+            Control::Indirect(_) => (),
+        }
+        leaders.insert(pc + 1);
+    }
+
+    let mut blocks = BTreeMap::new();
+    let mut start = 0;
+    let mut assignments = Vec::new();
+    for (pc, (assignment, control)) in translated.into_iter().enumerate() {
+        assignments.extend(assignment);
+        let exit = match control {
+            Control::Next if leaders.contains(&(pc + 1)) || pc + 1 == len => {
+                Exit::Goto(address(pc as i64 + 1))
+            }
+            Control::Next => continue,
+            Control::Jump(target) => Exit::Goto(address(target)),
+            // Only a comparison makes sure the flag is 0 or 1, anything else could jump anywhere.
+            Control::Skip(flag)
+                if assignments
+                    .iter()
+                    .rev()
+                    .find(|a| a.target == flag)
+                    .is_some_and(|a| a.expression.is_comparison()) =>
+            {
+                Exit::Branch(
+                    Expression::new(Operand::Register(flag), "!=", Operand::Value(Value(0))),
+                    address(pc as i64 + 2),
+                    address(pc as i64 + 1),
+                )
+            }
+            Control::Skip(flag) => Exit::Indirect(Expression::new(
+                Operand::Value(Value(pc as i64)),
+                "+",
+                Operand::Register(flag),
+            )),
+            Control::Indirect(expression) => Exit::Indirect(expression),
+        };
+        let block = Block {
+            start,
+            end: pc + 1,
+            assignments: std::mem::take(&mut assignments),
+            exit,
+        };
+        blocks.insert(start, block);
+        start = pc + 1;
+    }
+    blocks
+}
+
+// Blocks that only jump on are bypassed, what's no longer reachable afterwards is dropped. An
+// indirect jump could land anywhere, so it keeps the block right after it and every block that
+// does more than jump on.
+fn thread_jumps(
+    entry: Option<usize>,
+    mut blocks: BTreeMap<usize, Block>,
+) -> (Option<usize>, BTreeMap<usize, Block>) {
+    let resolve = |mut target: Option<usize>| {
+        let mut seen = HashSet::new();
+        while let Some(t) = target {
+            match &blocks[&t] {
+                Block {
+                    assignments,
+                    exit: Exit::Goto(next),
+                    ..
+                } if assignments.is_empty() && seen.insert(t) => target = *next,
+                _ => break,
+            }
+        }
+        target
+    };
+
+    let entry = resolve(entry);
+    let exits: Vec<(usize, Exit)> = blocks
+        .iter()
+        .map(|(start, block)| {
+            let exit = match block.exit {
+                Exit::Goto(t) => Exit::Goto(resolve(t)),
+                Exit::Branch(c, a, b) => Exit::Branch(c, resolve(a), resolve(b)),
+                exit => exit,
+            };
+            (*start, exit)
+        })
+        .collect();
+    for (start, exit) in exits {
+        blocks.get_mut(&start).unwrap().exit = exit;
+    }
+
+    let mut reachable = HashSet::new();
+    let mut todo: Vec<usize> = entry.into_iter().collect();
+    let mut indirect = false;
+    while let Some(start) = todo.pop() {
+        if !reachable.insert(start) {
+            continue;
+        }
+        let block = &blocks[&start];
+        todo.extend(block.successors());
+        if let Exit::Indirect(_) = block.exit {
+            todo.extend(blocks.get(&block.end).map(|b| b.start));
+            if !indirect {
+                indirect = true;
+                todo.extend(
+                    blocks
+                        .values()
+                        .filter(|b| !b.assignments.is_empty() || !matches!(b.exit, Exit::Goto(_)))
+                        .map(|b| b.start),
+                );
+            }
+        }
+    }
+    blocks.retain(|start, _| reachable.contains(start));
+    (entry, blocks)
+}
+
+// Registers read before being written, at the start of every block. Only r0 matters once the
+// program halts.
+fn live_registers(blocks: &BTreeMap<usize, Block>) -> HashMap<usize, HashSet<usize>> {
+    let mut live_in: HashMap<usize, HashSet<usize>> = blocks
+        .keys()
+        .map(|start| (*start, HashSet::new()))
+        .collect();
+
+    loop {
+        let mut changed = false;
+        for (start, block) in blocks.iter().rev() {
+            let mut live = live_out(block, &live_in);
+            for assignment in block.assignments.iter().rev() {
+                live.remove(&assignment.target.0);
+                live.extend(assignment.expression.registers());
+            }
+            if live != live_in[start] {
+                live_in.insert(*start, live);
+                changed = true;
+            }
+        }
+        if !changed {
+            return live_in;
+        }
+    }
+}
+
+fn live_out(block: &Block, live_in: &HashMap<usize, HashSet<usize>>) -> HashSet<usize> {
+    let live = |target: Option<usize>| match target {
+        Some(t) => live_in[&t].clone(),
+        None => HashSet::from([0]),
+    };
+    match block.exit {
+        Exit::Goto(t) => live(t),
+        Exit::Branch(c, a, b) => live(a)
+            .into_iter()
+            .chain(live(b))
+            .chain(c.registers())
+            .collect(),
+        Exit::Indirect(_) => (0..NUM_REGISTERS).collect(),
+    }
+}
+
+// A comparison right before a skip becomes the condition of the branch. The flag itself is only
+// kept if it's read later on.
+fn fold_conditions(mut blocks: BTreeMap<usize, Block>) -> BTreeMap<usize, Block> {
+    let live_in = live_registers(&blocks);
+
+    for block in blocks.values_mut() {
+        let (Exit::Branch(condition, a, b), Some(last)) = (block.exit, block.assignments.last())
+        else {
+            continue;
+        };
+        let flag = last.target;
+        if condition.lhs != Operand::Register(flag) || !last.expression.is_comparison() {
+            continue;
+        }
+
+        let comparison = last.expression;
+        let overwritten = comparison.registers().any(|r| r == flag.0);
+        let needed = [a, b].into_iter().any(|target| match target {
+            Some(t) => live_in[&t].contains(&flag.0),
+            None => flag.0 == 0,
+        });
+
+        if !needed {
+            block.assignments.pop();
+            block.exit = Exit::Branch(comparison, a, b);
+        } else if !overwritten {
+            block.exit = Exit::Branch(comparison, a, b);
+        }
+    }
+    blocks
+}
+
+struct Frame {
+    header: usize,
+    exit: Option<usize>,
+}
+
+struct Structurer<'a> {
+    blocks: &'a BTreeMap<usize, Block>,
+    // Blocks the entry doesn't lead to, only indirect jumps do.
+    fragments: Vec<usize>,
+    order: HashMap<usize, usize>,
+    loops: HashMap<usize, BTreeSet<usize>>,
+    emitted: HashSet<usize>,
+}
+
+impl<'a> Structurer<'a> {
+    fn new(entry: Option<usize>, blocks: &'a BTreeMap<usize, Block>) -> Self {
+        // The entry and the fragments hang off a virtual root, so they share a dominator tree.
+        let root = usize::MAX;
+        let mut postorder = Vec::new();
+        let mut seen = HashSet::new();
+        let mut fragments = Vec::new();
+        for first in entry.into_iter().chain(blocks.keys().copied()) {
+            if !seen.insert(first) {
+                continue;
+            }
+            if Some(first) != entry {
+                fragments.push(first);
+            }
+            let mut stack = vec![(first, 0)];
+            while let Some((start, idx)) = stack.pop() {
+                let successors = blocks[&start].successors();
+                match successors.get(idx) {
+                    Some(next) => {
+                        stack.push((start, idx + 1));
+                        if seen.insert(*next) {
+                            stack.push((*next, 0));
+                        }
+                    }
+                    None => postorder.push(start),
+                }
+            }
+        }
+        postorder.push(root);
+        let rpo: Vec<usize> = postorder.into_iter().rev().collect();
+        let order: HashMap<usize, usize> = rpo.iter().enumerate().map(|(i, s)| (*s, i)).collect();
+
+        let mut predecessors: HashMap<usize, Vec<usize>> = HashMap::new();
+        for first in entry.iter().chain(&fragments) {
+            predecessors.entry(*first).or_default().push(root);
+        }
+        for (start, block) in blocks {
+            for next in block.successors() {
+                predecessors.entry(next).or_default().push(*start);
+            }
+        }
+
+        // Cooper, Harvey and Kennedy's iterative dominators on the reverse postorder.
+        let mut idom = HashMap::from([(root, root)]);
+        let intersect = |idom: &HashMap<usize, usize>, mut a: usize, mut b: usize| {
+            while a != b {
+                while order[&a] > order[&b] {
+                    a = idom[&a];
+                }
+                while order[&b] > order[&a] {
+                    b = idom[&b];
+                }
+            }
+            a
+        };
+        loop {
+            let mut changed = false;
+            for start in rpo.iter().skip(1) {
+                let new = predecessors[start]
+                    .iter()
+                    .filter(|p| idom.contains_key(p))
+                    .copied()
+                    .reduce(|a, b| intersect(&idom, a, b))
+                    .unwrap();
+                if idom.insert(*start, new) != Some(new) {
+                    changed = true;
+                }
+            }
+            if !changed {
+                break;
+            }
+        }
+        let dominates = |a: usize, mut b: usize| loop {
+            if a == b {
+                return true;
+            }
+            if idom[&b] == b {
+                return false;
+            }
+            b = idom[&b];
+        };
+
+        let mut loops: HashMap<usize, BTreeSet<usize>> = HashMap::new();
+        for start in rpo.iter().skip(1) {
+            for header in blocks[start].successors() {
+                if !dominates(header, *start) {
+                    continue;
+                }
+                let body = loops
+                    .entry(header)
+                    .or_insert_with(|| BTreeSet::from([header]));
+                let mut todo = vec![*start];
+                while let Some(b) = todo.pop() {
+                    if body.insert(b) {
+                        todo.extend(predecessors.get(&b).into_iter().flatten());
+                    }
+                }
+            }
+        }
+
+        Self {
+            blocks,
+            fragments,
+            order,
+            loops,
+            emitted: HashSet::new(),
+        }
+    }
+
+    fn sequence(
+        &mut self,
+        mut target: Option<usize>,
+        stop: Option<usize>,
+        frames: &mut Vec<Frame>,
+        out: &mut Vec<Statement>,
+    ) {
+        loop {
+            let Some(start) = target else {
+                out.push(Statement::Halt);
+                return;
+            };
+            if Some(start) == stop {
+                return;
+            }
+            if frames.iter().any(|f| f.header == start) {
+                out.push(Statement::Continue(start));
+                return;
+            }
+            if let Some(frame) = frames.iter().rev().find(|f| f.exit == Some(start)) {
+                out.push(Statement::Break(frame.header));
+                return;
+            }
+            let outside = frames
+                .last()
+                .is_some_and(|f| !self.loops[&f.header].contains(&start));
+            if outside || self.emitted.contains(&start) {
+                out.push(Statement::Goto(start));
+                return;
+            }
+
+            let next = if self.loops.contains_key(&start) {
+                self.repeat(start, frames, out)
+            } else {
+                self.block(start, frames, out)
+            };
+            match next {
+                Some(next) => target = next,
+                None => return,
+            }
+        }
+    }
+
+    // A loop continues with its exit, if there's a single one.
+    fn repeat(
+        &mut self,
+        header: usize,
+        frames: &mut Vec<Frame>,
+        out: &mut Vec<Statement>,
+    ) -> Option<Option<usize>> {
+        let body = &self.loops[&header];
+        let exits: BTreeSet<usize> = body
+            .iter()
+            .flat_map(|b| self.blocks[b].successors())
+            .filter(|b| !body.contains(b))
+            .collect();
+        let exit = (exits.len() == 1).then(|| *exits.first().unwrap());
+
+        frames.push(Frame { header, exit });
+        let mut statements = Vec::new();
+        if let Some(next) = self.block(header, frames, &mut statements) {
+            self.sequence(next, None, frames, &mut statements);
+        }
+        frames.pop();
+        out.push(Statement::Loop(header, statements));
+
+        exit.map(Some)
+    }
+
+    // Emits a block, returns where to go on afterwards unless it ends the sequence.
+    fn block(
+        &mut self,
+        start: usize,
+        frames: &mut Vec<Frame>,
+        out: &mut Vec<Statement>,
+    ) -> Option<Option<usize>> {
+        self.emitted.insert(start);
+        let block = &self.blocks[&start];
+        out.push(Statement::Label(start));
+        out.extend(block.assignments.iter().copied().map(Statement::Assign));
+
+        match block.exit {
+            Exit::Goto(target) => Some(target),
+            Exit::Indirect(expression) => {
+                out.push(Statement::Jump(expression));
+                None
+            }
+            Exit::Branch(condition, taken, otherwise) => {
+                let join = self.join(taken, otherwise, frames);
+                let mut then = Vec::new();
+                self.sequence(taken, join, frames, &mut then);
+                let mut els = Vec::new();
+                self.sequence(otherwise, join, frames, &mut els);
+                out.push(Statement::If(condition, then, els));
+                join.map(Some)
+            }
+        }
+    }
+
+    // The earliest block both sides of a branch lead to without leaving the innermost loop.
+    fn join(&self, a: Option<usize>, b: Option<usize>, frames: &[Frame]) -> Option<usize> {
+        let allowed = |start: usize| {
+            !frames.iter().any(|f| f.header == start)
+                && frames
+                    .last()
+                    .is_none_or(|f| self.loops[&f.header].contains(&start))
+        };
+        let reach = |from: Option<usize>| {
+            let mut seen = HashSet::new();
+            let mut todo: Vec<usize> = from.into_iter().filter(|s| allowed(*s)).collect();
+            while let Some(start) = todo.pop() {
+                if seen.insert(start) {
+                    todo.extend(
+                        self.blocks[&start]
+                            .successors()
+                            .into_iter()
+                            .filter(|s| allowed(*s)),
+                    );
+                }
+            }
+            seen
+        };
+
+        let (a, b) = (reach(a), reach(b));
+        a.intersection(&b).min_by_key(|s| self.order[s]).copied()
+    }
+}
+
+fn collect_gotos(statements: &[Statement], gotos: &mut HashSet<usize>) {
+    for statement in statements {
+        match statement {
+            Statement::Goto(target) => {
+                gotos.insert(*target);
+            }
+            Statement::If(_, then, els) => {
+                collect_gotos(then, gotos);
+                collect_gotos(els, gotos);
+            }
+            Statement::Loop(_, body) => collect_gotos(body, gotos),
+            _ => (),
+        }
+    }
+}
+
+// Drops a `continue` that the loop would do anyway at the end of its body.
+fn strip_continue(statements: &mut Vec<Statement>, header: usize) {
+    match statements.last_mut() {
+        Some(Statement::Continue(h)) if *h == header => {
+            statements.pop();
+        }
+        Some(Statement::If(_, then, els)) => {
+            strip_continue(then, header);
+            strip_continue(els, header);
+        }
+        _ => (),
+    }
+}
+
+fn simplify(statements: Vec<Statement>, gotos: &HashSet<usize>) -> Vec<Statement> {
+    let mut simplified = Vec::new();
+    for statement in statements {
+        match statement {
+            Statement::Label(start) if !gotos.contains(&start) => (),
+            Statement::Loop(h, mut body) => {
+                strip_continue(&mut body, h);
+                simplified.push(Statement::Loop(h, simplify(body, gotos)));
+            }
+            Statement::If(condition, then, els) => {
+                let then = simplify(then, gotos);
+                let els = simplify(els, gotos);
+                match (then.is_empty(), els.is_empty()) {
+                    (true, true) => (),
+                    (true, false) => simplified.push(Statement::If(condition.negate(), els, then)),
+                    (false, false) if then.last().is_some_and(Statement::diverges) => {
+                        simplified.push(Statement::If(condition, then, Vec::new()));
+                        simplified.extend(els);
+                    }
+                    _ => simplified.push(Statement::If(condition, then, els)),
+                }
+            }
+            statement => simplified.push(statement),
+        }
+    }
+    simplified
+}
+
+fn targets_loop(statements: &[Statement], header: usize, nested: bool) -> bool {
+    statements.iter().any(|statement| match statement {
+        Statement::Break(h) | Statement::Continue(h) => nested && *h == header,
+        Statement::If(_, then, els) => {
+            targets_loop(then, header, nested) || targets_loop(els, header, nested)
+        }
+        Statement::Loop(_, body) => targets_loop(body, header, true),
+        _ => false,
+    })
+}
+
+fn render(
+    f: &mut std::fmt::Formatter<'_>,
+    statements: &[Statement],
+    depth: usize,
+    innermost: Option<usize>,
+) -> std::fmt::Result {
+    let indent = " ".repeat(depth * PSEUDOCODE_INDENT);
+    let label = |h: &usize| match innermost {
+        Some(innermost) if innermost == *h => String::new(),
+        _ => format!(" 'l{h}"),
+    };
+
+    for statement in statements {
+        match statement {
+            Statement::Assign(assignment) => writeln!(f, "{indent}{assignment}")?,
+            Statement::If(condition, then, els) => {
+                writeln!(f, "{indent}if {condition} {{")?;
+                render(f, then, depth + 1, innermost)?;
+                if !els.is_empty() {
+                    writeln!(f, "{indent}}} else {{")?;
+                    render(f, els, depth + 1, innermost)?;
+                }
+                writeln!(f, "{indent}}}")?;
+            }
+            Statement::Loop(h, body) => {
+                if targets_loop(body, *h, false) {
+                    writeln!(f, "{indent}'l{h}: loop {{")?;
+                } else {
+                    writeln!(f, "{indent}loop {{")?;
+                }
+                render(f, body, depth + 1, Some(*h))?;
+                writeln!(f, "{indent}}}")?;
+            }
+            Statement::Break(h) => writeln!(f, "{indent}break{}", label(h))?,
+            Statement::Continue(h) => writeln!(f, "{indent}continue{}", label(h))?,
+            Statement::Label(start) => writeln!(f, "{start}:")?,
+            Statement::Goto(start) => writeln!(f, "{indent}goto {start}")?,
+            Statement::Jump(expression) => writeln!(f, "{indent}jump {expression} + 1")?,
+            Statement::Halt => writeln!(f, "{indent}halt")?,
+        }
+    }
+    Ok(())
+}
+
+impl Display for Pseudocode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        render(f, &self.statements, 0, None)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Exit, Pseudocode};
+
+    #[test]
+    fn test_examples() {
+        let program = "\
+            #ip 0\n\
+            seti 5 0 1\n\
+            seti 6 0 2\n\
+            addi 0 1 0\n\
+            addr 1 2 3\n\
+            setr 1 0 0\n\
+            seti 8 0 4\n\
+            seti 9 0 5\
+        ";
+        let pseudocode = program.parse::<Pseudocode>().unwrap();
+        // Whatever the indirect jump may land on is kept as a labelled fragment.
+        assert_eq!(
+            pseudocode.to_string(),
+            "\
+            r1 = 5\n\
+            r2 = 6\n\
+            4:\n\
+            jump r1 + 1\n\
+            3:\n\
+            r3 = r1 + r2\n\
+            goto 4\n\
+            5:\n\
+            r4 = 8\n\
+            r5 = 9\n\
+            halt\n\
+            "
+        );
+        assert_eq!(
+            pseudocode.blocks.keys().collect::<Vec<_>>(),
+            [&0, &3, &4, &5]
+        );
+        assert_eq!(pseudocode.blocks[&0].exit, Exit::Goto(Some(4)));
+
+        // Without a binding there's no control flow at all.
+        let pseudocode = "seti 5 0 1\naddi 1 -1 0\neqrr 0 1 2"
+            .parse::<Pseudocode>()
+            .unwrap();
+        assert_eq!(
+            pseudocode.to_string(),
+            "r1 = 5\nr0 = r1 - 1\nr2 = r0 == r1 ? 1 : 0\nhalt\n"
+        );
+
+        let program = "\
+            #ip 4\n\
+            seti 10 0 1\n\
+            gtri 1 5 2\n\
+            addr 2 4 4\n\
+            seti 6 0 4\n\
+            addi 0 2 0\n\
+            seti 7 0 4\n\
+            addi 3 1 3\n\
+            addi 1 -1 1\n\
+            eqri 1 0 2\n\
+            addr 4 2 4\n\
+            seti 0 0 4\
+        ";
+        assert_eq!(
+            program.parse::<Pseudocode>().unwrap().to_string(),
+            "\
+            r1 = 10\n\
+            loop {\n\
+            \x20   if r1 > 5 {\n\
+            \x20       r0 = r0 + 2\n\
+            \x20   } else {\n\
+            \x20       r1 = r1 - 1\n\
+            \x20   }\n\
+            \x20   if r1 == 0 {\n\
+            \x20       halt\n\
+            \x20   }\n\
+            }\n\
+            "
+        );
+
+        // Jumping into the middle of a loop from two sides can't be structured, gotos remain.
+        let program = "\
+            #ip 5\n\
+            eqri 0 1 1\n\
+            addr 5 1 5\n\
+            seti 3 0 5\n\
+            addi 2 1 2\n\
+            addi 3 1 3\n\
+            gtri 3 5 4\n\
+            addr 4 5 5\n\
+            seti 2 0 5\
+        ";
+        assert_eq!(
+            program.parse::<Pseudocode>().unwrap().to_string(),
+            "\
+            if r0 != 1 {\n\
+            4:\n\
+            \x20   r3 = r3 + 1\n\
+            \x20   if r3 > 5 {\n\
+            \x20       halt\n\
+            \x20   }\n\
+            3:\n\
+            \x20   r2 = r2 + 1\n\
+            \x20   goto 4\n\
+            }\n\
+            goto 3\n\
+            "
+        );
+
+        // Adding anything but a comparison to the instruction pointer isn't a skip.
+        let program = "\
+            #ip 1\n\
+            seti 2 0 0\n\
+            addr 1 0 1\n\
+            seti 7 0 2\n\
+            seti 7 0 3\n\
+            seti 7 0 4\
+        ";
+        let pseudocode = program.parse::<Pseudocode>().unwrap();
+        assert_eq!(
+            pseudocode.to_string(),
+            "r0 = 2\njump 1 + r0 + 1\n2:\nr2 = 7\nr3 = 7\nr4 = 7\nhalt\n"
+        );
+        assert_eq!(pseudocode.blocks.keys().collect::<Vec<_>>(), [&0, &2, &3]);
+        assert!(matches!(pseudocode.blocks[&0].exit, Exit::Indirect(_)));
+
+        assert_eq!(
+            "foo".parse::<Pseudocode>().unwrap_err().to_string(),
+            "line 1: Expected an instruction and 3 operands: 'foo'!"
+        );
+    }
+
+    #[test]
+    fn test_input() {
+        let program = std::fs::read_to_string("../day_21/input/program.txt").unwrap();
+        let pseudocode = program.parse::<Pseudocode>().unwrap();
+        assert_eq!(pseudocode.blocks.len(), 10);
+        assert_eq!(
+            pseudocode.to_string(),
+            "\
+            r1 = 123\n\
+            loop {\n\
+            \x20   r1 = r1 & 456\n\
+            \x20   r1 = r1 == 72 ? 1 : 0\n\
+            \x20   if r1 != 0 {\n\
+            \x20       break\n\
+            \x20   }\n\
+            }\n\
+            r1 = 0\n\
+            loop {\n\
+            \x20   r4 = r1 | 65536\n\
+            \x20   r1 = 12772194\n\
+            \x20   loop {\n\
+            \x20       r3 = r4 & 255\n\
+            \x20       r1 = r1 + r3\n\
+            \x20       r1 = r1 & 16777215\n\
+            \x20       r1 = r1 * 65899\n\
+            \x20       r1 = r1 & 16777215\n\
+            \x20       if 256 > r4 {\n\
+            \x20           break\n\
+            \x20       }\n\
+            \x20       r3 = 0\n\
+            \x20       loop {\n\
+            \x20           r2 = r3 + 1\n\
+            \x20           r2 = r2 * 256\n\
+            \x20           if r2 > r4 {\n\
+            \x20               break\n\
+            \x20           }\n\
+            \x20           r3 = r3 + 1\n\
+            \x20       }\n\
+            \x20       r4 = r3\n\
+            \x20   }\n\
+            \x20   if r1 == r0 {\n\
+            \x20       halt\n\
+            \x20   }\n\
+            }\n\
+            "
+        );
+
+        // r0 picks the setup by skipping ahead, but nothing limits it to 0 or 1. Both setups and
+        // the divisor sum are only reached through that jump.
+        let program = std::fs::read_to_string("../day_19/input/program.txt").unwrap();
+        assert_eq!(
+            program.parse::<Pseudocode>().unwrap().to_string(),
+            "\
+            r2 = r2 + 2\n\
+            r2 = r2 * r2\n\
+            r2 = 19 * r2\n\
+            r2 = r2 * 11\n\
+            r4 = r4 + 1\n\
+            r4 = r4 * 22\n\
+            r4 = r4 + 19\n\
+            r2 = r2 + r4\n\
+            jump 25 + r0 + 1\n\
+            1:\n\
+            r3 = 1\n\
+            loop {\n\
+            \x20   r1 = 1\n\
+            \x20   loop {\n\
+            \x20       r4 = r3 * r1\n\
+            \x20       if r4 == r2 {\n\
+            \x20           r0 = r3 + r0\n\
+            \x20       }\n\
+            \x20       r1 = r1 + 1\n\
+            \x20       if r1 > r2 {\n\
+            \x20           break\n\
+            \x20       }\n\
+            \x20   }\n\
+            \x20   r3 = r3 + 1\n\
+            \x20   if r3 > r2 {\n\
+            \x20       halt\n\
+            \x20   }\n\
+            }\n\
+            26:\n\
+            goto 1\n\
+            27:\n\
+            r4 = 27\n\
+            r4 = r4 * 28\n\
+            r4 = 29 + r4\n\
+            r4 = 30 * r4\n\
+            r4 = r4 * 14\n\
+            r4 = r4 * 32\n\
+            r2 = r2 + r4\n\
+            r2 = 10007\n\
+            r0 = 0\n\
+            goto 1\n\
+            "
+        );
+    }
+}
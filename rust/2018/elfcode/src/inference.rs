@@ -0,0 +1,114 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::{OpCode, OP_NAMES};
+
+// A recorded instruction in its numeric form `[op code, a, b, c]` with the four registers before and
+// after it ran.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Sample {
+    pub before: [i64; 4],
+    pub instruction: [i64; 4],
+    pub after: [i64; 4],
+}
+
+pub fn viable_op_codes(sample: &Sample) -> HashSet<&'static str> {
+    let [_, a, b, c] = sample.instruction;
+
+    OP_NAMES
+        .into_iter()
+        .filter_map(|name| OpCode::new(name, a, b, c).ok())
+        .filter(|op| op.registers().iter().all(|r| r.0 < sample.before.len()))
+        .filter(|op| {
+            let mut r = sample.before;
+            op.eval(&mut r);
+            r == sample.after
+        })
+        .map(|op| op.name())
+        .collect()
+}
+
+// Narrows every op code down to the names that fit all of its samples, then repeatedly assigns the
+// op codes left with a single candidate. Returns None if the samples contradict each other or
+// leave the mapping ambiguous.
+pub fn infer_op_codes(samples: &[Sample]) -> Option<HashMap<i64, &'static str>> {
+    let mut candidates: HashMap<i64, HashSet<&'static str>> = HashMap::new();
+    for sample in samples {
+        let viable = viable_op_codes(sample);
+        candidates
+            .entry(sample.instruction[0])
+            .and_modify(|names| names.retain(|name| viable.contains(name)))
+            .or_insert(viable);
+    }
+
+    let mut solved = HashMap::new();
+    while !candidates.is_empty() {
+        let (op_code, name) = candidates
+            .iter()
+            .find(|(_, names)| names.len() == 1)
+            .map(|(op_code, names)| (*op_code, *names.iter().next().unwrap()))?;
+
+        candidates.remove(&op_code);
+        for names in candidates.values_mut() {
+            names.remove(name);
+        }
+        solved.insert(op_code, name);
+    }
+
+    Some(solved)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use crate::{infer_op_codes, viable_op_codes, Sample};
+
+    #[test]
+    fn test_examples() {
+        let sample = Sample {
+            before: [3, 2, 1, 1],
+            instruction: [9, 2, 1, 2],
+            after: [3, 2, 2, 1],
+        };
+        assert_eq!(
+            viable_op_codes(&sample),
+            HashSet::from(["mulr", "addi", "seti"])
+        );
+
+        // Register 4 doesn't exist in a sample, so only the immediate interpretations remain.
+        let sample = Sample {
+            before: [0, 0, 0, 0],
+            instruction: [0, 4, 4, 0],
+            after: [4, 0, 0, 0],
+        };
+        assert_eq!(viable_op_codes(&sample), HashSet::from(["seti"]));
+
+        let samples = [
+            sample,
+            Sample {
+                before: [1, 2, 0, 0],
+                instruction: [1, 0, 1, 3],
+                after: [1, 2, 0, 3],
+            },
+            Sample {
+                before: [1, 2, 0, 0],
+                instruction: [2, 0, 1, 3],
+                after: [1, 2, 0, 2],
+            },
+        ];
+        assert_eq!(infer_op_codes(&samples), None);
+
+        let samples = [
+            samples[0],
+            Sample {
+                before: [2, 3, 0, 0],
+                instruction: [1, 0, 1, 3],
+                after: [2, 3, 0, 5],
+            },
+        ];
+        let solved = infer_op_codes(&samples).unwrap();
+        assert_eq!(solved.len(), 2);
+        assert_eq!(solved[&0], "seti");
+        assert_eq!(solved[&1], "addr");
+    }
+}
@@ -0,0 +1,11 @@
+mod computer;
+mod decompiler;
+mod inference;
+mod opcode;
+mod trace;
+
+pub use computer::{Computer, NUM_REGISTERS};
+pub use decompiler::{Assignment, Block, Exit, Expression, Operand, Pseudocode};
+pub use inference::{infer_op_codes, viable_op_codes, Sample};
+pub use opcode::{OpCode, ParseError, Register, Value, OP_NAMES};
+pub use trace::{Cycle, Record, RingBuffer, Sink, Tracer};
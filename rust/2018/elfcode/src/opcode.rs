@@ -0,0 +1,250 @@
+use std::{fmt::Display, str::FromStr};
+
+use crate::NUM_REGISTERS;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseError {
+    pub line: usize,
+    pub msg: String,
+}
+
+impl Display for ParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "line {}: {}", self.line, self.msg)
+    }
+}
+
+impl ParseError {
+    pub(crate) fn new(msg: String) -> Self {
+        Self { line: 0, msg }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Register(pub usize);
+
+impl TryFrom<i64> for Register {
+    type Error = ParseError;
+
+    fn try_from(value: i64) -> Result<Self, Self::Error> {
+        match usize::try_from(value) {
+            Ok(r) if r < NUM_REGISTERS => Ok(Self(r)),
+            _ => Err(ParseError::new(format!(
+                "Register {value} is not a legal register!"
+            ))),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Value(pub i64);
+
+impl From<i64> for Value {
+    fn from(value: i64) -> Self {
+        Self(value)
+    }
+}
+
+pub const OP_NAMES: [&str; 16] = [
+    "addr", "addi", "mulr", "muli", "banr", "bani", "borr", "bori", "setr", "seti", "gtir", "gtri",
+    "gtrr", "eqir", "eqri", "eqrr",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum OpCode {
+    Addr(Register, Register, Register),
+    Addi(Register, Value, Register),
+    Mulr(Register, Register, Register),
+    Muli(Register, Value, Register),
+    Banr(Register, Register, Register),
+    Bani(Register, Value, Register),
+    Borr(Register, Register, Register),
+    Bori(Register, Value, Register),
+    Setr(Register, Value, Register),
+    Seti(Value, Value, Register),
+    Gtir(Value, Register, Register),
+    Gtri(Register, Value, Register),
+    Gtrr(Register, Register, Register),
+    Eqir(Value, Register, Register),
+    Eqri(Register, Value, Register),
+    Eqrr(Register, Register, Register),
+}
+
+impl FromStr for OpCode {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let split: Vec<&str> = s.split_whitespace().collect();
+        let [name, a, b, c] = split[..] else {
+            return Err(ParseError::new(format!(
+                "Expected an instruction and 3 operands: '{s}'!"
+            )));
+        };
+
+        let operand = |o: &str| {
+            o.parse::<i64>()
+                .map_err(|_| ParseError::new(format!("Not an integer: '{o}'!")))
+        };
+        Self::new(name, operand(a)?, operand(b)?, operand(c)?)
+    }
+}
+
+impl Display for OpCode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let (a, b, c) = self.operands();
+        write!(f, "{} {a} {b} {c}", self.name())
+    }
+}
+
+impl OpCode {
+    pub fn new(name: &str, a: i64, b: i64, c: i64) -> Result<Self, ParseError> {
+        let r = Register::try_from;
+        Ok(match name {
+            "addr" => Self::Addr(r(a)?, r(b)?, r(c)?),
+            "addi" => Self::Addi(r(a)?, b.into(), r(c)?),
+            "mulr" => Self::Mulr(r(a)?, r(b)?, r(c)?),
+            "muli" => Self::Muli(r(a)?, b.into(), r(c)?),
+            "banr" => Self::Banr(r(a)?, r(b)?, r(c)?),
+            "bani" => Self::Bani(r(a)?, b.into(), r(c)?),
+            "borr" => Self::Borr(r(a)?, r(b)?, r(c)?),
+            "bori" => Self::Bori(r(a)?, b.into(), r(c)?),
+            "setr" => Self::Setr(r(a)?, b.into(), r(c)?),
+            "seti" => Self::Seti(a.into(), b.into(), r(c)?),
+            "gtir" => Self::Gtir(a.into(), r(b)?, r(c)?),
+            "gtri" => Self::Gtri(r(a)?, b.into(), r(c)?),
+            "gtrr" => Self::Gtrr(r(a)?, r(b)?, r(c)?),
+            "eqir" => Self::Eqir(a.into(), r(b)?, r(c)?),
+            "eqri" => Self::Eqri(r(a)?, b.into(), r(c)?),
+            "eqrr" => Self::Eqrr(r(a)?, r(b)?, r(c)?),
+            _ => return Err(ParseError::new(format!("Illegal instruction: '{name}'!"))),
+        })
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            OpCode::Addr(..) => "addr",
+            OpCode::Addi(..) => "addi",
+            OpCode::Mulr(..) => "mulr",
+            OpCode::Muli(..) => "muli",
+            OpCode::Banr(..) => "banr",
+            OpCode::Bani(..) => "bani",
+            OpCode::Borr(..) => "borr",
+            OpCode::Bori(..) => "bori",
+            OpCode::Setr(..) => "setr",
+            OpCode::Seti(..) => "seti",
+            OpCode::Gtir(..) => "gtir",
+            OpCode::Gtri(..) => "gtri",
+            OpCode::Gtrr(..) => "gtrr",
+            OpCode::Eqir(..) => "eqir",
+            OpCode::Eqri(..) => "eqri",
+            OpCode::Eqrr(..) => "eqrr",
+        }
+    }
+
+    pub fn operands(&self) -> (i64, i64, i64) {
+        let r = |r: &Register| r.0 as i64;
+        match self {
+            OpCode::Addr(a, b, c)
+            | OpCode::Mulr(a, b, c)
+            | OpCode::Banr(a, b, c)
+            | OpCode::Borr(a, b, c)
+            | OpCode::Gtrr(a, b, c)
+            | OpCode::Eqrr(a, b, c) => (r(a), r(b), r(c)),
+            OpCode::Addi(a, b, c)
+            | OpCode::Muli(a, b, c)
+            | OpCode::Bani(a, b, c)
+            | OpCode::Bori(a, b, c)
+            | OpCode::Setr(a, b, c)
+            | OpCode::Gtri(a, b, c)
+            | OpCode::Eqri(a, b, c) => (r(a), b.0, r(c)),
+            OpCode::Gtir(a, b, c) | OpCode::Eqir(a, b, c) => (a.0, r(b), r(c)),
+            OpCode::Seti(a, b, c) => (a.0, b.0, r(c)),
+        }
+    }
+
+    // Every register the instruction reads or writes, the written one `c` last.
+    pub fn registers(&self) -> Vec<Register> {
+        match *self {
+            OpCode::Addr(a, b, c)
+            | OpCode::Mulr(a, b, c)
+            | OpCode::Banr(a, b, c)
+            | OpCode::Borr(a, b, c)
+            | OpCode::Gtrr(a, b, c)
+            | OpCode::Eqrr(a, b, c) => vec![a, b, c],
+            OpCode::Addi(a, _, c)
+            | OpCode::Muli(a, _, c)
+            | OpCode::Bani(a, _, c)
+            | OpCode::Bori(a, _, c)
+            | OpCode::Setr(a, _, c)
+            | OpCode::Gtri(a, _, c)
+            | OpCode::Eqri(a, _, c) => vec![a, c],
+            OpCode::Gtir(_, b, c) | OpCode::Eqir(_, b, c) => vec![b, c],
+            OpCode::Seti(_, _, c) => vec![c],
+        }
+    }
+
+    pub fn eval(&self, r: &mut [i64]) {
+        match self {
+            OpCode::Addr(a, b, c) => r[c.0] = r[a.0] + r[b.0],
+            OpCode::Addi(a, b, c) => r[c.0] = r[a.0] + b.0,
+            OpCode::Mulr(a, b, c) => r[c.0] = r[a.0] * r[b.0],
+            OpCode::Muli(a, b, c) => r[c.0] = r[a.0] * b.0,
+            OpCode::Banr(a, b, c) => r[c.0] = r[a.0] & r[b.0],
+            OpCode::Bani(a, b, c) => r[c.0] = r[a.0] & b.0,
+            OpCode::Borr(a, b, c) => r[c.0] = r[a.0] | r[b.0],
+            OpCode::Bori(a, b, c) => r[c.0] = r[a.0] | b.0,
+            OpCode::Setr(a, _, c) => r[c.0] = r[a.0],
+            OpCode::Seti(a, _, c) => r[c.0] = a.0,
+            OpCode::Gtir(a, b, c) => r[c.0] = if a.0 > r[b.0] { 1 } else { 0 },
+            OpCode::Gtri(a, b, c) => r[c.0] = if r[a.0] > b.0 { 1 } else { 0 },
+            OpCode::Gtrr(a, b, c) => r[c.0] = if r[a.0] > r[b.0] { 1 } else { 0 },
+            OpCode::Eqir(a, b, c) => r[c.0] = if a.0 == r[b.0] { 1 } else { 0 },
+            OpCode::Eqri(a, b, c) => r[c.0] = if r[a.0] == b.0 { 1 } else { 0 },
+            OpCode::Eqrr(a, b, c) => r[c.0] = if r[a.0] == r[b.0] { 1 } else { 0 },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{OpCode, OP_NAMES};
+
+    #[test]
+    fn test_examples() {
+        let mut r = [3, 2, 1, 1];
+        "mulr 2 1 2".parse::<OpCode>().unwrap().eval(&mut r);
+        assert_eq!(r, [3, 2, 2, 1]);
+        "addi 2 1 2".parse::<OpCode>().unwrap().eval(&mut r);
+        assert_eq!(r, [3, 2, 3, 1]);
+        "seti 2 1 2".parse::<OpCode>().unwrap().eval(&mut r);
+        assert_eq!(r, [3, 2, 2, 1]);
+        "eqir 2 1 0".parse::<OpCode>().unwrap().eval(&mut r);
+        assert_eq!(r, [1, 2, 2, 1]);
+
+        for name in OP_NAMES {
+            let op = OpCode::new(name, 1, 2, 3).unwrap();
+            assert_eq!(op.name(), name);
+            assert_eq!(op.to_string(), format!("{name} 1 2 3"));
+            assert_eq!(op.to_string().parse(), Ok(op));
+            assert_eq!(op.registers().last().unwrap().0, 3);
+        }
+    }
+
+    #[test]
+    fn test_errors() {
+        let error = |s: &str| s.parse::<OpCode>().unwrap_err().msg;
+
+        assert_eq!(
+            error("addr 1 2"),
+            "Expected an instruction and 3 operands: 'addr 1 2'!"
+        );
+        assert_eq!(error("noop 1 2 3"), "Illegal instruction: 'noop'!");
+        assert_eq!(error("addi 1 x 3"), "Not an integer: 'x'!");
+        assert_eq!(error("addr 1 6 3"), "Register 6 is not a legal register!");
+        assert_eq!(
+            error("seti 1 -1 -1"),
+            "Register -1 is not a legal register!"
+        );
+        assert!("seti -1 6 0".parse::<OpCode>().is_ok());
+    }
+}
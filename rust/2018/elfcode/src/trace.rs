@@ -0,0 +1,324 @@
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap, VecDeque},
+    fmt::Display,
+    io::Write,
+    str::FromStr,
+};
+
+use crate::{Computer, ParseError, Register, NUM_REGISTERS};
+
+const TRACE_RING_CAPACITY: usize = 1024;
+
+// The registers right before the instruction at `ip` ran, `step` instructions into the trace.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Record {
+    pub step: usize,
+    pub ip: usize,
+    pub registers: [i64; NUM_REGISTERS],
+}
+
+impl Display for Record {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} {}", self.step, self.ip)?;
+        for r in self.registers {
+            write!(f, " {r}")?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for Record {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let values = s
+            .split_whitespace()
+            .map(|v| {
+                v.parse::<i64>()
+                    .map_err(|_| ParseError::new(format!("Not an integer: '{v}'!")))
+            })
+            .collect::<Result<Vec<i64>, ParseError>>()?;
+        let [step, ip, ref registers @ ..] = values[..] else {
+            return Err(ParseError::new(format!("Incomplete record: '{s}'!")));
+        };
+        let registers = registers
+            .try_into()
+            .map_err(|_| ParseError::new(format!("Expected {NUM_REGISTERS} registers: '{s}'!")))?;
+        let count = |v: i64| {
+            usize::try_from(v).map_err(|_| ParseError::new(format!("Negative count: '{v}'!")))
+        };
+
+        Ok(Self {
+            step: count(step)?,
+            ip: count(ip)?,
+            registers,
+        })
+    }
+}
+
+pub trait Sink {
+    fn write(&mut self, record: &Record) -> std::io::Result<()>;
+}
+
+// Any writer, like a `BufWriter<File>`, gets one record per line.
+impl<W: Write> Sink for W {
+    fn write(&mut self, record: &Record) -> std::io::Result<()> {
+        writeln!(self, "{record}")
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RingBuffer {
+    pub capacity: usize,
+    pub records: VecDeque<Record>,
+}
+
+impl RingBuffer {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            records: VecDeque::with_capacity(capacity),
+        }
+    }
+}
+
+impl Sink for RingBuffer {
+    fn write(&mut self, record: &Record) -> std::io::Result<()> {
+        if self.capacity == 0 {
+            return Ok(());
+        }
+        while self.records.len() >= self.capacity {
+            self.records.pop_front();
+        }
+        self.records.push_back(*record);
+        Ok(())
+    }
+}
+
+// Counted in records, the one at `start + length` repeats the one at `start`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Cycle {
+    pub start: usize,
+    pub length: usize,
+}
+
+#[derive(Debug, Clone)]
+pub struct Tracer<S: Sink = RingBuffer> {
+    // No addresses record every instruction, no registers compare all of them.
+    pub addresses: BTreeSet<usize>,
+    pub registers: Vec<Register>,
+    pub sink: S,
+    pub records: usize,
+    pub steps: usize,
+    pub cycle: Option<Cycle>,
+    seen: HashMap<(usize, Vec<i64>), usize>,
+    first: BTreeMap<usize, Record>,
+    last_new: BTreeMap<usize, Record>,
+}
+
+impl Tracer {
+    pub fn new(addresses: &[usize], registers: &[Register]) -> Self {
+        Self::with_sink(addresses, registers, RingBuffer::new(TRACE_RING_CAPACITY))
+    }
+}
+
+impl<S: Sink> Tracer<S> {
+    pub fn with_sink(addresses: &[usize], registers: &[Register], sink: S) -> Self {
+        Self {
+            addresses: addresses.iter().copied().collect(),
+            registers: registers.to_vec(),
+            sink,
+            records: 0,
+            steps: 0,
+            cycle: None,
+            seen: HashMap::new(),
+            first: BTreeMap::new(),
+            last_new: BTreeMap::new(),
+        }
+    }
+
+    pub fn first(&self, ip: usize) -> Option<&Record> {
+        self.first.get(&ip)
+    }
+
+    // The last record at `ip` whose state hadn't been seen before.
+    pub fn last_new(&self, ip: usize) -> Option<&Record> {
+        self.last_new.get(&ip)
+    }
+
+    pub fn record(
+        &mut self,
+        step: usize,
+        ip: usize,
+        registers: &[i64; NUM_REGISTERS],
+    ) -> std::io::Result<()> {
+        if !self.addresses.is_empty() && !self.addresses.contains(&ip) {
+            return Ok(());
+        }
+
+        let record = Record {
+            step,
+            ip,
+            registers: *registers,
+        };
+        self.sink.write(&record)?;
+        self.first.entry(ip).or_insert(record);
+
+        let state = if self.registers.is_empty() {
+            registers.to_vec()
+        } else {
+            self.registers.iter().map(|r| registers[r.0]).collect()
+        };
+        match self.seen.get(&(ip, state.clone())) {
+            Some(start) if self.cycle.is_none() => {
+                self.cycle = Some(Cycle {
+                    start: *start,
+                    length: self.records - start,
+                });
+            }
+            Some(_) => (),
+            None => {
+                self.seen.insert((ip, state), self.records);
+                self.last_new.insert(ip, record);
+            }
+        }
+        self.records += 1;
+        Ok(())
+    }
+
+    // Runs the computer until it halts, a state repeats or `limit` records were taken.
+    pub fn run_for(
+        &mut self,
+        computer: &mut Computer,
+        limit: usize,
+    ) -> std::io::Result<Option<Cycle>> {
+        let start = self.records;
+        let watched: Vec<bool> = (0..computer.instructions.len())
+            .map(|ip| self.addresses.is_empty() || self.addresses.contains(&ip))
+            .collect();
+        while self.cycle.is_none() && self.records - start < limit {
+            let ip = computer.ip as usize;
+            let Some(&op) = computer.instructions.get(ip) else {
+                break;
+            };
+            if watched[ip] {
+                self.record(self.steps, ip, &computer.registers)?;
+                if self.cycle.is_some() {
+                    break;
+                }
+            }
+            computer.execute(op);
+            self.steps += 1;
+        }
+        Ok(self.cycle)
+    }
+
+    pub fn run(&mut self, computer: &mut Computer) -> std::io::Result<Option<Cycle>> {
+        self.run_for(computer, usize::MAX)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{BufWriter, Write};
+
+    use crate::{Computer, Cycle, Record, Register, RingBuffer, Tracer};
+
+    // r0 counts 8, then 1 to 4 over and over at address 2.
+    const COUNTER: &str = "#ip 3\nseti 7 0 0\naddi 0 1 0\nbani 0 3 0\nseti 0 0 3";
+
+    #[test]
+    fn test_examples() {
+        let mut com: Computer = COUNTER.parse().unwrap();
+        let mut tracer = Tracer::new(&[2], &[Register(0)]);
+        let cycle = tracer.run(&mut com).unwrap();
+
+        assert_eq!(
+            cycle,
+            Some(Cycle {
+                start: 1,
+                length: 4
+            })
+        );
+        assert_eq!(tracer.records, 6);
+        assert_eq!(tracer.first(2).unwrap().registers[0], 8);
+        assert_eq!(tracer.last_new(2).unwrap().registers[0], 4);
+        assert_eq!(tracer.first(1), None);
+        let values: Vec<i64> = tracer.sink.records.iter().map(|r| r.registers[0]).collect();
+        assert_eq!(values, [8, 1, 2, 3, 4, 1]);
+        assert_eq!(tracer.sink.records[5].to_string(), "17 2 1 0 0 2 0 0");
+
+        // With every register compared the flow through all addresses has to repeat.
+        let mut com: Computer = COUNTER.parse().unwrap();
+        let mut tracer = Tracer::with_sink(&[], &[], RingBuffer::new(3));
+        let cycle = tracer.run(&mut com).unwrap();
+        assert_eq!(
+            cycle,
+            Some(Cycle {
+                start: 3,
+                length: 12
+            })
+        );
+        assert_eq!(tracer.sink.records.len(), 3);
+        assert_eq!(tracer.sink.records[2].step, 15);
+
+        let mut com: Computer = COUNTER.parse().unwrap();
+        let mut tracer = Tracer::new(&[2], &[Register(0)]);
+        assert_eq!(tracer.run_for(&mut com, 2).unwrap(), None);
+        assert_eq!(tracer.records, 2);
+        tracer.run_for(&mut com, 1).unwrap();
+        let steps: Vec<usize> = tracer.sink.records.iter().map(|r| r.step).collect();
+        assert_eq!(steps, [2, 5, 8]);
+        assert_eq!(tracer.steps, 9);
+
+        let mut com: Computer = COUNTER.parse().unwrap();
+        let mut tracer = Tracer::with_sink(&[], &[], RingBuffer::new(0));
+        tracer.run(&mut com).unwrap();
+        assert!(tracer.sink.records.is_empty());
+        assert_eq!(tracer.records, 16);
+
+        // A program that halts has no cycle.
+        let mut com: Computer = "seti 1 0 0\naddi 0 1 0".parse().unwrap();
+        let mut tracer = Tracer::new(&[], &[]);
+        assert_eq!(tracer.run(&mut com).unwrap(), None);
+        assert_eq!(tracer.records, 2);
+
+        assert_eq!(
+            "3 1 x".parse::<Record>().unwrap_err().msg,
+            "Not an integer: 'x'!"
+        );
+        assert_eq!(
+            "3 1 0 0".parse::<Record>().unwrap_err().msg,
+            "Expected 6 registers: '3 1 0 0'!"
+        );
+    }
+
+    #[test]
+    fn test_file() {
+        let path = std::env::temp_dir().join(format!("elfcode-trace-{}.txt", std::process::id()));
+        let file = std::fs::File::create(&path).unwrap();
+
+        let mut com: Computer = COUNTER.parse().unwrap();
+        let mut tracer = Tracer::with_sink(&[2], &[Register(0)], BufWriter::new(file));
+        tracer.run(&mut com).unwrap();
+        tracer.sink.flush().unwrap();
+
+        let trace = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        let records: Vec<Record> = trace.lines().map(|l| l.parse().unwrap()).collect();
+        assert_eq!(records.len(), 6);
+        assert_eq!(records[0], *tracer.first(2).unwrap());
+        assert_eq!(records[4], *tracer.last_new(2).unwrap());
+    }
+
+    #[test]
+    fn test_input() {
+        let program = std::fs::read_to_string("../day_21/input/program.txt").unwrap();
+        let mut com: Computer = program.parse().unwrap();
+        let mut tracer = Tracer::new(&[28], &[Register(1)]);
+        tracer.run_for(&mut com, 1).unwrap();
+
+        assert_eq!(tracer.first(28).unwrap().registers[1], 2159153);
+        assert_eq!(tracer.first(28).unwrap().step, 1846);
+    }
+}
@@ -1,369 +1,293 @@
 #![allow(dead_code)]
 
-use std::collections::VecDeque;
+use std::{collections::VecDeque, str::FromStr};
+
+use intcode::{Computer, Fault, InputOutput, State};
 
 fn main() {
     println!("Advent of Code 2019 - day 07");
 }
 
-const PARAMETER_MODE_FLAGS: usize = 3;
-
-#[derive(Debug, Clone, Copy, PartialEq)]
-enum ParameterMode {
-    Position,
-    Immediate,
+#[derive(Debug, Clone, PartialEq)]
+struct TryFromError {
+    msg: String,
 }
 
-impl From<isize> for ParameterMode {
-    fn from(value: isize) -> Self {
-        match value {
-            0 => Self::Position,
-            1 => Self::Immediate,
-            x => panic!("Illegal ParamterMode: {x}!"),
-        }
-    }
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+enum Merge {
+    #[default]
+    Queue,
+    Sum,
+    Max,
 }
 
-impl Default for ParameterMode {
-    fn default() -> Self {
-        Self::Position
+impl FromStr for Merge {
+    type Err = TryFromError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "queue" => Ok(Self::Queue),
+            "sum" => Ok(Self::Sum),
+            "max" => Ok(Self::Max),
+            x => Err(TryFromError {
+                msg: format!("Illegal merge policy '{x}'!"),
+            }),
+        }
     }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq)]
-enum Mnemonic {
-    Add,
-    Mul,
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Port {
     Input,
     Output,
-    JumpIfTrue,
-    JumpIfFalse,
-    LessThan,
-    Equals,
-    Halt,
+    Amp(usize),
 }
 
-impl Mnemonic {
-    fn instruction_pointer_offset(&self) -> usize {
-        match self {
-            Mnemonic::Add | Mnemonic::Mul | Mnemonic::LessThan | Mnemonic::Equals => 4,
-            Mnemonic::Input | Mnemonic::Output => 2,
-            Mnemonic::Halt => 0,
-            Mnemonic::JumpIfTrue | Mnemonic::JumpIfFalse => 3,
-        }
-    }
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+struct Topology {
+    names: Vec<String>,
+    merges: Vec<Merge>,
+    edges: Vec<(Port, Port)>,
 }
 
-impl From<isize> for Mnemonic {
-    fn from(value: isize) -> Self {
-        match value {
-            1 => Self::Add,
-            2 => Self::Mul,
-            3 => Self::Input,
-            4 => Self::Output,
-            5 => Self::JumpIfTrue,
-            6 => Self::JumpIfFalse,
-            7 => Self::LessThan,
-            8 => Self::Equals,
-            99 => Self::Halt,
-            x => panic!("Illegal mnemonic: {x}!"),
+impl Topology {
+    fn chain(n: usize) -> Self {
+        let mut topology = Self::default();
+        for idx in 0..n {
+            topology
+                .names
+                .push(((b'A' + idx as u8) as char).to_string());
+            topology.merges.push(Merge::Queue);
         }
-    }
-}
-
-struct OpCode {
-    mnemonic: Mnemonic,
-    parameter_modes: [ParameterMode; PARAMETER_MODE_FLAGS],
-}
 
-impl OpCode {
-    fn new(mnemonic: Mnemonic, parameter_modes: [ParameterMode; PARAMETER_MODE_FLAGS]) -> Self {
-        match mnemonic {
-            Mnemonic::Add | Mnemonic::Mul | Mnemonic::LessThan | Mnemonic::Equals => {
-                assert_eq!(parameter_modes[2], ParameterMode::Position)
-            }
-            Mnemonic::Input => {
-                assert_eq!(parameter_modes[0], ParameterMode::Position)
-            }
-            Mnemonic::Output | Mnemonic::JumpIfFalse | Mnemonic::JumpIfTrue | Mnemonic::Halt => (),
+        topology.edges.push((Port::Input, Port::Amp(0)));
+        for idx in 1..n {
+            topology.edges.push((Port::Amp(idx - 1), Port::Amp(idx)));
         }
+        topology.edges.push((Port::Amp(n - 1), Port::Amp(0)));
+        topology.edges.push((Port::Amp(n - 1), Port::Output));
 
-        Self {
-            mnemonic,
-            parameter_modes,
-        }
-    }
-
-    fn get_paramter(memory: &mut [isize], parameter_mode: ParameterMode, offset: usize) -> isize {
-        let parameter = memory[offset];
-        match parameter_mode {
-            ParameterMode::Position => memory[usize::try_from(parameter).unwrap()],
-            ParameterMode::Immediate => memory[offset],
-        }
-    }
-
-    fn eval_add(&self, memory: &mut [isize], instruction_pointer: usize) -> (usize, State) {
-        let param_1 = Self::get_paramter(memory, self.parameter_modes[0], instruction_pointer + 1);
-        let param_2 = Self::get_paramter(memory, self.parameter_modes[1], instruction_pointer + 2);
-        let target_idx = usize::try_from(memory[instruction_pointer + 3]).unwrap();
-        memory[target_idx] = param_1 + param_2;
-        (
-            instruction_pointer + Mnemonic::Add.instruction_pointer_offset(),
-            State::Running,
-        )
+        topology
     }
 
-    fn eval_mul(&self, memory: &mut [isize], instruction_pointer: usize) -> (usize, State) {
-        let param_1: isize =
-            Self::get_paramter(memory, self.parameter_modes[0], instruction_pointer + 1);
-        let param_2 = Self::get_paramter(memory, self.parameter_modes[1], instruction_pointer + 2);
-        let target_idx = usize::try_from(memory[instruction_pointer + 3]).unwrap();
-        memory[target_idx] = param_1 * param_2;
-        (
-            instruction_pointer + Mnemonic::Mul.instruction_pointer_offset(),
-            State::Running,
-        )
-    }
-
-    fn eval_input(
-        &self,
-        memory: &mut [isize],
-        instruction_pointer: usize,
-        io: &mut InputOutput,
-    ) -> (usize, State) {
-        let target_idx = usize::try_from(memory[instruction_pointer + 1]).unwrap();
-
-        match io.read_in() {
-            None => (instruction_pointer, State::WaitingForInput),
-            Some(next) => {
-                memory[target_idx] = next;
-                (
-                    instruction_pointer + Mnemonic::Input.instruction_pointer_offset(),
-                    State::Running,
-                )
+    fn port(&mut self, name: &str) -> Result<Port, TryFromError> {
+        match name {
+            "in" => Ok(Port::Input),
+            "out" => Ok(Port::Output),
+            _ if !name.is_empty() && name.chars().all(|c| c.is_alphanumeric() || c == '_') => {
+                let idx = match self.names.iter().position(|n| n == name) {
+                    Some(idx) => idx,
+                    None => {
+                        self.names.push(name.to_string());
+                        self.merges.push(Merge::Queue);
+                        self.names.len() - 1
+                    }
+                };
+                Ok(Port::Amp(idx))
             }
-        }
-    }
-
-    fn eval_output(
-        &self,
-        memory: &mut [isize],
-        instruction_pointer: usize,
-        io: &mut InputOutput,
-    ) -> (usize, State) {
-        let param_1: isize =
-            Self::get_paramter(memory, self.parameter_modes[0], instruction_pointer + 1);
-        io.write_out(param_1);
-        (
-            instruction_pointer + Mnemonic::Output.instruction_pointer_offset(),
-            State::Running,
-        )
-    }
-
-    fn eval_jump_if_true(
-        &self,
-        memory: &mut [isize],
-        instruction_pointer: usize,
-    ) -> (usize, State) {
-        let param_1 = Self::get_paramter(memory, self.parameter_modes[0], instruction_pointer + 1);
-        let param_2 = Self::get_paramter(memory, self.parameter_modes[1], instruction_pointer + 2);
-
-        if param_1 != 0 {
-            (usize::try_from(param_2).unwrap(), State::Running)
-        } else {
-            (
-                instruction_pointer + Mnemonic::JumpIfTrue.instruction_pointer_offset(),
-                State::Running,
-            )
-        }
-    }
-
-    fn eval_jump_if_false(
-        &self,
-        memory: &mut [isize],
-        instruction_pointer: usize,
-    ) -> (usize, State) {
-        let param_1 = Self::get_paramter(memory, self.parameter_modes[0], instruction_pointer + 1);
-        let param_2 = Self::get_paramter(memory, self.parameter_modes[1], instruction_pointer + 2);
-
-        if param_1 == 0 {
-            (usize::try_from(param_2).unwrap(), State::Running)
-        } else {
-            (
-                instruction_pointer + Mnemonic::JumpIfTrue.instruction_pointer_offset(),
-                State::Running,
-            )
-        }
-    }
-
-    fn eval_less_than(&self, memory: &mut [isize], instruction_pointer: usize) -> (usize, State) {
-        let param_1 = Self::get_paramter(memory, self.parameter_modes[0], instruction_pointer + 1);
-        let param_2 = Self::get_paramter(memory, self.parameter_modes[1], instruction_pointer + 2);
-        let target_idx = usize::try_from(memory[instruction_pointer + 3]).unwrap();
-        memory[target_idx] = if param_1 < param_2 { 1 } else { 0 };
-        (
-            instruction_pointer + Mnemonic::LessThan.instruction_pointer_offset(),
-            State::Running,
-        )
-    }
-
-    fn eval_equals(&self, memory: &mut [isize], instruction_pointer: usize) -> (usize, State) {
-        let param_1 = Self::get_paramter(memory, self.parameter_modes[0], instruction_pointer + 1);
-        let param_2 = Self::get_paramter(memory, self.parameter_modes[1], instruction_pointer + 2);
-        let target_idx = usize::try_from(memory[instruction_pointer + 3]).unwrap();
-        memory[target_idx] = if param_1 == param_2 { 1 } else { 0 };
-        (
-            instruction_pointer + Mnemonic::Equals.instruction_pointer_offset(),
-            State::Running,
-        )
-    }
-
-    fn eval(
-        &self,
-        memory: &mut [isize],
-        instruction_pointer: usize,
-        io: &mut InputOutput,
-    ) -> (usize, State) {
-        match self.mnemonic {
-            Mnemonic::Add => self.eval_add(memory, instruction_pointer),
-            Mnemonic::Mul => self.eval_mul(memory, instruction_pointer),
-            Mnemonic::Input => self.eval_input(memory, instruction_pointer, io),
-            Mnemonic::Output => self.eval_output(memory, instruction_pointer, io),
-            Mnemonic::JumpIfTrue => self.eval_jump_if_true(memory, instruction_pointer),
-            Mnemonic::JumpIfFalse => self.eval_jump_if_false(memory, instruction_pointer),
-            Mnemonic::LessThan => self.eval_less_than(memory, instruction_pointer),
-            Mnemonic::Equals => self.eval_equals(memory, instruction_pointer),
-            Mnemonic::Halt => (instruction_pointer, State::Halted),
+            _ => Err(TryFromError {
+                msg: format!("Illegal amplifier name '{name}'!"),
+            }),
         }
     }
 }
 
-impl From<isize> for OpCode {
-    fn from(mut value: isize) -> Self {
-        let mut parameter_modes = [ParameterMode::default(); PARAMETER_MODE_FLAGS];
+impl FromStr for Topology {
+    type Err = TryFromError;
 
-        let mnemonic = (value % 100).into();
-        value /= 100;
-        for parameter_mode in &mut parameter_modes {
-            *parameter_mode = (value % 10).into();
-            value /= 10;
-        }
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut topology = Self::default();
 
-        Self::new(mnemonic, parameter_modes)
-    }
-}
+        for line in s.lines() {
+            let line = line.split('#').next().unwrap().trim();
+            if line.is_empty() {
+                continue;
+            }
 
-struct InputOutput {
-    in_q: VecDeque<isize>,
-    out_q: VecDeque<isize>,
-}
+            if let Some(rest) = line.strip_prefix("merge ") {
+                let (name, merge) = rest.trim().split_once(' ').ok_or_else(|| TryFromError {
+                    msg: format!("Illegal merge line '{line}'!"),
+                })?;
+                let Port::Amp(idx) = topology.port(name.trim())? else {
+                    return Err(TryFromError {
+                        msg: format!("Cannot merge into '{name}'!"),
+                    });
+                };
+                topology.merges[idx] = merge.trim().parse()?;
+                continue;
+            }
 
-impl InputOutput {
-    fn new(initial_in: &[isize]) -> Self {
-        Self {
-            in_q: initial_in.iter().copied().collect(),
-            out_q: VecDeque::new(),
+            let segments: Vec<&str> = line.split("->").collect();
+            if segments.len() < 2 {
+                return Err(TryFromError {
+                    msg: format!("Illegal connection '{line}'!"),
+                });
+            }
+            for pair in segments.windows(2) {
+                for from in pair[0].split(',') {
+                    for to in pair[1].split(',') {
+                        let from = topology.port(from.trim())?;
+                        let to = topology.port(to.trim())?;
+                        if from == Port::Output || to == Port::Input {
+                            return Err(TryFromError {
+                                msg: format!("Illegal connection '{line}'!"),
+                            });
+                        }
+                        topology.edges.push((from, to));
+                    }
+                }
+            }
         }
-    }
 
-    fn read_in(&mut self) -> Option<isize> {
-        self.in_q.pop_front()
-    }
+        if !topology.edges.iter().any(|(_, to)| *to == Port::Output) {
+            return Err(TryFromError {
+                msg: "No amplifier is connected to 'out'!".to_string(),
+            });
+        }
 
-    fn write_out(&mut self, data: isize) {
-        self.out_q.push_back(data);
+        Ok(topology)
     }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq)]
-enum State {
-    Running,
-    WaitingForInput,
-    Halted,
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum ChainError {
+    Deadlock { waiting: Vec<String> },
+    PhaseCount { expected: usize, got: usize },
+    Fault(Fault),
 }
 
-#[derive(Debug, Clone)]
-struct Computer {
-    state: State,
-    memory: Vec<isize>,
-    instruction_pointer: usize,
-}
-
-impl From<&str> for Computer {
-    fn from(value: &str) -> Self {
-        let memory = value.split(',').map(|s| s.parse().unwrap()).collect();
-        let instruction_pointer = 0;
-
-        Self {
-            state: State::Running,
-            memory,
-            instruction_pointer,
-        }
+impl From<Fault> for ChainError {
+    fn from(value: Fault) -> Self {
+        Self::Fault(value)
     }
 }
 
-impl Computer {
-    fn eval(&mut self, io: &mut InputOutput) {
-        if self.state == State::Halted {
-            panic!("Halted program can't be resumed!")
-        }
-
-        let mut opcode = OpCode::from(self.memory[self.instruction_pointer]);
-
-        self.state = State::Running;
-        while self.state == State::Running {
-            (self.instruction_pointer, self.state) =
-                opcode.eval(&mut self.memory, self.instruction_pointer, io);
-            opcode = OpCode::from(self.memory[self.instruction_pointer]);
-        }
-    }
+struct Amp {
+    computer: Computer,
+    io: InputOutput,
 }
 
 struct AmpChain {
     template: Computer,
+    topology: Topology,
 }
 
 impl From<Computer> for AmpChain {
     fn from(value: Computer) -> Self {
-        Self { template: value }
+        Self::with_topology(value, Topology::chain(5))
     }
 }
 
 impl AmpChain {
-    fn eval(&self, phases: &[isize], initial_input: isize) -> isize {
-        let mut output = initial_input;
-        let mut computers: Vec<Computer> = phases.iter().map(|_| self.template.clone()).collect();
-        let mut ios: Vec<InputOutput> = phases.iter().map(|p| InputOutput::new(&[*p])).collect();
+    fn with_topology(template: Computer, topology: Topology) -> Self {
+        Self { template, topology }
+    }
 
-        ios[0].in_q.push_back(output);
-        loop {
-            for idx in 0..phases.len() {
-                computers[idx].eval(&mut ios[idx]);
-                assert_eq!(ios[idx].out_q.len(), 1);
-                output = ios[idx].out_q.pop_back().unwrap();
+    fn deliver(
+        &self,
+        amps: &mut [Amp],
+        buffers: &mut [VecDeque<isize>],
+        outputs: &mut Vec<isize>,
+        from: Port,
+        value: isize,
+    ) {
+        for (edge_idx, (_, to)) in self
+            .topology
+            .edges
+            .iter()
+            .enumerate()
+            .filter(|(_, (f, _))| *f == from)
+        {
+            let idx = match to {
+                Port::Amp(idx) => *idx,
+                _ => {
+                    outputs.push(value);
+                    continue;
+                }
+            };
+
+            let merge = self.topology.merges[idx];
+            if merge == Merge::Queue {
+                amps[idx].io.in_q.push_back(value);
+                continue;
+            }
 
-                let next_idx = if idx != ios.len() - 1 { idx + 1 } else { 0 };
-                ios[next_idx].in_q.push_back(output);
+            buffers[edge_idx].push_back(value);
+            let incoming: Vec<usize> = (0..self.topology.edges.len())
+                .filter(|e| self.topology.edges[*e].1 == Port::Amp(idx))
+                .collect();
+            while incoming.iter().all(|e| !buffers[*e].is_empty()) {
+                let values = incoming.iter().map(|e| buffers[*e].pop_front().unwrap());
+                let merged = match merge {
+                    Merge::Sum => values.sum(),
+                    _ => values.max().unwrap(),
+                };
+                amps[idx].io.in_q.push_back(merged);
             }
+        }
+    }
 
-            let state =
-                computers[1..]
-                    .iter()
-                    .map(|c| c.state)
-                    .fold(computers[0].state, |acc, s| {
-                        if acc != s {
-                            panic!("All computer should be in the same state!");
-                        } else {
-                            s
-                        }
-                    });
+    fn eval(&self, phases: &[isize], initial_input: isize) -> Result<Option<isize>, ChainError> {
+        if phases.len() != self.topology.names.len() {
+            return Err(ChainError::PhaseCount {
+                expected: self.topology.names.len(),
+                got: phases.len(),
+            });
+        }
 
-            if state == State::Halted {
-                break;
+        let mut amps: Vec<Amp> = phases
+            .iter()
+            .map(|p| Amp {
+                computer: self.template.clone(),
+                io: InputOutput::new(&[*p]),
+            })
+            .collect();
+        let mut buffers = vec![VecDeque::new(); self.topology.edges.len()];
+        let mut outputs = Vec::new();
+
+        self.deliver(
+            &mut amps,
+            &mut buffers,
+            &mut outputs,
+            Port::Input,
+            initial_input,
+        );
+
+        loop {
+            let mut progress = false;
+
+            for idx in 0..amps.len() {
+                let amp = &mut amps[idx];
+                if amp.computer.state == State::Halted {
+                    continue;
+                }
+
+                let pending = amp.io.in_q.len();
+                amp.computer.eval(&mut amp.io)?;
+                let values: Vec<isize> = amp.io.out_q.drain(..).collect();
+                progress |= pending != amp.io.in_q.len()
+                    || !values.is_empty()
+                    || amp.computer.state == State::Halted;
+
+                for value in values {
+                    self.deliver(&mut amps, &mut buffers, &mut outputs, Port::Amp(idx), value);
+                }
             }
-        }
 
-        output
+            if amps.iter().all(|a| a.computer.state == State::Halted) {
+                return Ok(outputs.last().copied());
+            }
+            if !progress {
+                return Err(ChainError::Deadlock {
+                    waiting: amps
+                        .iter()
+                        .enumerate()
+                        .filter(|(_, a)| a.computer.state != State::Halted)
+                        .map(|(idx, _)| self.topology.names[idx].clone())
+                        .collect(),
+                });
+            }
+        }
     }
 
     fn factorial(n: usize) -> usize {
@@ -377,7 +301,7 @@ impl AmpChain {
     fn phase_permutations_go(
         buf: &mut Vec<Vec<isize>>,
         current: &mut Vec<isize>,
-        remaining: &Vec<isize>,
+        remaining: &[isize],
     ) {
         if remaining.is_empty() {
             buf.push(current.clone());
@@ -386,7 +310,7 @@ impl AmpChain {
 
         for (idx, phase) in remaining.iter().enumerate() {
             current.push(*phase);
-            let mut remaining = remaining.clone();
+            let mut remaining = remaining.to_vec();
             remaining.remove(idx);
             Self::phase_permutations_go(buf, current, &remaining);
             current.pop();
@@ -395,11 +319,7 @@ impl AmpChain {
 
     fn phase_permutations(phases: &[isize]) -> Vec<Vec<isize>> {
         let mut buf = Vec::with_capacity(Self::factorial(phases.len()));
-        Self::phase_permutations_go(
-            &mut buf,
-            &mut Vec::with_capacity(phases.len()),
-            &Vec::from_iter(phases.iter().copied()),
-        );
+        Self::phase_permutations_go(&mut buf, &mut Vec::with_capacity(phases.len()), phases);
         buf
     }
 
@@ -408,8 +328,9 @@ impl AmpChain {
         let mut max_output = isize::MIN;
 
         for phase_combo in phase_combos {
-            let output = self.eval(&phase_combo, initial_input);
-            max_output = max_output.max(output);
+            if let Ok(Some(output)) = self.eval(&phase_combo, initial_input) {
+                max_output = max_output.max(output);
+            }
         }
 
         max_output
@@ -418,27 +339,29 @@ impl AmpChain {
 
 #[cfg(test)]
 mod tests {
-    use crate::{AmpChain, Computer};
+    use intcode::FaultReason;
+
+    use crate::{AmpChain, ChainError, Computer, Topology};
 
     #[test]
     fn test_examples() {
         let program = "3,15,3,16,1002,16,10,16,1,16,15,15,4,15,99,0,0";
         let chain = AmpChain::from(Computer::from(program));
-        let thruster_signal = chain.eval(&[4, 3, 2, 1, 0], 0);
+        let thruster_signal = chain.eval(&[4, 3, 2, 1, 0], 0).unwrap().unwrap();
         assert_eq!(thruster_signal, 43210);
         let max_output = chain.compute_max_output(&[0, 1, 2, 3, 4], 0);
         assert_eq!(max_output, thruster_signal);
 
         let program = "3,23,3,24,1002,24,10,24,1002,23,-1,23,101,5,23,23,1,24,23,23,4,23,99,0,0";
         let chain = AmpChain::from(Computer::from(program));
-        let thruster_signal = chain.eval(&[0, 1, 2, 3, 4], 0);
+        let thruster_signal = chain.eval(&[0, 1, 2, 3, 4], 0).unwrap().unwrap();
         assert_eq!(thruster_signal, 54321);
         let max_output = chain.compute_max_output(&[0, 1, 2, 3, 4], 0);
         assert_eq!(max_output, thruster_signal);
 
         let program = "3,31,3,32,1002,32,10,32,1001,31,-2,31,1007,31,0,33,1002,33,7,33,1,33,31,31,1,32,31,31,4,31,99,0,0,0";
         let chain = AmpChain::from(Computer::from(program));
-        let thruster_signal = chain.eval(&[1, 0, 4, 3, 2], 0);
+        let thruster_signal = chain.eval(&[1, 0, 4, 3, 2], 0).unwrap().unwrap();
         assert_eq!(thruster_signal, 65210);
         let max_output = chain.compute_max_output(&[0, 1, 2, 3, 4], 0);
         assert_eq!(max_output, thruster_signal);
@@ -446,7 +369,7 @@ mod tests {
         let program =
             "3,26,1001,26,-4,26,3,27,1002,27,2,27,1,27,26,27,4,27,1001,28,-1,28,1005,28,6,99,0,0,5";
         let chain = AmpChain::from(Computer::from(program));
-        let thruster_signal = chain.eval(&[9, 8, 7, 6, 5], 0);
+        let thruster_signal = chain.eval(&[9, 8, 7, 6, 5], 0).unwrap().unwrap();
         assert_eq!(thruster_signal, 139629729);
         let max_output = chain.compute_max_output(&[5, 6, 7, 8, 9], 0);
         assert_eq!(max_output, thruster_signal);
@@ -454,12 +377,82 @@ mod tests {
         let program =
             "3,52,1001,52,-5,52,3,53,1,52,56,54,1007,54,5,55,1005,55,26,1001,54,-5,54,1105,1,12,1,53,54,53,1008,54,0,55,1001,55,1,55,2,53,55,53,4,53,1001,56,-1,56,1005,56,6,99,0,0,0,0,10";
         let chain = AmpChain::from(Computer::from(program));
-        let thruster_signal = chain.eval(&[9, 7, 8, 5, 6], 0);
+        let thruster_signal = chain.eval(&[9, 7, 8, 5, 6], 0).unwrap().unwrap();
         assert_eq!(thruster_signal, 18216);
         let max_output = chain.compute_max_output(&[5, 6, 7, 8, 9], 0);
         assert_eq!(max_output, thruster_signal);
     }
 
+    #[test]
+    fn test_topologies() {
+        let program = "3,15,3,16,1002,16,10,16,1,16,15,15,4,15,99,0,0";
+        let topology: Topology = "in -> A -> B -> C -> D -> E -> out".parse().unwrap();
+        let chain = AmpChain::with_topology(Computer::from(program), topology);
+        assert_eq!(chain.eval(&[4, 3, 2, 1, 0], 0), Ok(Some(43210)));
+
+        let topology: Topology = "\
+            # fan-out to A and B, fan-in on C\n\
+            in -> A, B\n\
+            A, B -> C -> out\n\
+            merge C sum\n\
+        "
+        .parse()
+        .unwrap();
+        let chain = AmpChain::with_topology(Computer::from(program), topology.clone());
+        assert_eq!(chain.eval(&[1, 2, 3], 0), Ok(Some(33)));
+        assert_eq!(chain.compute_max_output(&[1, 2, 3], 0), 51);
+
+        let mut topology = topology;
+        topology.merges[2] = "max".parse().unwrap();
+        let chain = AmpChain::with_topology(Computer::from(program), topology);
+        assert_eq!(chain.eval(&[1, 2, 3], 0), Ok(Some(23)));
+
+        let topology: Topology = "A -> B -> A, out".parse().unwrap();
+        let chain = AmpChain::with_topology(Computer::from(program), topology);
+        assert_eq!(
+            chain.eval(&[1, 2], 0),
+            Err(ChainError::Deadlock {
+                waiting: vec!["A".to_string(), "B".to_string()]
+            })
+        );
+        assert_eq!(chain.compute_max_output(&[1, 2], 0), isize::MIN);
+        assert_eq!(
+            chain.eval(&[1, 2, 3], 0),
+            Err(ChainError::PhaseCount {
+                expected: 2,
+                got: 3
+            })
+        );
+
+        let chain = AmpChain::from(Computer::from("3,9,3,10,1101,1,1,9,98"));
+        let Err(ChainError::Fault(fault)) = chain.eval(&[0, 1, 2, 3, 4], 0) else {
+            panic!("Expected a fault!");
+        };
+        assert_eq!(
+            fault.reason,
+            FaultReason::IllegalOpCode("Illegal mnemonic: 98!".to_string())
+        );
+
+        let program =
+            "3,26,1001,26,-4,26,3,27,1002,27,2,27,1,27,26,27,4,27,1001,28,-1,28,1005,28,6,99,0,0,5";
+        let topology: Topology = "in -> A -> B -> A\nB -> C -> D -> C, out".parse().unwrap();
+        let chain = AmpChain::with_topology(Computer::from(program), topology);
+        assert!(chain.compute_max_output(&[5, 6, 7, 8], 0) > 0);
+
+        assert_eq!(
+            "A -> B".parse::<Topology>().unwrap_err().msg,
+            "No amplifier is connected to 'out'!"
+        );
+        assert_eq!(
+            "A -> in, out".parse::<Topology>().unwrap_err().msg,
+            "Illegal connection 'A -> in, out'!"
+        );
+        assert_eq!(
+            "A -> out\nmerge A avg".parse::<Topology>().unwrap_err().msg,
+            "Illegal merge policy 'avg'!"
+        );
+    }
+
     #[test]
     fn test_input() {
         let program = std::fs::read_to_string("input/program.txt").unwrap();
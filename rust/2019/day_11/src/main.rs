@@ -1,359 +1,41 @@
 #![allow(dead_code)]
 
-use std::{
-    collections::{HashMap, VecDeque},
-    fmt::Display,
-};
+use std::{collections::HashMap, fmt::Display};
+
+use intcode::{Computer, InputOutput, State};
 
 fn main() {
     println!("Advent of Code 2019 - day 11");
-}
-
-const PARAMETER_MODE_FLAGS: usize = 3;
-
-#[derive(Debug, Clone, Copy, PartialEq)]
-enum ParameterMode {
-    Position,
-    Immediate,
-    Relative,
-}
-
-impl From<isize> for ParameterMode {
-    fn from(value: isize) -> Self {
-        match value {
-            0 => Self::Position,
-            1 => Self::Immediate,
-            2 => Self::Relative,
-            x => panic!("Illegal ParamterMode: {x}!"),
-        }
-    }
-}
-
-impl Default for ParameterMode {
-    fn default() -> Self {
-        Self::Position
-    }
-}
-
-#[derive(Debug, Clone, Copy, PartialEq)]
-enum Mnemonic {
-    Add,
-    Mul,
-    Input,
-    Output,
-    JumpIfTrue,
-    JumpIfFalse,
-    LessThan,
-    Equals,
-    AdjustRelBase,
-    Halt,
-}
-
-impl Mnemonic {
-    fn instruction_pointer_offset(&self) -> usize {
-        match self {
-            Mnemonic::Add | Mnemonic::Mul | Mnemonic::LessThan | Mnemonic::Equals => 4,
-            Mnemonic::Input | Mnemonic::Output | Mnemonic::AdjustRelBase => 2,
-            Mnemonic::Halt => 0,
-            Mnemonic::JumpIfTrue | Mnemonic::JumpIfFalse => 3,
-        }
-    }
-}
-
-impl From<isize> for Mnemonic {
-    fn from(value: isize) -> Self {
-        match value {
-            1 => Self::Add,
-            2 => Self::Mul,
-            3 => Self::Input,
-            4 => Self::Output,
-            5 => Self::JumpIfTrue,
-            6 => Self::JumpIfFalse,
-            7 => Self::LessThan,
-            8 => Self::Equals,
-            9 => Self::AdjustRelBase,
-            99 => Self::Halt,
-            x => panic!("Illegal mnemonic: {x}!"),
-        }
-    }
-}
-
-#[derive(Debug)]
-struct OpCode {
-    mnemonic: Mnemonic,
-    parameter_modes: [ParameterMode; PARAMETER_MODE_FLAGS],
-}
-
-impl OpCode {
-    fn new(mnemonic: Mnemonic, parameter_modes: [ParameterMode; PARAMETER_MODE_FLAGS]) -> Self {
-        match mnemonic {
-            Mnemonic::Add | Mnemonic::Mul | Mnemonic::LessThan | Mnemonic::Equals => {
-                assert_ne!(parameter_modes[2], ParameterMode::Immediate)
-            }
-            Mnemonic::Input => {
-                assert_ne!(parameter_modes[0], ParameterMode::Immediate)
-            }
-            Mnemonic::Output
-            | Mnemonic::JumpIfFalse
-            | Mnemonic::JumpIfTrue
-            | Mnemonic::Halt
-            | Mnemonic::AdjustRelBase => (),
-        }
-
-        Self {
-            mnemonic,
-            parameter_modes,
-        }
-    }
-
-    fn get_paramter(
-        computer: &mut Computer,
-        parameter_mode: ParameterMode,
-        offset: usize,
-    ) -> isize {
-        let offset = computer.instruction_pointer + offset;
-        let memory = &computer.memory;
-        let parameter = memory[offset];
-        match parameter_mode {
-            ParameterMode::Position => memory[usize::try_from(parameter).unwrap()],
-            ParameterMode::Immediate => parameter,
-            ParameterMode::Relative => {
-                let offset = usize::try_from(computer.relative_base + parameter).unwrap();
-                memory[offset]
-            }
-        }
-    }
-
-    fn get_target_idx(
-        computer: &mut Computer,
-        parameter_mode: ParameterMode,
-        offset: usize,
-    ) -> usize {
-        let offset = computer.instruction_pointer + offset;
-        let memory = &computer.memory;
-        let parameter = memory[offset];
-        let p = match parameter_mode {
-            ParameterMode::Position => parameter,
-            ParameterMode::Relative => computer.relative_base + parameter,
-            ParameterMode::Immediate => panic!("Target index cannot be in immediate mode!"),
-        };
-
-        usize::try_from(p).unwrap()
-    }
-
-    fn eval_add(&self, computer: &mut Computer) {
-        let param_1 = Self::get_paramter(computer, self.parameter_modes[0], 1);
-        let param_2 = Self::get_paramter(computer, self.parameter_modes[1], 2);
-        let target_idx = Self::get_target_idx(computer, self.parameter_modes[2], 3);
-        computer.memory[target_idx] = param_1 + param_2;
-        computer.instruction_pointer += Mnemonic::Add.instruction_pointer_offset();
-    }
-
-    fn eval_mul(&self, computer: &mut Computer) {
-        let param_1: isize = Self::get_paramter(computer, self.parameter_modes[0], 1);
-        let param_2 = Self::get_paramter(computer, self.parameter_modes[1], 2);
-        let target_idx = Self::get_target_idx(computer, self.parameter_modes[2], 3);
-        computer.memory[target_idx] = param_1 * param_2;
-        computer.instruction_pointer += Mnemonic::Mul.instruction_pointer_offset();
-    }
-
-    fn eval_input(&self, computer: &mut Computer, io: &mut InputOutput) {
-        let target_idx = Self::get_target_idx(computer, self.parameter_modes[0], 1);
-
-        match io.read_in() {
-            None => computer.state = State::WaitingForInput,
-            Some(next) => {
-                computer.memory[target_idx] = next;
-                computer.instruction_pointer += Mnemonic::Input.instruction_pointer_offset();
-            }
-        }
-    }
-
-    fn eval_output(&self, computer: &mut Computer, io: &mut InputOutput) {
-        let param_1: isize = Self::get_paramter(computer, self.parameter_modes[0], 1);
-        io.write_out(param_1);
-        computer.instruction_pointer += Mnemonic::Output.instruction_pointer_offset();
-    }
-
-    fn eval_jump_if_true(&self, computer: &mut Computer) {
-        let param_1 = Self::get_paramter(computer, self.parameter_modes[0], 1);
-        let param_2 = Self::get_paramter(computer, self.parameter_modes[1], 2);
-
-        if param_1 != 0 {
-            computer.instruction_pointer = usize::try_from(param_2).unwrap();
-        } else {
-            computer.instruction_pointer += Mnemonic::JumpIfTrue.instruction_pointer_offset();
-        }
-    }
-
-    fn eval_jump_if_false(&self, computer: &mut Computer) {
-        let param_1 = Self::get_paramter(computer, self.parameter_modes[0], 1);
-        let param_2 = Self::get_paramter(computer, self.parameter_modes[1], 2);
-
-        if param_1 == 0 {
-            computer.instruction_pointer = usize::try_from(param_2).unwrap();
-        } else {
-            computer.instruction_pointer += Mnemonic::JumpIfFalse.instruction_pointer_offset();
-        }
-    }
-
-    fn eval_less_than(&self, computer: &mut Computer) {
-        let param_1 = Self::get_paramter(computer, self.parameter_modes[0], 1);
-        let param_2 = Self::get_paramter(computer, self.parameter_modes[1], 2);
-        let target_idx = Self::get_target_idx(computer, self.parameter_modes[2], 3);
-        computer.memory[target_idx] = if param_1 < param_2 { 1 } else { 0 };
-        computer.instruction_pointer += Mnemonic::LessThan.instruction_pointer_offset();
-    }
-
-    fn eval_equals(&self, computer: &mut Computer) {
-        let param_1 = Self::get_paramter(computer, self.parameter_modes[0], 1);
-        let param_2 = Self::get_paramter(computer, self.parameter_modes[1], 2);
-        let target_idx = Self::get_target_idx(computer, self.parameter_modes[2], 3);
-        computer.memory[target_idx] = if param_1 == param_2 { 1 } else { 0 };
-        computer.instruction_pointer += Mnemonic::Equals.instruction_pointer_offset();
-    }
-
-    fn eval_adjust_rel_base(&self, computer: &mut Computer) {
-        let param_1 = Self::get_paramter(computer, self.parameter_modes[0], 1);
-        computer.relative_base += param_1;
-        computer.instruction_pointer += Mnemonic::AdjustRelBase.instruction_pointer_offset();
-    }
-
-    fn eval(&self, computer: &mut Computer, io: &mut InputOutput) {
-        match self.mnemonic {
-            Mnemonic::Add => self.eval_add(computer),
-            Mnemonic::Mul => self.eval_mul(computer),
-            Mnemonic::Input => self.eval_input(computer, io),
-            Mnemonic::Output => self.eval_output(computer, io),
-            Mnemonic::JumpIfTrue => self.eval_jump_if_true(computer),
-            Mnemonic::JumpIfFalse => self.eval_jump_if_false(computer),
-            Mnemonic::LessThan => self.eval_less_than(computer),
-            Mnemonic::Equals => self.eval_equals(computer),
-            Mnemonic::AdjustRelBase => self.eval_adjust_rel_base(computer),
-            Mnemonic::Halt => computer.state = State::Halted,
-        }
-    }
-}
-
-impl From<isize> for OpCode {
-    fn from(mut value: isize) -> Self {
-        let mut parameter_modes = [ParameterMode::default(); PARAMETER_MODE_FLAGS];
-
-        let mnemonic = (value % 100).into();
-        value /= 100;
-        for parameter_mode in &mut parameter_modes {
-            *parameter_mode = (value % 10).into();
-            value /= 10;
-        }
-
-        Self::new(mnemonic, parameter_modes)
-    }
-}
-
-struct InputOutput {
-    in_q: VecDeque<isize>,
-    out_q: VecDeque<isize>,
-}
-
-impl Display for InputOutput {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        writeln!(f, "IO:").unwrap();
-        writeln!(f, "  i_q: {:?}", self.in_q).unwrap();
-        writeln!(f, "  o_q: {:?}", self.out_q).unwrap();
-        Ok(())
-    }
-}
 
-impl InputOutput {
-    fn new(initial_in: &[isize]) -> Self {
-        Self {
-            in_q: initial_in.iter().copied().collect(),
-            out_q: VecDeque::new(),
-        }
-    }
-
-    fn read_in(&mut self) -> Option<isize> {
-        self.in_q.pop_front()
-    }
-
-    fn write_out(&mut self, data: isize) {
-        self.out_q.push_back(data);
-    }
-}
-
-#[derive(Debug, Clone, Copy, PartialEq)]
-enum State {
-    Running,
-    WaitingForInput,
-    Halted,
-}
-
-#[derive(Debug, Clone)]
-struct Computer {
-    state: State,
-    memory: Vec<isize>,
-    instruction_pointer: usize,
-    relative_base: isize,
-}
-
-impl Display for Computer {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        writeln!(f, "Computer:").unwrap();
-        writeln!(f, "  State: {:?}", self.state).unwrap();
-        writeln!(f, "  Instruction Pointer: {}", self.instruction_pointer).unwrap();
-        let window_start = self.instruction_pointer;
-        let window_end = window_start + 4;
-        writeln!(
-            f,
-            "  Memory around ip: {:?}",
-            &self.memory[window_start..window_end]
-        )
-        .unwrap();
-        writeln!(f, "  Relative Base: {}", self.relative_base).unwrap();
-
-        let window_start = usize::try_from((self.relative_base - 3).max(0)).unwrap();
-        let window_end = window_start + 7;
-        writeln!(
-            f,
-            "  Memory around rel: {:?}",
-            &self.memory[window_start..window_end]
-        )
-        .unwrap();
-
-        Ok(())
-    }
-}
+    let mut args = std::env::args().skip(1);
+    let Some(path) = args.next() else {
+        return;
+    };
+    let scale = args.next().map_or(1, |s| s.parse().unwrap());
+
+    let program = std::fs::read_to_string("input/program.txt").unwrap();
+    let computer = Computer::from(program.as_str());
+    let painting = Painting::run(
+        &computer,
+        &[(Pos::default(), Color::White)],
+        Merge::Overwrite,
+    );
 
-impl From<&str> for Computer {
-    fn from(value: &str) -> Self {
-        let mut memory: Vec<isize> = value.split(',').map(|s| s.parse().unwrap()).collect();
-        memory.resize(1_000_000, 0);
-        let instruction_pointer = 0;
-
-        Self {
-            state: State::Running,
-            memory,
-            instruction_pointer,
-            relative_base: 0,
+    if path.ends_with(".pgm") {
+        let bounds = bounds(&painting.tiles);
+        std::fs::write(&path, to_pgm(&painting.tiles, bounds, scale)).unwrap();
+    } else if path.ends_with(".pbm") {
+        std::fs::write(&path, to_pbm(&painting.tiles, scale)).unwrap();
+    } else {
+        // Anything else is a directory receiving one PGM per stroke.
+        std::fs::create_dir_all(&path).unwrap();
+        for (idx, frame) in painting.animate(1, scale).iter().enumerate() {
+            std::fs::write(format!("{path}/frame_{idx:05}.pgm"), frame).unwrap();
         }
     }
 }
 
-impl Computer {
-    fn eval(&mut self, io: &mut InputOutput) {
-        if self.state == State::Halted {
-            panic!("Halted program can't be resumed!")
-        }
-
-        let mut opcode = OpCode::from(self.memory[self.instruction_pointer]);
-
-        self.state = State::Running;
-        while self.state == State::Running {
-            opcode.eval(self, io);
-            opcode = OpCode::from(self.memory[self.instruction_pointer]);
-        }
-    }
-}
+const PNM_LINE_LEN: usize = 70;
 
 #[derive(Debug, Clone, Copy, PartialEq)]
 enum Color {
@@ -449,48 +131,207 @@ impl Pos {
     }
 }
 
-fn paint(computer: &mut Computer, start_color: Color) -> HashMap<Pos, Color> {
-    let mut tiles: HashMap<Pos, Color> = HashMap::new();
-    let mut io = InputOutput::new(&[]);
-    let mut pos = Pos::default();
-    let mut dir = Dir::Up;
-
-    tiles.insert(pos, start_color);
-    loop {
-        let current_tile = *tiles.get(&pos).unwrap_or(&Color::Black);
-        io.in_q.push_back(current_tile.into());
-        computer.eval(&mut io);
-        if computer.state == State::Halted {
-            break;
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+enum Merge {
+    #[default]
+    Overwrite,
+    KeepFirst,
+    Lighten,
+    Darken,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Stroke {
+    robot: usize,
+    pos: Pos,
+    color: Color,
+}
+
+#[derive(Debug, Clone, Default)]
+struct Painting {
+    tiles: HashMap<Pos, Color>,
+    owners: HashMap<Pos, usize>,
+    strokes: Vec<Stroke>,
+}
+
+struct Robot {
+    computer: Computer,
+    io: InputOutput,
+    pos: Pos,
+    dir: Dir,
+}
+
+impl Painting {
+    fn run(computer: &Computer, starts: &[(Pos, Color)], merge: Merge) -> Self {
+        let mut painting = Self::default();
+        let mut robots: Vec<Robot> = starts
+            .iter()
+            .enumerate()
+            .map(|(idx, (pos, color))| {
+                painting.paint(idx, *pos, *color, merge);
+                Robot {
+                    computer: computer.clone(),
+                    io: InputOutput::new(&[]),
+                    pos: *pos,
+                    dir: Dir::Up,
+                }
+            })
+            .collect();
+
+        // Robots take turns, one panel each, so they see each other's paint.
+        while robots.iter().any(|r| r.computer.state != State::Halted) {
+            for (idx, robot) in robots.iter_mut().enumerate() {
+                if robot.computer.state == State::Halted {
+                    continue;
+                }
+
+                let current_tile = *painting.tiles.get(&robot.pos).unwrap_or(&Color::Black);
+                robot.io.in_q.push_back(current_tile.into());
+                robot.computer.eval(&mut robot.io).unwrap();
+                if robot.io.out_q.len() < 2 {
+                    continue;
+                }
+                let (color, turn): (Color, Turn) = (
+                    robot.io.out_q.pop_front().unwrap().into(),
+                    robot.io.out_q.pop_front().unwrap().into(),
+                );
+                painting.paint(idx, robot.pos, color, merge);
+                robot.dir = robot.dir.turn(turn);
+                robot.pos.step(robot.dir);
+            }
         }
-        let (color, turn): (Color, Turn) = (
-            io.out_q.pop_front().unwrap().into(),
-            io.out_q.pop_front().unwrap().into(),
-        );
-        tiles.insert(pos, color);
-        dir = dir.turn(turn);
-        pos.step(dir);
+
+        painting
     }
 
-    tiles
+    fn paint(&mut self, robot: usize, pos: Pos, color: Color, merge: Merge) {
+        let current = self.tiles.get(&pos).copied();
+        let owner = *self.owners.entry(pos).or_insert(robot);
+        let color = match (merge, current) {
+            (Merge::KeepFirst, Some(current)) if owner != robot => current,
+            (Merge::Lighten, Some(Color::White)) => Color::White,
+            (Merge::Darken, Some(Color::Black)) => Color::Black,
+            _ => color,
+        };
+
+        self.tiles.insert(pos, color);
+        self.strokes.push(Stroke { robot, pos, color });
+    }
+
+    // With `every` 0 only the final frame is rendered.
+    fn animate(&self, every: usize, scale: usize) -> Vec<String> {
+        let bounds = bounds(&self.tiles);
+        let mut tiles = HashMap::new();
+        let mut frames = Vec::new();
+
+        for (idx, stroke) in self.strokes.iter().enumerate() {
+            tiles.insert(stroke.pos, stroke.color);
+            if (every != 0 && (idx + 1) % every == 0) || idx + 1 == self.strokes.len() {
+                frames.push(to_pgm(&tiles, bounds, scale));
+            }
+        }
+
+        frames
+    }
 }
 
-fn render(tiles: &HashMap<Pos, Color>) -> String {
-    let (mut min_x, mut min_y, mut max_x, mut max_y) = (0, 0, 0, 0);
+fn paint(computer: &Computer, start_color: Color) -> HashMap<Pos, Color> {
+    Painting::run(computer, &[(Pos::default(), start_color)], Merge::Overwrite).tiles
+}
+
+fn bounds(tiles: &HashMap<Pos, Color>) -> (Pos, Pos) {
+    let (mut min, mut max) = (Pos::default(), Pos::default());
     tiles.keys().for_each(|p| {
-        min_x = min_x.min(p.x);
-        min_y = min_y.min(p.y);
-        max_x = max_x.max(p.x);
-        max_y = max_y.max(p.y);
+        min = Pos::new(min.x.min(p.x), min.y.min(p.y));
+        max = Pos::new(max.x.max(p.x), max.y.max(p.y));
     });
+    (min, max)
+}
+
+fn pixels(
+    tiles: &HashMap<Pos, Color>,
+    (min, max): (Pos, Pos),
+    scale: usize,
+) -> Vec<Vec<Option<Color>>> {
+    let mut rows = Vec::new();
+    for y in min.y..=max.y {
+        let row: Vec<Option<Color>> = (min.x..=max.x)
+            .flat_map(|x| std::iter::repeat_n(tiles.get(&Pos::new(x, y)).copied(), scale))
+            .collect();
+        rows.extend(std::iter::repeat_n(row, scale));
+    }
+    rows
+}
+
+fn write_pnm(header: &str, rows: &[Vec<String>]) -> String {
+    let mut buf = String::from(header);
+    for row in rows {
+        let mut line = String::new();
+        for value in row {
+            if !line.is_empty() && line.len() + 1 + value.len() > PNM_LINE_LEN {
+                buf.push_str(&line);
+                buf.push('\n');
+                line.clear();
+            }
+            if !line.is_empty() {
+                line.push(' ');
+            }
+            line.push_str(value);
+        }
+        buf.push_str(&line);
+        buf.push('\n');
+    }
+    buf
+}
+
+fn to_pbm(tiles: &HashMap<Pos, Color>, scale: usize) -> String {
+    let rows = pixels(tiles, bounds(tiles), scale);
+    let values: Vec<Vec<String>> = rows
+        .iter()
+        .map(|row| {
+            row.iter()
+                .map(|c| match c {
+                    Some(Color::White) => "0".to_string(),
+                    _ => "1".to_string(),
+                })
+                .collect()
+        })
+        .collect();
+
+    let width = rows.first().map_or(0, |r| r.len());
+    write_pnm(&format!("P1\n{width} {}\n", rows.len()), &values)
+}
+
+// Unlike the bitmap, the grey map tells never painted panels apart from black ones.
+fn to_pgm(tiles: &HashMap<Pos, Color>, bounds: (Pos, Pos), scale: usize) -> String {
+    let rows = pixels(tiles, bounds, scale);
+    let values: Vec<Vec<String>> = rows
+        .iter()
+        .map(|row| {
+            row.iter()
+                .map(|c| match c {
+                    Some(Color::White) => "255".to_string(),
+                    Some(Color::Black) => "0".to_string(),
+                    None => "64".to_string(),
+                })
+                .collect()
+        })
+        .collect();
+
+    let width = rows.first().map_or(0, |r| r.len());
+    write_pnm(&format!("P2\n{width} {}\n255\n", rows.len()), &values)
+}
+
+fn render(tiles: &HashMap<Pos, Color>) -> String {
+    let (min, max) = bounds(tiles);
 
     let mut buf = String::with_capacity(
-        usize::try_from((max_x - min_x + 1 + 1) * (max_y - min_y + 1)).unwrap(),
+        usize::try_from((max.x - min.x + 1 + 1) * (max.y - min.y + 1)).unwrap(),
     );
     let mut sep = "";
-    for y in min_y..=max_y {
+    for y in min.y..=max.y {
         buf.push_str(sep);
-        for x in min_x..=max_x {
+        for x in min.x..=max.x {
             let p = Pos::new(x, y);
             match tiles.get(&p) {
                 Some(color) => buf.push_str(&color.to_string()),
@@ -505,22 +346,126 @@ fn render(tiles: &HashMap<Pos, Color>) -> String {
 
 #[cfg(test)]
 mod tests {
-    use crate::{paint, render, Color, Computer};
+    use std::collections::HashMap;
+
+    use intcode::assemble_to_string;
+
+    use crate::{
+        bounds, paint, render, to_pbm, to_pgm, Color, Computer, Merge, Painting, Pos, Stroke,
+    };
+
+    // Paints the given color and turns right, four times, tracing a square back to its start.
+    fn square(color: isize) -> Computer {
+        let program = assemble_to_string(&format!(
+            "\
+            loop:\n\
+                input [seen]\n\
+                output #{color}\n\
+                output #1\n\
+                add [count], #-1, [count]\n\
+                jump_if_true [count], #loop\n\
+                halt\n\
+            count: db 4\n\
+            seen: db 0\n\
+            "
+        ))
+        .unwrap();
+        Computer::from(program.as_str())
+    }
 
     #[test]
-    fn test_examples() {}
+    fn test_examples() {
+        let tiles = HashMap::from([
+            (Pos::new(0, 0), Color::White),
+            (Pos::new(1, 0), Color::Black),
+            (Pos::new(1, 1), Color::White),
+        ]);
+        assert_eq!(bounds(&tiles), (Pos::new(0, 0), Pos::new(1, 1)));
+        assert_eq!(render(&tiles), "#.\n.#");
+        assert_eq!(to_pbm(&tiles, 1), "P1\n2 2\n0 1\n1 0\n");
+        assert_eq!(
+            to_pbm(&tiles, 2),
+            "P1\n4 4\n0 0 1 1\n0 0 1 1\n1 1 0 0\n1 1 0 0\n"
+        );
+        assert_eq!(
+            to_pgm(&tiles, bounds(&tiles), 1),
+            "P2\n2 2\n255\n255 0\n64 255\n"
+        );
+
+        let wide = HashMap::from([(Pos::new(0, 0), Color::White)]);
+        let pbm = to_pbm(&wide, 40);
+        assert!(pbm.lines().all(|l| l.len() <= 70));
+        assert_eq!(pbm.lines().count(), 2 + 40 * 2);
+
+        let painting = Painting::run(
+            &square(1),
+            &[(Pos::default(), Color::Black)],
+            Merge::Overwrite,
+        );
+        assert_eq!(render(&painting.tiles), "##\n##");
+        assert_eq!(painting.strokes.len(), 5);
+        assert_eq!(
+            painting.strokes[1],
+            Stroke {
+                robot: 0,
+                pos: Pos::new(0, 0),
+                color: Color::White
+            }
+        );
+
+        let frames = painting.animate(2, 1);
+        assert_eq!(frames.len(), 3);
+        assert_eq!(frames[0], "P2\n2 2\n255\n255 64\n64 64\n");
+        assert_eq!(
+            frames[2],
+            to_pgm(&painting.tiles, bounds(&painting.tiles), 1)
+        );
+        assert_eq!(painting.animate(0, 1), frames[2..]);
+    }
+
+    #[test]
+    fn test_multiple_robots() {
+        let starts = [
+            (Pos::new(0, 0), Color::Black),
+            (Pos::new(1, 0), Color::Black),
+        ];
+
+        let painting = Painting::run(&square(1), &starts, Merge::Overwrite);
+        assert_eq!(render(&painting.tiles), "###\n###");
+        assert_eq!(painting.strokes.len(), 2 + 2 * 4);
+
+        let pos = Pos::new(0, 0);
+        for (merge, expected) in [
+            (Merge::Overwrite, Color::Black),
+            (Merge::KeepFirst, Color::White),
+            (Merge::Lighten, Color::White),
+            (Merge::Darken, Color::Black),
+        ] {
+            let mut painting = Painting::default();
+            painting.paint(0, pos, Color::White, merge);
+            painting.paint(1, pos, Color::Black, merge);
+            assert_eq!(painting.tiles[&pos], expected, "{merge:?}");
+            assert_eq!(painting.strokes[1].color, expected);
+        }
+
+        let mut painting = Painting::default();
+        painting.paint(0, pos, Color::Black, Merge::Darken);
+        painting.paint(1, pos, Color::White, Merge::Darken);
+        painting.paint(0, pos, Color::White, Merge::KeepFirst);
+        assert_eq!(painting.tiles[&pos], Color::White);
+    }
 
     #[test]
     fn test_input() {
         let program = std::fs::read_to_string("input/program.txt").unwrap();
-        let mut computer = Computer::from(program.as_str());
+        let computer = Computer::from(program.as_str());
 
-        let tiles_painted = paint(&mut computer, Color::Black);
+        let tiles_painted = paint(&computer, Color::Black);
         assert_eq!(tiles_painted.len(), 1909);
 
         let program = std::fs::read_to_string("input/program.txt").unwrap();
-        let mut computer = Computer::from(program.as_str());
-        let tiles_painted = paint(&mut computer, Color::White);
+        let computer = Computer::from(program.as_str());
+        let tiles_painted = paint(&computer, Color::White);
 
         let registration_id = render(&tiles_painted);
         let expected = "\
@@ -532,5 +477,23 @@ mod tests {
             ..##...##..#....####.#..#.#..#.#....#..#...\
         ";
         assert_eq!(registration_id, expected);
+
+        let pbm = to_pbm(&tiles_painted, 3);
+        assert!(pbm.starts_with("P1\n129 18\n"));
+
+        let painting = Painting::run(
+            &computer,
+            &[
+                (Pos::default(), Color::White),
+                (Pos::new(0, 10), Color::White),
+            ],
+            Merge::KeepFirst,
+        );
+        let registration_id = render(&painting.tiles);
+        assert!(registration_id.starts_with(expected.lines().next().unwrap()));
+        assert_eq!(
+            painting.animate(100, 1).len(),
+            painting.strokes.len().div_ceil(100)
+        );
     }
 }
@@ -1,467 +1,404 @@
 #![allow(dead_code)]
 
 use std::{
-    collections::{HashMap, VecDeque},
+    collections::HashMap,
     fmt::Display,
+    io::{BufRead, Write},
 };
 
-fn main() {
-    println!("Advent of Code 2019 - day 13");
-}
+use intcode::{Computer, Event, Fault, InputOutput, State, Transcript};
 
-const PARAMETER_MODE_FLAGS: usize = 3;
+const ANSI_CLEAR: &str = "\x1b[2J\x1b[H";
 
-#[derive(Debug, Clone, Copy, PartialEq)]
-enum ParameterMode {
-    Position,
-    Immediate,
-    Relative,
-}
+fn main() {
+    println!("Advent of Code 2019 - day 13");
 
-impl From<isize> for ParameterMode {
-    fn from(value: isize) -> Self {
-        match value {
-            0 => Self::Position,
-            1 => Self::Immediate,
-            2 => Self::Relative,
-            x => panic!("Illegal ParamterMode: {x}!"),
+    let mut args = std::env::args().skip(1);
+    let Some(mode) = args.next() else {
+        println!("Usage: day_13 play|follow|predict [record file] | replay <file>");
+        return;
+    };
+    let file = args.next();
+
+    let program = std::fs::read_to_string("input/program.txt").unwrap();
+    let mut computer = Computer::from(program.as_str());
+    computer.memory[0] = 2;
+    let mut arcade = Arcade::from(computer);
+
+    let stdin = std::io::stdin();
+    let mut controller: Box<dyn Controller> = match mode.as_str() {
+        "play" => Box::new(Keyboard::new(stdin.lock())),
+        "follow" => Box::new(FollowBall),
+        "predict" => Box::new(Predictive::default()),
+        "replay" => {
+            let path = file.clone().expect("No transcript to replay!");
+            let transcript = std::fs::read_to_string(path).unwrap();
+            Box::new(Replay::from(&transcript.parse().unwrap()))
         }
+        _ => panic!("Illegal mode '{mode}'!"),
+    };
+
+    let mut stdout = std::io::stdout();
+    let result = arcade.run(controller.as_mut(), |arcade| {
+        write!(stdout, "{ANSI_CLEAR}{arcade}").unwrap();
+        stdout.flush().unwrap();
+    });
+    if let Err(e) = result {
+        println!("ERR: {e}");
     }
-}
 
-impl Default for ParameterMode {
-    fn default() -> Self {
-        Self::Position
+    if let (Some(path), true) = (file, mode != "replay") {
+        std::fs::write(&path, arcade.joystick().to_string()).unwrap();
+        println!("Recorded {} frames to '{path}'.", arcade.frames);
     }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq)]
-enum Mnemonic {
-    Add,
-    Mul,
-    Input,
-    Output,
-    JumpIfTrue,
-    JumpIfFalse,
-    LessThan,
-    Equals,
-    AdjustRelBase,
-    Halt,
-}
-
-impl Mnemonic {
-    fn instruction_pointer_offset(&self) -> usize {
-        match self {
-            Mnemonic::Add | Mnemonic::Mul | Mnemonic::LessThan | Mnemonic::Equals => 4,
-            Mnemonic::Input | Mnemonic::Output | Mnemonic::AdjustRelBase => 2,
-            Mnemonic::Halt => 0,
-            Mnemonic::JumpIfTrue | Mnemonic::JumpIfFalse => 3,
-        }
-    }
+enum Tile {
+    Empty,
+    Wall,
+    Block,
+    HPaddle,
+    Ball,
 }
 
-impl From<isize> for Mnemonic {
+impl From<isize> for Tile {
     fn from(value: isize) -> Self {
         match value {
-            1 => Self::Add,
-            2 => Self::Mul,
-            3 => Self::Input,
-            4 => Self::Output,
-            5 => Self::JumpIfTrue,
-            6 => Self::JumpIfFalse,
-            7 => Self::LessThan,
-            8 => Self::Equals,
-            9 => Self::AdjustRelBase,
-            99 => Self::Halt,
-            x => panic!("Illegal mnemonic: {x}!"),
+            0 => Tile::Empty,
+            1 => Tile::Wall,
+            2 => Tile::Block,
+            3 => Tile::HPaddle,
+            4 => Tile::Ball,
+            id => panic!("Illegal tile id: {id}!"),
         }
     }
 }
 
-#[derive(Debug)]
-struct OpCode {
-    mnemonic: Mnemonic,
-    parameter_modes: [ParameterMode; PARAMETER_MODE_FLAGS],
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+struct Pos {
+    x: usize,
+    y: usize,
 }
 
-impl OpCode {
-    fn new(mnemonic: Mnemonic, parameter_modes: [ParameterMode; PARAMETER_MODE_FLAGS]) -> Self {
-        match mnemonic {
-            Mnemonic::Add | Mnemonic::Mul | Mnemonic::LessThan | Mnemonic::Equals => {
-                assert_ne!(parameter_modes[2], ParameterMode::Immediate)
-            }
-            Mnemonic::Input => {
-                assert_ne!(parameter_modes[0], ParameterMode::Immediate)
-            }
-            Mnemonic::Output
-            | Mnemonic::JumpIfFalse
-            | Mnemonic::JumpIfTrue
-            | Mnemonic::Halt
-            | Mnemonic::AdjustRelBase => (),
-        }
-
-        Self {
-            mnemonic,
-            parameter_modes,
-        }
+impl Pos {
+    fn new(x: usize, y: usize) -> Self {
+        Self { x, y }
     }
+}
 
-    fn get_paramter(
-        computer: &mut Computer,
-        parameter_mode: ParameterMode,
-        offset: usize,
-    ) -> isize {
-        let offset = computer.instruction_pointer + offset;
-        let memory = &computer.memory;
-        let parameter = memory[offset];
-        match parameter_mode {
-            ParameterMode::Position => memory[usize::try_from(parameter).unwrap()],
-            ParameterMode::Immediate => parameter,
-            ParameterMode::Relative => {
-                let offset = usize::try_from(computer.relative_base + parameter).unwrap();
-                memory[offset]
-            }
+impl From<(isize, isize)> for Pos {
+    fn from(value: (isize, isize)) -> Self {
+        Self {
+            x: usize::try_from(value.0).unwrap(),
+            y: usize::try_from(value.1).unwrap(),
         }
     }
+}
 
-    fn get_target_idx(
-        computer: &mut Computer,
-        parameter_mode: ParameterMode,
-        offset: usize,
-    ) -> usize {
-        let offset = computer.instruction_pointer + offset;
-        let memory = &computer.memory;
-        let parameter = memory[offset];
-        let p = match parameter_mode {
-            ParameterMode::Position => parameter,
-            ParameterMode::Relative => computer.relative_base + parameter,
-            ParameterMode::Immediate => panic!("Target index cannot be in immediate mode!"),
+impl Display for Tile {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let c = match self {
+            Tile::Empty => ' ',
+            Tile::Wall => '#',
+            Tile::Block => '=',
+            Tile::HPaddle => '-',
+            Tile::Ball => 'o',
         };
-
-        usize::try_from(p).unwrap()
-    }
-
-    fn eval_add(&self, computer: &mut Computer) {
-        let param_1 = Self::get_paramter(computer, self.parameter_modes[0], 1);
-        let param_2 = Self::get_paramter(computer, self.parameter_modes[1], 2);
-        let target_idx = Self::get_target_idx(computer, self.parameter_modes[2], 3);
-        computer.memory[target_idx] = param_1 + param_2;
-        computer.instruction_pointer += Mnemonic::Add.instruction_pointer_offset();
-    }
-
-    fn eval_mul(&self, computer: &mut Computer) {
-        let param_1: isize = Self::get_paramter(computer, self.parameter_modes[0], 1);
-        let param_2 = Self::get_paramter(computer, self.parameter_modes[1], 2);
-        let target_idx = Self::get_target_idx(computer, self.parameter_modes[2], 3);
-        computer.memory[target_idx] = param_1 * param_2;
-        computer.instruction_pointer += Mnemonic::Mul.instruction_pointer_offset();
+        write!(f, "{c}")
     }
+}
 
-    fn eval_input(&self, computer: &mut Computer, io: &mut InputOutput) {
-        let target_idx = Self::get_target_idx(computer, self.parameter_modes[0], 1);
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum JoyDir {
+    Left,
+    Neutral,
+    Right,
+}
 
-        match io.read_in() {
-            None => computer.state = State::WaitingForInput,
-            Some(next) => {
-                computer.memory[target_idx] = next;
-                computer.instruction_pointer += Mnemonic::Input.instruction_pointer_offset();
-            }
+impl From<JoyDir> for isize {
+    fn from(value: JoyDir) -> Self {
+        match value {
+            JoyDir::Left => -1,
+            JoyDir::Neutral => 0,
+            JoyDir::Right => 1,
         }
     }
+}
 
-    fn eval_output(&self, computer: &mut Computer, io: &mut InputOutput) {
-        let param_1: isize = Self::get_paramter(computer, self.parameter_modes[0], 1);
-        io.write_out(param_1);
-        computer.instruction_pointer += Mnemonic::Output.instruction_pointer_offset();
-    }
-
-    fn eval_jump_if_true(&self, computer: &mut Computer) {
-        let param_1 = Self::get_paramter(computer, self.parameter_modes[0], 1);
-        let param_2 = Self::get_paramter(computer, self.parameter_modes[1], 2);
-
-        if param_1 != 0 {
-            computer.instruction_pointer = usize::try_from(param_2).unwrap();
-        } else {
-            computer.instruction_pointer += Mnemonic::JumpIfTrue.instruction_pointer_offset();
+impl From<isize> for JoyDir {
+    fn from(value: isize) -> Self {
+        match value.signum() {
+            -1 => JoyDir::Left,
+            1 => JoyDir::Right,
+            _ => JoyDir::Neutral,
         }
     }
+}
 
-    fn eval_jump_if_false(&self, computer: &mut Computer) {
-        let param_1 = Self::get_paramter(computer, self.parameter_modes[0], 1);
-        let param_2 = Self::get_paramter(computer, self.parameter_modes[1], 2);
-
-        if param_1 == 0 {
-            computer.instruction_pointer = usize::try_from(param_2).unwrap();
-        } else {
-            computer.instruction_pointer += Mnemonic::JumpIfFalse.instruction_pointer_offset();
+impl JoyDir {
+    fn towards(from: usize, to: usize) -> Self {
+        match from.cmp(&to) {
+            std::cmp::Ordering::Less => JoyDir::Right,
+            std::cmp::Ordering::Equal => JoyDir::Neutral,
+            std::cmp::Ordering::Greater => JoyDir::Left,
         }
     }
+}
 
-    fn eval_less_than(&self, computer: &mut Computer) {
-        let param_1 = Self::get_paramter(computer, self.parameter_modes[0], 1);
-        let param_2 = Self::get_paramter(computer, self.parameter_modes[1], 2);
-        let target_idx = Self::get_target_idx(computer, self.parameter_modes[2], 3);
-        computer.memory[target_idx] = if param_1 < param_2 { 1 } else { 0 };
-        computer.instruction_pointer += Mnemonic::LessThan.instruction_pointer_offset();
-    }
-
-    fn eval_equals(&self, computer: &mut Computer) {
-        let param_1 = Self::get_paramter(computer, self.parameter_modes[0], 1);
-        let param_2 = Self::get_paramter(computer, self.parameter_modes[1], 2);
-        let target_idx = Self::get_target_idx(computer, self.parameter_modes[2], 3);
-        computer.memory[target_idx] = if param_1 == param_2 { 1 } else { 0 };
-        computer.instruction_pointer += Mnemonic::Equals.instruction_pointer_offset();
-    }
-
-    fn eval_adjust_rel_base(&self, computer: &mut Computer) {
-        let param_1 = Self::get_paramter(computer, self.parameter_modes[0], 1);
-        computer.relative_base += param_1;
-        computer.instruction_pointer += Mnemonic::AdjustRelBase.instruction_pointer_offset();
-    }
-
-    fn eval(&self, computer: &mut Computer, io: &mut InputOutput) {
-        match self.mnemonic {
-            Mnemonic::Add => self.eval_add(computer),
-            Mnemonic::Mul => self.eval_mul(computer),
-            Mnemonic::Input => self.eval_input(computer, io),
-            Mnemonic::Output => self.eval_output(computer, io),
-            Mnemonic::JumpIfTrue => self.eval_jump_if_true(computer),
-            Mnemonic::JumpIfFalse => self.eval_jump_if_false(computer),
-            Mnemonic::LessThan => self.eval_less_than(computer),
-            Mnemonic::Equals => self.eval_equals(computer),
-            Mnemonic::AdjustRelBase => self.eval_adjust_rel_base(computer),
-            Mnemonic::Halt => computer.state = State::Halted,
-        }
-    }
+trait Controller {
+    fn joystick(&mut self, arcade: &Arcade) -> Option<JoyDir>;
 }
 
-impl From<isize> for OpCode {
-    fn from(mut value: isize) -> Self {
-        let mut parameter_modes = [ParameterMode::default(); PARAMETER_MODE_FLAGS];
+struct FollowBall;
 
-        let mnemonic = (value % 100).into();
-        value /= 100;
-        for parameter_mode in &mut parameter_modes {
-            *parameter_mode = (value % 10).into();
-            value /= 10;
+impl Controller for FollowBall {
+    fn joystick(&mut self, arcade: &Arcade) -> Option<JoyDir> {
+        match (arcade.ball, arcade.paddle) {
+            (Some(ball), Some(paddle)) => Some(JoyDir::towards(paddle.x, ball.x)),
+            _ => Some(JoyDir::Neutral),
         }
-
-        Self::new(mnemonic, parameter_modes)
     }
 }
 
-struct InputOutput {
-    in_q: VecDeque<isize>,
-    out_q: VecDeque<isize>,
+#[derive(Debug, Default)]
+struct Predictive {
+    last_ball: Option<Pos>,
 }
 
-impl Display for InputOutput {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        writeln!(f, "IO:").unwrap();
-        writeln!(f, "  i_q: {:?}", self.in_q).unwrap();
-        writeln!(f, "  o_q: {:?}", self.out_q).unwrap();
-        Ok(())
-    }
-}
-
-impl InputOutput {
-    fn new(initial_in: &[isize]) -> Self {
-        Self {
-            in_q: initial_in.iter().copied().collect(),
-            out_q: VecDeque::new(),
+impl Predictive {
+    // Follows the ball on its way up and heads for where it will land, bouncing off the side walls.
+    fn landing(&self, ball: Pos, paddle: Pos, width: usize) -> usize {
+        let Some(last) = self.last_ball else {
+            return ball.x;
+        };
+        if ball.y <= last.y || ball.y >= paddle.y || width < 3 {
+            return ball.x;
         }
-    }
-
-    fn read_in(&mut self) -> Option<isize> {
-        self.in_q.pop_front()
-    }
 
-    fn write_out(&mut self, data: isize) {
-        self.out_q.push_back(data);
+        let (lo, hi) = (1, width as isize - 2);
+        let dx = ball.x as isize - last.x as isize;
+        let mut x = ball.x as isize + dx * (paddle.y - 1 - ball.y) as isize;
+        let period = 2 * (hi - lo);
+        if period > 0 {
+            x = (x - lo).rem_euclid(period);
+            if x > hi - lo {
+                x = period - x;
+            }
+            x += lo;
+        }
+        x.clamp(lo, hi) as usize
     }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq)]
-enum State {
-    Running,
-    WaitingForInput,
-    Halted,
-}
-
-#[derive(Debug, Clone)]
-struct Computer {
-    state: State,
-    memory: Vec<isize>,
-    instruction_pointer: usize,
-    relative_base: isize,
-}
-
-impl Display for Computer {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        writeln!(f, "Computer:").unwrap();
-        writeln!(f, "  State: {:?}", self.state).unwrap();
-        writeln!(f, "  Instruction Pointer: {}", self.instruction_pointer).unwrap();
-        let window_start = self.instruction_pointer;
-        let window_end = window_start + 4;
-        writeln!(
-            f,
-            "  Memory around ip: {:?}",
-            &self.memory[window_start..window_end]
-        )
-        .unwrap();
-        writeln!(f, "  Relative Base: {}", self.relative_base).unwrap();
-
-        let window_start = usize::try_from((self.relative_base - 3).max(0)).unwrap();
-        let window_end = window_start + 7;
-        writeln!(
-            f,
-            "  Memory around rel: {:?}",
-            &self.memory[window_start..window_end]
-        )
-        .unwrap();
-
-        Ok(())
+impl Controller for Predictive {
+    fn joystick(&mut self, arcade: &Arcade) -> Option<JoyDir> {
+        let joy = match (arcade.ball, arcade.paddle) {
+            (Some(ball), Some(paddle)) => {
+                JoyDir::towards(paddle.x, self.landing(ball, paddle, arcade.width()))
+            }
+            _ => JoyDir::Neutral,
+        };
+        self.last_ball = arcade.ball;
+        Some(joy)
     }
 }
 
-impl From<&str> for Computer {
-    fn from(value: &str) -> Self {
-        let mut memory: Vec<isize> = value.split(',').map(|s| s.parse().unwrap()).collect();
-        memory.resize(1_000_000, 0);
-        let instruction_pointer = 0;
+struct Keyboard<R: BufRead> {
+    input: R,
+}
 
-        Self {
-            state: State::Running,
-            memory,
-            instruction_pointer,
-            relative_base: 0,
-        }
+impl<R: BufRead> Keyboard<R> {
+    fn new(input: R) -> Self {
+        Self { input }
     }
 }
 
-impl Computer {
-    fn eval(&mut self, io: &mut InputOutput) {
-        if self.state == State::Halted {
-            panic!("Halted program can't be resumed!")
+impl<R: BufRead> Controller for Keyboard<R> {
+    fn joystick(&mut self, _arcade: &Arcade) -> Option<JoyDir> {
+        let mut buf = String::new();
+        if self.input.read_line(&mut buf).ok()? == 0 {
+            return None;
         }
-
-        let mut opcode = OpCode::from(self.memory[self.instruction_pointer]);
-
-        self.state = State::Running;
-        while self.state == State::Running {
-            opcode.eval(self, io);
-            opcode = OpCode::from(self.memory[self.instruction_pointer]);
+        match buf.trim() {
+            "a" | "h" => Some(JoyDir::Left),
+            "d" | "l" => Some(JoyDir::Right),
+            "q" => None,
+            _ => Some(JoyDir::Neutral),
         }
     }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq)]
-enum Tile {
-    Empty,
-    Wall,
-    Block,
-    HPaddle,
-    Ball,
+struct Replay {
+    inputs: std::vec::IntoIter<isize>,
 }
 
-impl From<isize> for Tile {
-    fn from(value: isize) -> Self {
-        match value {
-            0 => Tile::Empty,
-            1 => Tile::Wall,
-            2 => Tile::Block,
-            3 => Tile::HPaddle,
-            4 => Tile::Ball,
-            id => panic!("Illegal tile id: {id}!"),
+impl From<&Transcript<InputOutput>> for Replay {
+    fn from(value: &Transcript<InputOutput>) -> Self {
+        Self {
+            inputs: value.inputs().into_iter(),
         }
     }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
-struct Pos {
-    x: usize,
-    y: usize,
+impl Controller for Replay {
+    fn joystick(&mut self, _arcade: &Arcade) -> Option<JoyDir> {
+        self.inputs.next().map(JoyDir::from)
+    }
 }
 
-impl Pos {
-    fn new(x: usize, y: usize) -> Self {
-        Self { x, y }
-    }
+struct Arcade {
+    computer: Computer,
+    io: Transcript<InputOutput>,
+    screen: HashMap<Pos, Tile>,
+    score: isize,
+    ball: Option<Pos>,
+    paddle: Option<Pos>,
+    frames: usize,
 }
 
-impl From<(isize, isize)> for Pos {
-    fn from(value: (isize, isize)) -> Self {
+impl From<Computer> for Arcade {
+    fn from(value: Computer) -> Self {
         Self {
-            x: usize::try_from(value.0).unwrap(),
-            y: usize::try_from(value.1).unwrap(),
+            computer: value,
+            io: Transcript::new(InputOutput::new(&[])),
+            screen: HashMap::new(),
+            score: 0,
+            ball: None,
+            paddle: None,
+            frames: 0,
         }
     }
 }
 
-enum JoyDir {
-    Left,
-    Neutral,
-    Right,
-}
+impl Arcade {
+    fn width(&self) -> usize {
+        self.screen
+            .keys()
+            .map(|p| p.x + 1)
+            .max()
+            .unwrap_or_default()
+    }
+
+    fn run<C: Controller + ?Sized>(
+        &mut self,
+        controller: &mut C,
+        mut on_frame: impl FnMut(&Arcade),
+    ) -> Result<(), Fault> {
+        while self.computer.state != State::Halted {
+            if self.computer.state == State::WaitingForInput {
+                let Some(joy) = controller.joystick(self) else {
+                    break;
+                };
+                self.io.device.write_to_in_q(joy.into());
+                self.frames += 1;
+            }
 
-impl From<JoyDir> for isize {
-    fn from(value: JoyDir) -> Self {
-        match value {
-            JoyDir::Left => -1,
-            JoyDir::Neutral => 0,
-            JoyDir::Right => 1,
+            self.computer.eval(&mut self.io)?;
+            self.update();
+            on_frame(self);
         }
-    }
-}
 
-fn render(computer: &mut Computer) -> (isize, HashMap<Pos, Tile>) {
-    let mut screen = HashMap::new();
-    let mut score = 0;
+        Ok(())
+    }
 
-    let mut io = InputOutput::new(&[]);
+    fn update(&mut self) {
+        let out_q = &mut self.io.device.out_q;
+        while out_q.len() >= 3 {
+            let (x, y, value) = (
+                out_q.pop_front().unwrap(),
+                out_q.pop_front().unwrap(),
+                out_q.pop_front().unwrap(),
+            );
 
-    let mut ball_x = -1;
-    let mut paddle_x = -1;
+            if (x, y) == (-1, 0) {
+                self.score = value;
+                continue;
+            }
 
-    while computer.state != State::Halted {
-        let joy_input = match (ball_x, paddle_x) {
-            (-1, -1) => JoyDir::Neutral,
-            (b, p) if b < p => JoyDir::Left,
-            (b, p) if b > p => JoyDir::Right,
-            _ => JoyDir::Neutral,
-        };
-        io.in_q.push_back(joy_input.into());
-        computer.eval(&mut io);
+            let pos = Pos::from((x, y));
+            let tile = Tile::from(value);
+            match tile {
+                Tile::Ball => self.ball = Some(pos),
+                Tile::HPaddle => self.paddle = Some(pos),
+                _ => (),
+            }
+            self.screen.insert(pos, tile);
+        }
+    }
 
-        while !io.out_q.is_empty() {
-            let (x, y) = (io.out_q.pop_front().unwrap(), io.out_q.pop_front().unwrap());
+    fn joystick(&self) -> Transcript<InputOutput> {
+        let inputs = self.io.inputs();
+        Transcript {
+            device: InputOutput::new(&inputs),
+            events: inputs.into_iter().map(Event::In).collect(),
+        }
+    }
+}
 
-            if (x, y) == (-1, 0) {
-                score = io.out_q.pop_front().unwrap();
-            } else {
-                let tile_id = io.out_q.pop_front().unwrap();
-                let pos = (x, y).into();
-                let tile = tile_id.into();
-
-                if tile == Tile::Ball {
-                    ball_x = x;
-                } else if tile == Tile::HPaddle {
-                    paddle_x = x;
-                }
-                screen.insert(pos, tile);
+impl Display for Arcade {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let height = self
+            .screen
+            .keys()
+            .map(|p| p.y + 1)
+            .max()
+            .unwrap_or_default();
+        for y in 0..height {
+            for x in 0..self.width() {
+                let tile = self.screen.get(&Pos::new(x, y)).unwrap_or(&Tile::Empty);
+                write!(f, "{tile}")?;
             }
+            writeln!(f)?;
         }
+        writeln!(f, "Score: {}  Frame: {}", self.score, self.frames)
     }
+}
+
+fn render(computer: &mut Computer) -> (isize, HashMap<Pos, Tile>) {
+    let mut arcade = Arcade::from(computer.clone());
+    arcade.run(&mut FollowBall, |_| ()).unwrap();
+    *computer = arcade.computer;
 
-    (score, screen)
+    (arcade.score, arcade.screen)
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::{render, Computer, Tile};
+    use intcode::{InputOutput, Transcript};
+
+    use crate::{
+        render, Arcade, Computer, Controller, FollowBall, JoyDir, Keyboard, Pos, Predictive,
+        Replay, Tile,
+    };
 
     #[test]
-    fn test_examples() {}
+    fn test_examples() {
+        // Draws a wall and the paddle, then adds each joystick input to the score until it reads 0.
+        let program = "104,0,104,0,104,1,104,1,104,0,104,3,\
+            3,100,1006,100,30,1,100,101,101,104,-1,104,0,4,101,1105,1,12,99";
+        let mut arcade = Arcade::from(Computer::from(program));
+        let mut keyboard = Keyboard::new("d\nl\nq\n".as_bytes());
+        let mut frames = Vec::new();
+        arcade
+            .run(&mut keyboard, |a| frames.push(a.to_string()))
+            .unwrap();
+
+        assert_eq!(arcade.frames, 2);
+        assert_eq!(frames[0], "#-\nScore: 0  Frame: 0\n");
+        assert_eq!(frames[2], "#-\nScore: 2  Frame: 2\n");
+        assert_eq!(arcade.joystick().to_string(), "< 1\n< 1\n");
+
+        let mut predictive = Predictive::default();
+        let mut arcade = Arcade::from(Computer::from("99"));
+        arcade.screen.insert(Pos::new(9, 0), Tile::Wall);
+        arcade.paddle = Some(Pos::new(5, 10));
+        arcade.ball = Some(Pos::new(6, 4));
+        assert_eq!(predictive.joystick(&arcade), Some(JoyDir::Right));
+        arcade.ball = Some(Pos::new(7, 5));
+        assert_eq!(predictive.landing(Pos::new(7, 5), Pos::new(5, 10), 10), 5);
+        assert_eq!(predictive.joystick(&arcade), Some(JoyDir::Neutral));
+    }
 
     #[test]
     fn test_input_part_1() {
@@ -484,4 +421,25 @@ mod tests {
         assert_eq!(blocks, 0);
         assert_eq!(score, 11040);
     }
+
+    #[test]
+    fn test_input_replay() {
+        let program = std::fs::read_to_string("input/program.txt").unwrap();
+        let mut computer = Computer::from(program.as_str());
+        computer.memory[0] = 2;
+
+        let mut arcade = Arcade::from(computer.clone());
+        arcade.run(&mut Predictive::default(), |_| ()).unwrap();
+        assert_eq!(arcade.score, 11040);
+
+        let recorded: Transcript<InputOutput> = arcade.joystick().to_string().parse().unwrap();
+        let mut replay = Arcade::from(computer.clone());
+        replay.run(&mut Replay::from(&recorded), |_| ()).unwrap();
+        assert_eq!(replay.frames, arcade.frames);
+        assert_eq!(replay.io.events, arcade.io.events);
+
+        let mut follow = Arcade::from(computer);
+        follow.run(&mut FollowBall, |_| ()).unwrap();
+        assert_eq!(follow.score, arcade.score);
+    }
 }
@@ -1,360 +1,16 @@
 #![allow(dead_code)]
 
 use std::{
-    collections::{HashSet, VecDeque},
+    collections::{HashMap, HashSet, VecDeque},
     fmt::Display,
 };
 
+use intcode::InputOutput;
+
 fn main() {
     println!("Advent of Code 2019 - day 17");
 }
 
-const PARAMETER_MODE_FLAGS: usize = 3;
-
-#[derive(Debug, Clone, Copy, PartialEq)]
-enum ParameterMode {
-    Position,
-    Immediate,
-    Relative,
-}
-
-impl From<isize> for ParameterMode {
-    fn from(value: isize) -> Self {
-        match value {
-            0 => Self::Position,
-            1 => Self::Immediate,
-            2 => Self::Relative,
-            x => panic!("Illegal ParamterMode: {x}!"),
-        }
-    }
-}
-
-impl Default for ParameterMode {
-    fn default() -> Self {
-        Self::Position
-    }
-}
-
-#[derive(Debug, Clone, Copy, PartialEq)]
-enum Mnemonic {
-    Add,
-    Mul,
-    Input,
-    Output,
-    JumpIfTrue,
-    JumpIfFalse,
-    LessThan,
-    Equals,
-    AdjustRelBase,
-    Halt,
-}
-
-impl Mnemonic {
-    fn instruction_pointer_offset(&self) -> usize {
-        match self {
-            Mnemonic::Add | Mnemonic::Mul | Mnemonic::LessThan | Mnemonic::Equals => 4,
-            Mnemonic::Input | Mnemonic::Output | Mnemonic::AdjustRelBase => 2,
-            Mnemonic::Halt => 0,
-            Mnemonic::JumpIfTrue | Mnemonic::JumpIfFalse => 3,
-        }
-    }
-}
-
-impl From<isize> for Mnemonic {
-    fn from(value: isize) -> Self {
-        match value {
-            1 => Self::Add,
-            2 => Self::Mul,
-            3 => Self::Input,
-            4 => Self::Output,
-            5 => Self::JumpIfTrue,
-            6 => Self::JumpIfFalse,
-            7 => Self::LessThan,
-            8 => Self::Equals,
-            9 => Self::AdjustRelBase,
-            99 => Self::Halt,
-            x => panic!("Illegal mnemonic: {x}!"),
-        }
-    }
-}
-
-#[derive(Debug)]
-struct OpCode {
-    mnemonic: Mnemonic,
-    parameter_modes: [ParameterMode; PARAMETER_MODE_FLAGS],
-}
-
-impl OpCode {
-    fn new(mnemonic: Mnemonic, parameter_modes: [ParameterMode; PARAMETER_MODE_FLAGS]) -> Self {
-        match mnemonic {
-            Mnemonic::Add | Mnemonic::Mul | Mnemonic::LessThan | Mnemonic::Equals => {
-                assert_ne!(parameter_modes[2], ParameterMode::Immediate)
-            }
-            Mnemonic::Input => {
-                assert_ne!(parameter_modes[0], ParameterMode::Immediate)
-            }
-            Mnemonic::Output
-            | Mnemonic::JumpIfFalse
-            | Mnemonic::JumpIfTrue
-            | Mnemonic::Halt
-            | Mnemonic::AdjustRelBase => (),
-        }
-
-        Self {
-            mnemonic,
-            parameter_modes,
-        }
-    }
-
-    fn get_paramter(
-        computer: &mut Computer,
-        parameter_mode: ParameterMode,
-        offset: usize,
-    ) -> isize {
-        let offset = computer.instruction_pointer + offset;
-        let memory = &computer.memory;
-        let parameter = memory[offset];
-        match parameter_mode {
-            ParameterMode::Position => memory[usize::try_from(parameter).unwrap()],
-            ParameterMode::Immediate => parameter,
-            ParameterMode::Relative => {
-                let offset = usize::try_from(computer.relative_base + parameter).unwrap();
-                memory[offset]
-            }
-        }
-    }
-
-    fn get_target_idx(
-        computer: &mut Computer,
-        parameter_mode: ParameterMode,
-        offset: usize,
-    ) -> usize {
-        let offset = computer.instruction_pointer + offset;
-        let memory = &computer.memory;
-        let parameter = memory[offset];
-        let p = match parameter_mode {
-            ParameterMode::Position => parameter,
-            ParameterMode::Relative => computer.relative_base + parameter,
-            ParameterMode::Immediate => panic!("Target index cannot be in immediate mode!"),
-        };
-
-        usize::try_from(p).unwrap()
-    }
-
-    fn eval_add(&self, computer: &mut Computer) {
-        let param_1 = Self::get_paramter(computer, self.parameter_modes[0], 1);
-        let param_2 = Self::get_paramter(computer, self.parameter_modes[1], 2);
-        let target_idx = Self::get_target_idx(computer, self.parameter_modes[2], 3);
-        computer.memory[target_idx] = param_1 + param_2;
-        computer.instruction_pointer += Mnemonic::Add.instruction_pointer_offset();
-    }
-
-    fn eval_mul(&self, computer: &mut Computer) {
-        let param_1: isize = Self::get_paramter(computer, self.parameter_modes[0], 1);
-        let param_2 = Self::get_paramter(computer, self.parameter_modes[1], 2);
-        let target_idx = Self::get_target_idx(computer, self.parameter_modes[2], 3);
-        computer.memory[target_idx] = param_1 * param_2;
-        computer.instruction_pointer += Mnemonic::Mul.instruction_pointer_offset();
-    }
-
-    fn eval_input(&self, computer: &mut Computer, io: &mut InputOutput) {
-        let target_idx = Self::get_target_idx(computer, self.parameter_modes[0], 1);
-
-        match io.read_in() {
-            None => computer.state = State::WaitingForInput,
-            Some(next) => {
-                computer.memory[target_idx] = next;
-                computer.instruction_pointer += Mnemonic::Input.instruction_pointer_offset();
-            }
-        }
-    }
-
-    fn eval_output(&self, computer: &mut Computer, io: &mut InputOutput) {
-        let param_1: isize = Self::get_paramter(computer, self.parameter_modes[0], 1);
-        io.write_out(param_1);
-        computer.instruction_pointer += Mnemonic::Output.instruction_pointer_offset();
-    }
-
-    fn eval_jump_if_true(&self, computer: &mut Computer) {
-        let param_1 = Self::get_paramter(computer, self.parameter_modes[0], 1);
-        let param_2 = Self::get_paramter(computer, self.parameter_modes[1], 2);
-
-        if param_1 != 0 {
-            computer.instruction_pointer = usize::try_from(param_2).unwrap();
-        } else {
-            computer.instruction_pointer += Mnemonic::JumpIfTrue.instruction_pointer_offset();
-        }
-    }
-
-    fn eval_jump_if_false(&self, computer: &mut Computer) {
-        let param_1 = Self::get_paramter(computer, self.parameter_modes[0], 1);
-        let param_2 = Self::get_paramter(computer, self.parameter_modes[1], 2);
-
-        if param_1 == 0 {
-            computer.instruction_pointer = usize::try_from(param_2).unwrap();
-        } else {
-            computer.instruction_pointer += Mnemonic::JumpIfFalse.instruction_pointer_offset();
-        }
-    }
-
-    fn eval_less_than(&self, computer: &mut Computer) {
-        let param_1 = Self::get_paramter(computer, self.parameter_modes[0], 1);
-        let param_2 = Self::get_paramter(computer, self.parameter_modes[1], 2);
-        let target_idx = Self::get_target_idx(computer, self.parameter_modes[2], 3);
-        computer.memory[target_idx] = if param_1 < param_2 { 1 } else { 0 };
-        computer.instruction_pointer += Mnemonic::LessThan.instruction_pointer_offset();
-    }
-
-    fn eval_equals(&self, computer: &mut Computer) {
-        let param_1 = Self::get_paramter(computer, self.parameter_modes[0], 1);
-        let param_2 = Self::get_paramter(computer, self.parameter_modes[1], 2);
-        let target_idx = Self::get_target_idx(computer, self.parameter_modes[2], 3);
-        computer.memory[target_idx] = if param_1 == param_2 { 1 } else { 0 };
-        computer.instruction_pointer += Mnemonic::Equals.instruction_pointer_offset();
-    }
-
-    fn eval_adjust_rel_base(&self, computer: &mut Computer) {
-        let param_1 = Self::get_paramter(computer, self.parameter_modes[0], 1);
-        computer.relative_base += param_1;
-        computer.instruction_pointer += Mnemonic::AdjustRelBase.instruction_pointer_offset();
-    }
-
-    fn eval(&self, computer: &mut Computer, io: &mut InputOutput) {
-        match self.mnemonic {
-            Mnemonic::Add => self.eval_add(computer),
-            Mnemonic::Mul => self.eval_mul(computer),
-            Mnemonic::Input => self.eval_input(computer, io),
-            Mnemonic::Output => self.eval_output(computer, io),
-            Mnemonic::JumpIfTrue => self.eval_jump_if_true(computer),
-            Mnemonic::JumpIfFalse => self.eval_jump_if_false(computer),
-            Mnemonic::LessThan => self.eval_less_than(computer),
-            Mnemonic::Equals => self.eval_equals(computer),
-            Mnemonic::AdjustRelBase => self.eval_adjust_rel_base(computer),
-            Mnemonic::Halt => computer.state = State::Halted,
-        }
-    }
-}
-
-impl From<isize> for OpCode {
-    fn from(mut value: isize) -> Self {
-        let mut parameter_modes = [ParameterMode::default(); PARAMETER_MODE_FLAGS];
-
-        let mnemonic = (value % 100).into();
-        value /= 100;
-        for parameter_mode in &mut parameter_modes {
-            *parameter_mode = (value % 10).into();
-            value /= 10;
-        }
-
-        Self::new(mnemonic, parameter_modes)
-    }
-}
-
-struct InputOutput {
-    in_q: VecDeque<isize>,
-    out_q: VecDeque<isize>,
-}
-
-impl Display for InputOutput {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        writeln!(f, "IO:").unwrap();
-        writeln!(f, "  i_q: {:?}", self.in_q).unwrap();
-        writeln!(f, "  o_q: {:?}", self.out_q).unwrap();
-        Ok(())
-    }
-}
-
-impl InputOutput {
-    fn new(initial_in: &[isize]) -> Self {
-        Self {
-            in_q: initial_in.iter().copied().collect(),
-            out_q: VecDeque::new(),
-        }
-    }
-
-    fn read_in(&mut self) -> Option<isize> {
-        self.in_q.pop_front()
-    }
-
-    fn write_out(&mut self, data: isize) {
-        self.out_q.push_back(data);
-    }
-}
-
-#[derive(Debug, Clone, Copy, PartialEq)]
-enum State {
-    Running,
-    WaitingForInput,
-    Halted,
-}
-
-#[derive(Debug, Clone)]
-struct Computer {
-    state: State,
-    memory: Vec<isize>,
-    instruction_pointer: usize,
-    relative_base: isize,
-}
-
-impl Display for Computer {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        writeln!(f, "Computer:").unwrap();
-        writeln!(f, "  State: {:?}", self.state).unwrap();
-        writeln!(f, "  Instruction Pointer: {}", self.instruction_pointer).unwrap();
-        let window_start = self.instruction_pointer;
-        let window_end = window_start + 4;
-        writeln!(
-            f,
-            "  Memory around ip: {:?}",
-            &self.memory[window_start..window_end]
-        )
-        .unwrap();
-        writeln!(f, "  Relative Base: {}", self.relative_base).unwrap();
-
-        let window_start = usize::try_from((self.relative_base - 3).max(0)).unwrap();
-        let window_end = window_start + 7;
-        writeln!(
-            f,
-            "  Memory around rel: {:?}",
-            &self.memory[window_start..window_end]
-        )
-        .unwrap();
-
-        Ok(())
-    }
-}
-
-impl From<&str> for Computer {
-    fn from(value: &str) -> Self {
-        let mut memory: Vec<isize> = value.split(',').map(|s| s.parse().unwrap()).collect();
-        memory.resize(1_000_000, 0);
-        let instruction_pointer = 0;
-
-        Self {
-            state: State::Running,
-            memory,
-            instruction_pointer,
-            relative_base: 0,
-        }
-    }
-}
-
-impl Computer {
-    fn eval(&mut self, io: &mut InputOutput) {
-        if self.state == State::Halted {
-            panic!("Halted program can't be resumed!")
-        }
-
-        let mut opcode = OpCode::from(self.memory[self.instruction_pointer]);
-
-        self.state = State::Running;
-        while self.state == State::Running {
-            opcode.eval(self, io);
-            opcode = OpCode::from(self.memory[self.instruction_pointer]);
-        }
-    }
-}
-
 #[derive(Debug, Clone, Copy, PartialEq)]
 enum Dir {
     Up,
@@ -612,6 +268,96 @@ impl Map {
 
         moves
     }
+
+    fn scaffold_capacity(&self, pos: (usize, usize)) -> usize {
+        let crossing = [Dir::Up, Dir::Down, Dir::Left, Dir::Right]
+            .into_iter()
+            .all(|dir| self.step(pos, dir).is_some());
+        if crossing {
+            2
+        } else {
+            1
+        }
+    }
+
+    // Enumerates every walk covering all scaffolding, choosing to go straight or turn at crossings.
+    fn find_traversal<T>(&self, f: &mut impl FnMut(&[Move]) -> Option<T>) -> Option<T> {
+        let start_idx = self.tiles.iter().position(|tile| tile.is_bot())?;
+        let CameraTile::Bot(dir) = self.tiles[start_idx] else {
+            return None;
+        };
+        let remaining = self
+            .tiles
+            .iter()
+            .filter(|tile| **tile == CameraTile::Scaffold)
+            .count();
+
+        self.traverse(
+            (start_idx % self.width, start_idx / self.width),
+            dir,
+            0,
+            &mut Vec::new(),
+            &mut HashMap::new(),
+            remaining,
+            f,
+        )
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn traverse<T>(
+        &self,
+        pos: (usize, usize),
+        dir: Dir,
+        steps: usize,
+        moves: &mut Vec<Move>,
+        visits: &mut HashMap<(usize, usize), usize>,
+        remaining: usize,
+        f: &mut impl FnMut(&[Move]) -> Option<T>,
+    ) -> Option<T> {
+        if remaining == 0 {
+            let len = moves.len();
+            if steps > 0 {
+                moves.push(Move::Forward(steps));
+            }
+            let result = f(moves);
+            moves.truncate(len);
+            return result;
+        }
+
+        for turn in [None, Some(Dir::Left), Some(Dir::Right)] {
+            let next_dir = turn.map_or(dir, |turn| dir.turn(turn));
+            let Some(next) = self.step(pos, next_dir) else {
+                continue;
+            };
+            let count = visits.get(&next).copied().unwrap_or_default();
+            if count >= self.scaffold_capacity(next) {
+                continue;
+            }
+
+            let len = moves.len();
+            let mut next_steps = steps + 1;
+            if let Some(turn) = turn {
+                if steps > 0 {
+                    moves.push(Move::Forward(steps));
+                }
+                moves.push(turn.into());
+                next_steps = 1;
+            }
+            *visits.entry(next).or_default() += 1;
+
+            let next_remaining = if count == 0 { remaining - 1 } else { remaining };
+            let result =
+                self.traverse(next, next_dir, next_steps, moves, visits, next_remaining, f);
+
+            *visits.get_mut(&next).unwrap() -= 1;
+            moves.truncate(len);
+            if result.is_some() {
+                return result;
+            }
+        }
+
+        None
+    }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq)]
@@ -661,82 +407,209 @@ fn moves_list_to_ascii_str(moves: &[Move]) -> String {
     s
 }
 
-fn first_replacable_char(s: &str) -> usize {
-    for (idx, c) in s.char_indices() {
-        match c {
-            'A' | 'B' | 'C' | ',' => continue,
-            _ => return idx,
-        }
+#[derive(Debug, Clone, PartialEq)]
+struct CompressError {
+    msg: String,
+}
+
+impl Display for CompressError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.msg)
     }
-    panic!("No more replacable characters in {s}!");
 }
 
-fn len_of_largest_repeated_pattern(s: &str) -> usize {
-    let mut max_len = 0;
+#[derive(Debug, Clone, PartialEq)]
+struct Compression {
+    main: Vec<usize>,
+    functions: Vec<Vec<Move>>,
+}
 
-    for len in 1..=20.max(s.len()) {
-        let substring = &s[0..len];
+impl Compression {
+    fn function_name(idx: usize) -> char {
+        (b'A' + idx as u8) as char
+    }
 
-        let mut iter = substring.chars().rev();
-        if let Some(',') = iter.next() {
-            if let Some(c) = iter.next() {
-                if !c.is_ascii_digit() {
-                    continue;
-                }
-            }
-        } else {
-            continue;
-        }
+    fn routines(&self) -> Vec<String> {
+        let main: Vec<String> = self
+            .main
+            .iter()
+            .map(|idx| Self::function_name(*idx).to_string())
+            .collect();
 
-        if s[len..].contains(substring) {
-            max_len = len;
-        } else {
-            break;
-        }
+        let mut routines = vec![format!("{}\n", main.join(","))];
+        routines.extend(self.functions.iter().map(|f| moves_list_to_ascii_str(f)));
+        routines
+    }
+
+    fn expand(&self) -> Vec<Move> {
+        self.main
+            .iter()
+            .flat_map(|idx| self.functions[*idx].iter().copied())
+            .collect()
     }
+}
 
-    if max_len == 0 {
-        panic!("No repeating pattern found in {s}!")
+struct Compressor {
+    functions: usize,
+    budget: usize,
+}
+
+impl Default for Compressor {
+    fn default() -> Self {
+        Self {
+            functions: 3,
+            budget: 20,
+        }
     }
-    max_len
 }
 
-fn build_movement_routines(moves: &[Move]) -> [String; 4] {
-    let mut moves = moves_list_to_ascii_str(moves);
-    moves.pop();
-    moves.push(',');
+impl Compressor {
+    fn routine_len(moves: &[Move]) -> usize {
+        moves_list_to_ascii_str(moves).len() - 1
+    }
 
-    let mut routines = [String::new(), String::new(), String::new(), String::new()];
+    fn max_calls(&self) -> usize {
+        self.budget.div_ceil(2)
+    }
 
-    for (i, item) in routines.iter_mut().enumerate().skip(1) {
-        let replacement = match i {
-            1 => "A,",
-            2 => "B,",
-            3 => "C,",
-            _ => panic!("Should never happen"),
+    fn compress(&self, moves: &[Move]) -> Result<Compression, CompressError> {
+        if let Some(mv) = moves
+            .iter()
+            .find(|mv| Self::routine_len(&[**mv]) > self.budget)
+        {
+            return Err(CompressError {
+                msg: format!(
+                    "Move '{}' alone exceeds the budget of {} characters!",
+                    moves_list_to_ascii_str(&[*mv]).trim_end(),
+                    self.budget
+                ),
+            });
+        }
+
+        let mut compression = Compression {
+            main: Vec::new(),
+            functions: Vec::new(),
         };
+        let mut best = None;
+        let mut covered = 0;
+        self.search(moves, 0, &mut compression, &mut best, &mut covered);
+
+        best.ok_or_else(|| CompressError {
+            msg: format!(
+                "At most {covered} of {} moves fit into {} calls of {} functions with {} characters each!",
+                moves.len(),
+                self.max_calls(),
+                self.functions,
+                self.budget
+            ),
+        })
+    }
+
+    fn cost(compression: &Compression) -> usize {
+        compression.main.len() * 2
+            + compression
+                .functions
+                .iter()
+                .map(|f| Self::routine_len(f))
+                .sum::<usize>()
+    }
+
+    // Ties go to fewer calls, then to shorter functions first, which matches the puzzle's example.
+    fn rank(compression: &Compression) -> (usize, usize, Vec<usize>) {
+        let lens = compression
+            .functions
+            .iter()
+            .map(|f| Self::routine_len(f))
+            .collect();
+        (Self::cost(compression), compression.main.len(), lens)
+    }
+
+    // Every function starts at the first move not covered yet, so trying all lengths there is exhaustive.
+    // Among all valid compressions the one with the fewest characters in total is kept.
+    fn search(
+        &self,
+        moves: &[Move],
+        pos: usize,
+        compression: &mut Compression,
+        best: &mut Option<Compression>,
+        covered: &mut usize,
+    ) {
+        *covered = (*covered).max(pos);
+        if best
+            .as_ref()
+            .is_some_and(|best| Self::cost(best) < Self::cost(compression))
+        {
+            return;
+        }
+        if pos == moves.len() {
+            if best
+                .as_ref()
+                .is_none_or(|best| Self::rank(compression) < Self::rank(best))
+            {
+                *best = Some(compression.clone());
+            }
+            return;
+        }
+        if compression.main.len() == self.max_calls() {
+            return;
+        }
+
+        for idx in 0..compression.functions.len() {
+            let len = compression.functions[idx].len();
+            if moves[pos..].starts_with(&compression.functions[idx]) {
+                compression.main.push(idx);
+                self.search(moves, pos + len, compression, best, covered);
+                compression.main.pop();
+            }
+        }
 
-        let start = first_replacable_char(&moves);
-        let len = len_of_largest_repeated_pattern(&moves[start..]);
+        if compression.functions.len() < self.functions {
+            let max_end = (pos + 1..=moves.len())
+                .take_while(|end| Self::routine_len(&moves[pos..*end]) <= self.budget)
+                .last()
+                .unwrap_or(pos);
 
-        *item = moves[start..start + len].to_string();
-        moves = moves.replace(item.as_str(), replacement);
+            for end in (pos + 1..=max_end).rev() {
+                compression.main.push(compression.functions.len());
+                compression.functions.push(moves[pos..end].to_vec());
+                self.search(moves, end, compression, best, covered);
+                compression.functions.pop();
+                compression.main.pop();
+            }
+        }
     }
-    routines[0] = moves;
 
-    for r in routines.iter_mut() {
-        r.pop();
-        r.push('\n');
+    fn solve(&self, map: &Map) -> Result<Compression, CompressError> {
+        let simple = self.compress(&map.simple_path());
+        if simple.is_ok() {
+            return simple;
+        }
+
+        let mut traversals = 0;
+        map.find_traversal(&mut |moves| {
+            traversals += 1;
+            self.compress(moves).ok()
+        })
+        .ok_or_else(|| CompressError {
+            msg: format!(
+                "None of {traversals} traversals compresses, straight path: {}",
+                simple.unwrap_err()
+            ),
+        })
     }
+}
 
-    routines
+fn build_movement_routines(moves: &[Move]) -> Vec<String> {
+    Compressor::default().compress(moves).unwrap().routines()
 }
 
 #[cfg(test)]
 mod tests {
+    use intcode::{Computer, InputOutput, State};
+
     use crate::{
-        build_movement_routines, moves_list_to_ascii_str, CameraTile, Computer, InputOutput, Map,
-        MovementRoutine, State,
+        build_movement_routines, moves_list_to_ascii_str, CameraTile, Compressor, Map, Move,
+        MovementRoutine,
     };
 
     #[test]
@@ -835,6 +708,51 @@ mod tests {
         assert_eq!(&routines[1], "R,8,R,8\n");
         assert_eq!(&routines[2], "R,4,R,4\n");
         assert_eq!(&routines[3], "R,8,L,6,L,2\n");
+
+        let compressor = Compressor {
+            functions: 2,
+            budget: 20,
+        };
+        assert_eq!(
+            compressor.compress(&path).unwrap_err().msg,
+            "At most 20 of 28 moves fit into 10 calls of 2 functions with 20 characters each!"
+        );
+        let compressor = Compressor {
+            functions: 4,
+            budget: 12,
+        };
+        let compression = compressor.compress(&path).unwrap();
+        assert_eq!(compression.expand(), path);
+        assert!(compression.routines().iter().all(|r| r.len() <= 13));
+
+        let compressor = Compressor {
+            functions: 3,
+            budget: 1,
+        };
+        assert_eq!(
+            compressor
+                .compress(&[Move::Right, Move::Forward(12)])
+                .unwrap_err()
+                .msg,
+            "Move '12' alone exceeds the budget of 1 characters!"
+        );
+
+        let compressor = Compressor {
+            functions: 1,
+            budget: 20,
+        };
+        let error = compressor.solve(&map).unwrap_err().msg;
+        assert!(error.starts_with("None of "), "{error}");
+
+        let first = map.find_traversal(&mut |moves: &[Move]| Some(moves.to_vec()));
+        assert_eq!(first, Some(path.clone()));
+
+        let mut traversals = 0;
+        map.find_traversal(&mut |_: &[Move]| {
+            traversals += 1;
+            None::<()>
+        });
+        assert!(traversals > 1);
     }
 
     #[test]
@@ -842,7 +760,7 @@ mod tests {
         let program = std::fs::read_to_string("input/program.txt").unwrap();
         let mut computer = Computer::from(program.as_str());
         let mut io = InputOutput::new(&[]);
-        computer.eval(&mut io);
+        computer.eval(&mut io).unwrap();
 
         let map = Map::new(&mut io);
 
@@ -864,7 +782,7 @@ mod tests {
         computer.memory[0] = 2;
         let mut io = InputOutput::new(&[]);
 
-        computer.eval(&mut io);
+        computer.eval(&mut io).unwrap();
         assert_eq!(computer.state, State::WaitingForInput);
 
         let map = Map::new(&mut io);
@@ -874,31 +792,31 @@ mod tests {
         let query = in_q_to_ascii_str(&mut io);
         assert_eq!(query, "Main:\n");
         io.in_q.extend_with_str("B,C,B,A,C,A,C,A,B,A\n");
-        computer.eval(&mut io);
+        computer.eval(&mut io).unwrap();
         assert_eq!(computer.state, State::WaitingForInput);
 
         let query = in_q_to_ascii_str(&mut io);
         assert_eq!(query, "Function A:\n");
         io.in_q.extend_with_str("R,12,L,10,L,6,R,10\n");
-        computer.eval(&mut io);
+        computer.eval(&mut io).unwrap();
         assert_eq!(computer.state, State::WaitingForInput);
 
         let query = in_q_to_ascii_str(&mut io);
         assert_eq!(query, "Function B:\n");
         io.in_q.extend_with_str("R,12,L,6,R,12\n");
-        computer.eval(&mut io);
+        computer.eval(&mut io).unwrap();
         assert_eq!(computer.state, State::WaitingForInput);
 
         let query = in_q_to_ascii_str(&mut io);
         assert_eq!(query, "Function C:\n");
         io.in_q.extend_with_str("L,8,L,6,L,10\n");
-        computer.eval(&mut io);
+        computer.eval(&mut io).unwrap();
         assert_eq!(computer.state, State::WaitingForInput);
 
         let query = in_q_to_ascii_str(&mut io);
         assert_eq!(query, "Continuous video feed?\n");
         io.in_q.extend_with_str("n\n");
-        computer.eval(&mut io);
+        computer.eval(&mut io).unwrap();
         assert_eq!(computer.state, State::Halted);
 
         let _ = Map::new(&mut io);
@@ -906,6 +824,28 @@ mod tests {
         assert_eq!(dust, 997790);
     }
 
+    #[test]
+    fn test_input_compressor() {
+        let program = std::fs::read_to_string("input/program.txt").unwrap();
+        let mut computer = Computer::from(program.as_str());
+        computer.memory[0] = 2;
+        let mut io = InputOutput::new(&[]);
+        computer.eval(&mut io).unwrap();
+
+        let map = Map::new(&mut io);
+        let compression = Compressor::default().solve(&map).unwrap();
+        assert_eq!(compression.expand(), map.simple_path());
+
+        io.out_q.clear();
+        for routine in compression.routines() {
+            io.in_q.extend_with_str(&routine);
+        }
+        io.in_q.extend_with_str("n\n");
+        computer.eval(&mut io).unwrap();
+        assert_eq!(computer.state, State::Halted);
+        assert_eq!(io.out_q.pop_back(), Some(997790));
+    }
+
     fn in_q_to_ascii_str(io: &mut InputOutput) -> String {
         let mut s = String::with_capacity(io.out_q.len());
 
@@ -1,418 +1,111 @@
 #![allow(dead_code)]
 
-use std::{collections::VecDeque, fmt::Display};
+mod springscript;
 
 fn main() {
     println!("Advent of Code 2019 - day 21");
 }
 
-const PARAMETER_MODE_FLAGS: usize = 3;
-
-#[derive(Debug, Clone, Copy, PartialEq)]
-enum ParameterMode {
-    Position,
-    Immediate,
-    Relative,
-}
-
-impl From<isize> for ParameterMode {
-    fn from(value: isize) -> Self {
-        match value {
-            0 => Self::Position,
-            1 => Self::Immediate,
-            2 => Self::Relative,
-            x => panic!("Illegal ParamterMode: {x}!"),
-        }
-    }
-}
-
-impl Default for ParameterMode {
-    fn default() -> Self {
-        Self::Position
-    }
-}
-
-#[derive(Debug, Clone, Copy, PartialEq)]
-enum Mnemonic {
-    Add,
-    Mul,
-    Input,
-    Output,
-    JumpIfTrue,
-    JumpIfFalse,
-    LessThan,
-    Equals,
-    AdjustRelBase,
-    Halt,
-}
-
-impl Mnemonic {
-    fn instruction_pointer_offset(&self) -> usize {
-        match self {
-            Mnemonic::Add | Mnemonic::Mul | Mnemonic::LessThan | Mnemonic::Equals => 4,
-            Mnemonic::Input | Mnemonic::Output | Mnemonic::AdjustRelBase => 2,
-            Mnemonic::Halt => 0,
-            Mnemonic::JumpIfTrue | Mnemonic::JumpIfFalse => 3,
-        }
-    }
-}
-
-impl From<isize> for Mnemonic {
-    fn from(value: isize) -> Self {
-        match value {
-            1 => Self::Add,
-            2 => Self::Mul,
-            3 => Self::Input,
-            4 => Self::Output,
-            5 => Self::JumpIfTrue,
-            6 => Self::JumpIfFalse,
-            7 => Self::LessThan,
-            8 => Self::Equals,
-            9 => Self::AdjustRelBase,
-            99 => Self::Halt,
-            x => panic!("Illegal mnemonic: {x}!"),
-        }
-    }
-}
-
-#[derive(Debug)]
-struct OpCode {
-    mnemonic: Mnemonic,
-    parameter_modes: [ParameterMode; PARAMETER_MODE_FLAGS],
-}
-
-impl OpCode {
-    fn new(mnemonic: Mnemonic, parameter_modes: [ParameterMode; PARAMETER_MODE_FLAGS]) -> Self {
-        match mnemonic {
-            Mnemonic::Add | Mnemonic::Mul | Mnemonic::LessThan | Mnemonic::Equals => {
-                assert_ne!(parameter_modes[2], ParameterMode::Immediate)
-            }
-            Mnemonic::Input => {
-                assert_ne!(parameter_modes[0], ParameterMode::Immediate)
-            }
-            Mnemonic::Output
-            | Mnemonic::JumpIfFalse
-            | Mnemonic::JumpIfTrue
-            | Mnemonic::Halt
-            | Mnemonic::AdjustRelBase => (),
-        }
-
-        Self {
-            mnemonic,
-            parameter_modes,
-        }
-    }
-
-    fn get_paramter(
-        computer: &mut Computer,
-        parameter_mode: ParameterMode,
-        offset: usize,
-    ) -> isize {
-        let offset = computer.instruction_pointer + offset;
-        let memory = &computer.memory;
-        let parameter = memory[offset];
-        match parameter_mode {
-            ParameterMode::Position => memory[usize::try_from(parameter).unwrap()],
-            ParameterMode::Immediate => parameter,
-            ParameterMode::Relative => {
-                let offset = usize::try_from(computer.relative_base + parameter).unwrap();
-                memory[offset]
-            }
-        }
-    }
-
-    fn get_target_idx(
-        computer: &mut Computer,
-        parameter_mode: ParameterMode,
-        offset: usize,
-    ) -> usize {
-        let offset = computer.instruction_pointer + offset;
-        let memory = &computer.memory;
-        let parameter = memory[offset];
-        let p = match parameter_mode {
-            ParameterMode::Position => parameter,
-            ParameterMode::Relative => computer.relative_base + parameter,
-            ParameterMode::Immediate => panic!("Target index cannot be in immediate mode!"),
-        };
-
-        usize::try_from(p).unwrap()
-    }
-
-    fn eval_add(&self, computer: &mut Computer) {
-        let param_1 = Self::get_paramter(computer, self.parameter_modes[0], 1);
-        let param_2 = Self::get_paramter(computer, self.parameter_modes[1], 2);
-        let target_idx = Self::get_target_idx(computer, self.parameter_modes[2], 3);
-        computer.memory[target_idx] = param_1 + param_2;
-        computer.instruction_pointer += Mnemonic::Add.instruction_pointer_offset();
-    }
-
-    fn eval_mul(&self, computer: &mut Computer) {
-        let param_1: isize = Self::get_paramter(computer, self.parameter_modes[0], 1);
-        let param_2 = Self::get_paramter(computer, self.parameter_modes[1], 2);
-        let target_idx = Self::get_target_idx(computer, self.parameter_modes[2], 3);
-        computer.memory[target_idx] = param_1 * param_2;
-        computer.instruction_pointer += Mnemonic::Mul.instruction_pointer_offset();
-    }
-
-    fn eval_input(&self, computer: &mut Computer, io: &mut InputOutput) {
-        let target_idx = Self::get_target_idx(computer, self.parameter_modes[0], 1);
-
-        match io.read_in() {
-            None => computer.state = State::WaitingForInput,
-            Some(next) => {
-                computer.memory[target_idx] = next;
-                computer.instruction_pointer += Mnemonic::Input.instruction_pointer_offset();
-            }
-        }
-    }
-
-    fn eval_output(&self, computer: &mut Computer, io: &mut InputOutput) {
-        let param_1: isize = Self::get_paramter(computer, self.parameter_modes[0], 1);
-        io.write_out(param_1);
-        computer.instruction_pointer += Mnemonic::Output.instruction_pointer_offset();
-    }
-
-    fn eval_jump_if_true(&self, computer: &mut Computer) {
-        let param_1 = Self::get_paramter(computer, self.parameter_modes[0], 1);
-        let param_2 = Self::get_paramter(computer, self.parameter_modes[1], 2);
-
-        if param_1 != 0 {
-            computer.instruction_pointer = usize::try_from(param_2).unwrap();
-        } else {
-            computer.instruction_pointer += Mnemonic::JumpIfTrue.instruction_pointer_offset();
-        }
-    }
-
-    fn eval_jump_if_false(&self, computer: &mut Computer) {
-        let param_1 = Self::get_paramter(computer, self.parameter_modes[0], 1);
-        let param_2 = Self::get_paramter(computer, self.parameter_modes[1], 2);
-
-        if param_1 == 0 {
-            computer.instruction_pointer = usize::try_from(param_2).unwrap();
-        } else {
-            computer.instruction_pointer += Mnemonic::JumpIfFalse.instruction_pointer_offset();
-        }
-    }
-
-    fn eval_less_than(&self, computer: &mut Computer) {
-        let param_1 = Self::get_paramter(computer, self.parameter_modes[0], 1);
-        let param_2 = Self::get_paramter(computer, self.parameter_modes[1], 2);
-        let target_idx = Self::get_target_idx(computer, self.parameter_modes[2], 3);
-        computer.memory[target_idx] = if param_1 < param_2 { 1 } else { 0 };
-        computer.instruction_pointer += Mnemonic::LessThan.instruction_pointer_offset();
-    }
-
-    fn eval_equals(&self, computer: &mut Computer) {
-        let param_1 = Self::get_paramter(computer, self.parameter_modes[0], 1);
-        let param_2 = Self::get_paramter(computer, self.parameter_modes[1], 2);
-        let target_idx = Self::get_target_idx(computer, self.parameter_modes[2], 3);
-        computer.memory[target_idx] = if param_1 == param_2 { 1 } else { 0 };
-        computer.instruction_pointer += Mnemonic::Equals.instruction_pointer_offset();
-    }
-
-    fn eval_adjust_rel_base(&self, computer: &mut Computer) {
-        let param_1 = Self::get_paramter(computer, self.parameter_modes[0], 1);
-        computer.relative_base += param_1;
-        computer.instruction_pointer += Mnemonic::AdjustRelBase.instruction_pointer_offset();
-    }
-
-    fn eval(&self, computer: &mut Computer, io: &mut InputOutput) {
-        match self.mnemonic {
-            Mnemonic::Add => self.eval_add(computer),
-            Mnemonic::Mul => self.eval_mul(computer),
-            Mnemonic::Input => self.eval_input(computer, io),
-            Mnemonic::Output => self.eval_output(computer, io),
-            Mnemonic::JumpIfTrue => self.eval_jump_if_true(computer),
-            Mnemonic::JumpIfFalse => self.eval_jump_if_false(computer),
-            Mnemonic::LessThan => self.eval_less_than(computer),
-            Mnemonic::Equals => self.eval_equals(computer),
-            Mnemonic::AdjustRelBase => self.eval_adjust_rel_base(computer),
-            Mnemonic::Halt => computer.state = State::Halted,
-        }
-    }
-}
-
-impl From<isize> for OpCode {
-    fn from(mut value: isize) -> Self {
-        let mut parameter_modes = [ParameterMode::default(); PARAMETER_MODE_FLAGS];
-
-        let mnemonic = (value % 100).into();
-        value /= 100;
-        for parameter_mode in &mut parameter_modes {
-            *parameter_mode = (value % 10).into();
-            value /= 10;
-        }
-
-        Self::new(mnemonic, parameter_modes)
-    }
-}
-
-struct InputOutput {
-    in_q: VecDeque<isize>,
-    out_q: VecDeque<isize>,
-}
-
-impl Display for InputOutput {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        writeln!(f, "IO:").unwrap();
-        writeln!(f, "  i_q: {:?}", self.in_q).unwrap();
-        writeln!(f, "  o_q: {:?}", self.out_q).unwrap();
-        Ok(())
-    }
-}
-
-impl InputOutput {
-    fn new(initial_in: &[isize]) -> Self {
-        Self {
-            in_q: initial_in.iter().copied().collect(),
-            out_q: VecDeque::new(),
-        }
-    }
-
-    fn read_in(&mut self) -> Option<isize> {
-        self.in_q.pop_front()
-    }
-
-    fn write_out(&mut self, data: isize) {
-        self.out_q.push_back(data);
-    }
-
-    fn write_to_in_q(&mut self, i: isize) {
-        self.in_q.push_back(i)
-    }
-
-    fn write_to_in_q_ascii(&mut self, data: &str) {
-        for c in data.chars() {
-            self.write_to_in_q(c as isize);
-        }
-    }
-
-    fn read_from_out_q(&mut self) -> Option<isize> {
-        self.out_q.pop_front()
-    }
-
-    fn read_from_out_q_ascii(&mut self) -> Option<String> {
-        let mut s = String::new();
-
-        while let Some(i) = self.out_q.pop_front() {
-            if !(0..=255).contains(&i) {
-                self.out_q.push_front(i);
-                break;
-            }
-            s.push(char::from_u32(i as u32).unwrap());
-        }
-
-        if !s.is_empty() {
-            Some(s)
-        } else {
-            None
-        }
-    }
-
-    fn debug(&self) {
-        for (name, q) in [("in_q", &self.in_q), ("out_q", &self.out_q)] {
-            let mut sep = "";
-            let mut ascii = false;
-            println!("{name}:");
-            for i in q {
-                print!("{sep}");
-                if *i >= 0 && *i <= 255 {
-                    if !ascii {
-                        print!("ASCII: ");
-                        ascii = true;
-                    }
-                    print!("{}", char::from_u32(*i as u32).unwrap());
-                    sep = "";
-                } else {
-                    print!("isize: {}", *i);
-                    sep = "\n";
-                }
-            }
-            print!("{sep}");
-        }
-    }
-}
-
-#[derive(Debug, Clone, Copy, PartialEq)]
-enum State {
-    Running,
-    WaitingForInput,
-    Halted,
-}
-
-#[derive(Debug, Clone)]
-struct Computer {
-    state: State,
-    memory: Vec<isize>,
-    instruction_pointer: usize,
-    relative_base: isize,
-}
-
-impl Display for Computer {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        writeln!(f, "Computer:").unwrap();
-        writeln!(f, "  State: {:?}", self.state).unwrap();
-        writeln!(f, "  Instruction Pointer: {}", self.instruction_pointer).unwrap();
-        let window_start = self.instruction_pointer;
-        let window_end = window_start + 4;
-        writeln!(
-            f,
-            "  Memory around ip: {:?}",
-            &self.memory[window_start..window_end]
-        )
-        .unwrap();
-        writeln!(f, "  Relative Base: {}", self.relative_base).unwrap();
-
-        let window_start = usize::try_from((self.relative_base - 3).max(0)).unwrap();
-        let window_end = window_start + 7;
-        writeln!(
-            f,
-            "  Memory around rel: {:?}",
-            &self.memory[window_start..window_end]
-        )
-        .unwrap();
-
-        Ok(())
-    }
-}
-
-impl From<&str> for Computer {
-    fn from(value: &str) -> Self {
-        let mut memory: Vec<isize> = value.split(',').map(|s| s.parse().unwrap()).collect();
-        memory.resize(1_000_000, 0);
-        let instruction_pointer = 0;
+#[cfg(test)]
+mod tests {
+    use intcode::{Computer, InputOutput};
 
-        Self {
-            state: State::Running,
-            memory,
-            instruction_pointer,
-            relative_base: 0,
-        }
-    }
-}
+    use crate::springscript::{compile, run, search, solve, Hull, Mode, Outcome, Program};
 
-impl Computer {
-    fn eval(&mut self, io: &mut InputOutput) {
-        if self.state == State::Halted {
-            panic!("Halted program can't be resumed!")
-        }
+    #[test]
+    fn test_examples() {
+        let program: Program = "NOT A J\nNOT B T\nAND D T\nOR T J\nWALK\n".parse().unwrap();
+        assert_eq!(program.instructions.len(), 4);
+        assert_eq!(
+            program.to_string(),
+            "NOT A J\nNOT B T\nAND D T\nOR T J\nWALK\n"
+        );
+        assert!(program.eval(&[false, true, true, true]));
+        assert!(!program.eval(&[true, true, true, true]));
+
+        let hull: Hull = "#####.###########".parse().unwrap();
+        assert!(program.survives(&hull));
+        assert!(!"NOT D J\nWALK".parse::<Program>().unwrap().survives(&hull));
+        assert!("WALK"
+            .parse::<Program>()
+            .unwrap()
+            .survives(&"#####".parse().unwrap()));
+
+        let output = "\
+Didn't make it across:
+
+.................
+.................
+@................
+#####.##.########
+
+.................
+.................
+.@...............
+#####.##.########
+";
+        assert_eq!(
+            Hull::from_failure(output),
+            Ok(Some("#####.##.########".parse().unwrap()))
+        );
+        assert_eq!(Hull::from_failure("Walking..."), Ok(None));
+        assert_eq!(
+            Hull::from_failure("Didn't make it across:\n\n#@#")
+                .unwrap_err()
+                .msg,
+            "Illegal hull tile '@'!"
+        );
 
-        let mut opcode = OpCode::from(self.memory[self.instruction_pointer]);
+        let errors = [
+            ("NOT A J", "line 1: Expected WALK or RUN, got 'NOT A J'!"),
+            (
+                "NOT E J\nWALK",
+                "line 1: Sensor E is not available in WALK!",
+            ),
+            ("NOT A B\nRUN", "line 1: Illegal register 'B'!"),
+            ("XOR A J\nRUN", "line 1: Illegal operation 'XOR'!"),
+            ("NOT A\nRUN", "line 1: Illegal instruction 'NOT A'!"),
+            (
+                &"NOT A J\n".repeat(16),
+                "line 16: Expected WALK or RUN, got 'NOT A J'!",
+            ),
+            (
+                &format!("{}WALK", "NOT A J\n".repeat(16)),
+                "line 16: More than 15 instructions!",
+            ),
+            ("", "Empty program!"),
+        ];
+        for (program, msg) in errors {
+            assert_eq!(program.parse::<Program>().unwrap_err().msg, msg);
+        }
+
+        let hulls: Vec<Hull> = [
+            "#####.###########",
+            "#####..#.########",
+            "#####.#..########",
+        ]
+        .iter()
+        .map(|h| h.parse().unwrap())
+        .collect();
+        let program = search(Mode::Walk, &hulls, 15).unwrap();
+        assert!(hulls.iter().all(|hull| program.survives(hull)));
+        let program = compile(
+            &[
+                vec![(0, false), (1, false), (2, false)],
+                vec![(3, true)],
+                vec![(4, true), (7, true)],
+            ],
+            Mode::Run,
+        );
+        assert_eq!(
+            program.to_string(),
+            "NOT A J\nNOT J J\nAND B J\nAND C J\nNOT J J\nAND D J\nNOT E T\nNOT T T\nOR H T\nAND T J\nRUN\n"
+        );
+        assert!(program.eval(&[true, false, true, true, false, false, false, true, false]));
+        assert!(!program.eval(&[true, false, true, true, false, false, false, false, false]));
 
-        self.state = State::Running;
-        while self.state == State::Running {
-            opcode.eval(self, io);
-            opcode = OpCode::from(self.memory[self.instruction_pointer]);
-        }
+        assert_eq!(search(Mode::Walk, &["#....#".parse().unwrap()], 15), None);
     }
-}
-
-#[cfg(test)]
-mod tests {
-    use crate::{Computer, InputOutput};
-
-    #[test]
-    fn test_examples() {}
 
     #[test]
     fn test_input() {
@@ -432,7 +125,7 @@ mod tests {
         );
         io.write_to_in_q_ascii("WALK\n");
 
-        computer.eval(&mut io);
+        computer.eval(&mut io).unwrap();
 
         assert_eq!(
             io.read_from_out_q_ascii(),
@@ -463,7 +156,7 @@ mod tests {
         );
         io.write_to_in_q_ascii("RUN\n");
 
-        computer.eval(&mut io);
+        computer.eval(&mut io).unwrap();
 
         assert_eq!(
             io.read_from_out_q_ascii(),
@@ -472,4 +165,41 @@ mod tests {
 
         assert_eq!(io.read_from_out_q(), Some(1141857182));
     }
+
+    #[test]
+    fn test_input_search() {
+        let code = std::fs::read_to_string("input/program.txt").unwrap();
+
+        let program: Program = "NOT A J\nWALK".parse().unwrap();
+        let Ok(Outcome::Fell(hull)) = run(&code, &program) else {
+            panic!("Expected a failed run!");
+        };
+        assert!(!program.survives(&hull));
+
+        assert_eq!(
+            run("104,79,104,75,99", &program).unwrap_err().msg,
+            "Neither hull damage nor a failed run: 'OK'!"
+        );
+        assert_eq!(
+            run("3,0,98", &program).unwrap_err().msg,
+            "Fault at 0002 (opcode 98): Illegal mnemonic: 98!"
+        );
+
+        // A droid that always falls on the same hull, whatever the program.
+        let fall: String = "Didn't make it across:\n\n#####.###\n"
+            .chars()
+            .map(|c| format!("104,{},", c as u32))
+            .collect();
+        assert_eq!(
+            solve(&format!("{fall}99"), Mode::Walk).unwrap_err().msg,
+            "Droid fell on '#####.###' where the program survives!"
+        );
+
+        let (damage, hulls) = solve(&code, Mode::Walk).unwrap();
+        assert_eq!(damage, 19348840);
+        assert!(!hulls.is_empty());
+
+        let (damage, _) = solve(&code, Mode::Run).unwrap();
+        assert_eq!(damage, 1141857182);
+    }
 }
@@ -0,0 +1,550 @@
+use std::{collections::HashMap, fmt::Display, str::FromStr};
+
+use intcode::{Computer, Fault, InputOutput};
+
+const MAX_INSTRUCTIONS: usize = 15;
+const JUMP_DISTANCE: usize = 4;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SpringscriptError {
+    pub msg: String,
+}
+
+impl Display for SpringscriptError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.msg)
+    }
+}
+
+impl From<Fault> for SpringscriptError {
+    fn from(value: Fault) -> Self {
+        Self {
+            msg: value.to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Mode {
+    Walk,
+    Run,
+}
+
+impl Mode {
+    pub fn sensors(&self) -> usize {
+        match self {
+            Mode::Walk => 4,
+            Mode::Run => 9,
+        }
+    }
+}
+
+impl Display for Mode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Mode::Walk => write!(f, "WALK"),
+            Mode::Run => write!(f, "RUN"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Op {
+    And,
+    Or,
+    Not,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Register {
+    Sensor(usize),
+    T,
+    J,
+}
+
+impl Display for Register {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Register::Sensor(idx) => write!(f, "{}", (b'A' + *idx as u8) as char),
+            Register::T => write!(f, "T"),
+            Register::J => write!(f, "J"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Instruction {
+    pub op: Op,
+    pub x: Register,
+    pub y: Register,
+}
+
+impl Display for Instruction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let op = match self.op {
+            Op::And => "AND",
+            Op::Or => "OR",
+            Op::Not => "NOT",
+        };
+        write!(f, "{op} {} {}", self.x, self.y)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Program {
+    pub instructions: Vec<Instruction>,
+    pub mode: Mode,
+}
+
+impl Program {
+    pub fn eval(&self, sensors: &[bool]) -> bool {
+        let (mut t, mut j) = (false, false);
+        for instruction in &self.instructions {
+            let x = match instruction.x {
+                Register::Sensor(idx) => sensors[idx],
+                Register::T => t,
+                Register::J => j,
+            };
+            let y = match instruction.y {
+                Register::T => &mut t,
+                _ => &mut j,
+            };
+            *y = match instruction.op {
+                Op::And => x && *y,
+                Op::Or => x || *y,
+                Op::Not => !x,
+            };
+        }
+        j
+    }
+
+    pub fn survives(&self, hull: &Hull) -> bool {
+        hull.walk(|pos| self.eval(&hull.sensors(pos, self.mode)))
+    }
+}
+
+impl Display for Program {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for instruction in &self.instructions {
+            writeln!(f, "{instruction}")?;
+        }
+        writeln!(f, "{}", self.mode)
+    }
+}
+
+impl FromStr for Program {
+    type Err = SpringscriptError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let lines: Vec<(usize, &str)> = s
+            .lines()
+            .map(|l| l.trim())
+            .enumerate()
+            .filter(|(_, l)| !l.is_empty())
+            .collect();
+        let err = |line: usize, msg: String| SpringscriptError {
+            msg: format!("line {}: {msg}", line + 1),
+        };
+
+        let Some((&(last, mode), instructions)) = lines.split_last() else {
+            return Err(SpringscriptError {
+                msg: "Empty program!".to_string(),
+            });
+        };
+        let mode = match mode {
+            "WALK" => Mode::Walk,
+            "RUN" => Mode::Run,
+            _ => return Err(err(last, format!("Expected WALK or RUN, got '{mode}'!"))),
+        };
+        if instructions.len() > MAX_INSTRUCTIONS {
+            return Err(err(
+                instructions[MAX_INSTRUCTIONS].0,
+                format!("More than {MAX_INSTRUCTIONS} instructions!"),
+            ));
+        }
+
+        let register = |line: usize, name: &str, writable: bool| {
+            let register = match name {
+                "T" => Register::T,
+                "J" => Register::J,
+                _ if !writable && name.len() == 1 && ("A"..="I").contains(&name) => {
+                    let idx = (name.as_bytes()[0] - b'A') as usize;
+                    if idx >= mode.sensors() {
+                        return Err(err(
+                            line,
+                            format!("Sensor {name} is not available in {mode}!"),
+                        ));
+                    }
+                    Register::Sensor(idx)
+                }
+                _ => return Err(err(line, format!("Illegal register '{name}'!"))),
+            };
+            Ok(register)
+        };
+
+        let instructions = instructions
+            .iter()
+            .map(|(line, instruction)| {
+                let parts: Vec<&str> = instruction.split_whitespace().collect();
+                let [op, x, y] = parts[..] else {
+                    return Err(err(*line, format!("Illegal instruction '{instruction}'!")));
+                };
+                let op = match op {
+                    "AND" => Op::And,
+                    "OR" => Op::Or,
+                    "NOT" => Op::Not,
+                    _ => return Err(err(*line, format!("Illegal operation '{op}'!"))),
+                };
+                Ok(Instruction {
+                    op,
+                    x: register(*line, x, false)?,
+                    y: register(*line, y, true)?,
+                })
+            })
+            .collect::<Result<_, _>>()?;
+
+        Ok(Self { instructions, mode })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Hull {
+    pub ground: Vec<bool>,
+}
+
+impl Hull {
+    // Failed runs replay the droid's fall, the first frame shows the whole hull below it.
+    pub fn from_failure(output: &str) -> Result<Option<Self>, SpringscriptError> {
+        let Some((_, frames)) = output.split_once("Didn't make it across:") else {
+            return Ok(None);
+        };
+        frames
+            .lines()
+            .map(|l| l.trim())
+            .find(|l| l.starts_with('#'))
+            .map(|l| l.parse())
+            .transpose()
+    }
+
+    fn is_ground(&self, pos: usize) -> bool {
+        self.ground.get(pos).copied().unwrap_or(true)
+    }
+
+    pub fn sensors(&self, pos: usize, mode: Mode) -> Vec<bool> {
+        (1..=mode.sensors())
+            .map(|offset| self.is_ground(pos + offset))
+            .collect()
+    }
+
+    pub fn walk(&self, mut jump: impl FnMut(usize) -> bool) -> bool {
+        let mut pos = 0;
+        while pos < self.ground.len() {
+            pos += if jump(pos) { JUMP_DISTANCE } else { 1 };
+            if !self.is_ground(pos) {
+                return false;
+            }
+        }
+        true
+    }
+}
+
+impl Display for Hull {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for ground in &self.ground {
+            write!(f, "{}", if *ground { '#' } else { '.' })?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for Hull {
+    type Err = SpringscriptError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let ground = s
+            .trim()
+            .chars()
+            .map(|c| match c {
+                '#' => Ok(true),
+                '.' => Ok(false),
+                _ => Err(SpringscriptError {
+                    msg: format!("Illegal hull tile '{c}'!"),
+                }),
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(Self { ground })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Outcome {
+    Damage(isize),
+    Fell(Hull),
+}
+
+pub fn run(code: &str, program: &Program) -> Result<Outcome, SpringscriptError> {
+    let mut computer = Computer::from(code);
+    let mut io = InputOutput::new(&[]);
+    io.write_to_in_q_ascii(&program.to_string());
+    computer.eval(&mut io)?;
+
+    let output = io.read_from_out_q_ascii().unwrap_or_default();
+    if let Some(damage) = io.read_from_out_q() {
+        return Ok(Outcome::Damage(damage));
+    }
+    match Hull::from_failure(&output)? {
+        Some(hull) => Ok(Outcome::Fell(hull)),
+        None => Err(SpringscriptError {
+            msg: format!("Neither hull damage nor a failed run: '{}'!", output.trim()),
+        }),
+    }
+}
+
+type Literal = (usize, bool);
+
+fn sensor_instruction(op: Op, (sensor, _): Literal, y: Register) -> Instruction {
+    Instruction {
+        op,
+        x: Register::Sensor(sensor),
+        y,
+    }
+}
+
+// Computes a disjunction of literals into `y`, negative literals go through De Morgan.
+fn clause_instructions(clause: &[Literal], y: Register) -> Vec<Instruction> {
+    let not_y = Instruction {
+        op: Op::Not,
+        x: y,
+        y,
+    };
+    let (negative, positive): (Vec<Literal>, Vec<Literal>) = clause.iter().partition(|l| !l.1);
+    let mut positive = positive.into_iter();
+
+    let mut instructions = Vec::new();
+    match negative.as_slice() {
+        [] => {
+            let first = positive.next().expect("Empty clause!");
+            instructions.extend([sensor_instruction(Op::Not, first, y), not_y]);
+        }
+        [literal] => instructions.push(sensor_instruction(Op::Not, *literal, y)),
+        [first, rest @ ..] => {
+            instructions.extend([sensor_instruction(Op::Not, *first, y), not_y]);
+            instructions.extend(rest.iter().map(|l| sensor_instruction(Op::And, *l, y)));
+            instructions.push(not_y);
+        }
+    }
+    instructions.extend(positive.map(|l| sensor_instruction(Op::Or, l, y)));
+    instructions
+}
+
+fn is_single_positive(clause: &[Literal]) -> bool {
+    matches!(clause, [(_, true)])
+}
+
+pub fn compile(clauses: &[Vec<Literal>], mode: Mode) -> Program {
+    let mut clauses: Vec<&Vec<Literal>> = clauses.iter().collect();
+    // Single positive literals are a plain AND into J, so something else goes first.
+    if let Some(idx) = clauses.iter().position(|c| !is_single_positive(c)) {
+        clauses.swap(0, idx);
+    }
+
+    let mut instructions = Vec::new();
+    for (idx, clause) in clauses.into_iter().enumerate() {
+        if idx == 0 {
+            instructions.extend(clause_instructions(clause, Register::J));
+        } else if is_single_positive(clause) {
+            instructions.push(sensor_instruction(Op::And, clause[0], Register::J));
+        } else {
+            instructions.extend(clause_instructions(clause, Register::T));
+            instructions.push(Instruction {
+                op: Op::And,
+                x: Register::T,
+                y: Register::J,
+            });
+        }
+    }
+
+    Program { instructions, mode }
+}
+
+struct Search<'a> {
+    hulls: &'a [Hull],
+    positions: Vec<Vec<usize>>,
+    clauses: Vec<(Vec<Literal>, Vec<u64>, usize)>,
+    max_len: usize,
+    best: Option<(usize, Vec<usize>)>,
+}
+
+impl Search<'_> {
+    fn survives(&self, jump: &[u64]) -> bool {
+        self.hulls
+            .iter()
+            .zip(&self.positions)
+            .all(|(hull, positions)| {
+                hull.walk(|pos| jump[positions[pos] / 64] & (1 << (positions[pos] % 64)) != 0)
+            })
+    }
+
+    fn len(&self, chosen: &[usize]) -> usize {
+        let len: usize = chosen.iter().map(|idx| self.clauses[*idx].2).sum();
+        match chosen
+            .iter()
+            .any(|idx| !is_single_positive(&self.clauses[*idx].0))
+        {
+            true => len - 1,
+            false if chosen.is_empty() => 0,
+            false => len + 1,
+        }
+    }
+
+    fn visit(&mut self, chosen: &mut Vec<usize>, jump: &[u64], depth: usize) {
+        let len = self.len(chosen);
+        let bound = self.best.as_ref().map_or(self.max_len + 1, |(len, _)| *len);
+        if len >= bound {
+            return;
+        }
+        if chosen.len() == depth {
+            if self.survives(jump) {
+                self.best = Some((len, chosen.clone()));
+            }
+            return;
+        }
+
+        let start = chosen.last().map_or(0, |idx| idx + 1);
+        for idx in start..self.clauses.len() {
+            let next: Vec<u64> = jump
+                .iter()
+                .zip(&self.clauses[idx].1)
+                .map(|(j, c)| j & c)
+                .collect();
+            if next == jump {
+                continue;
+            }
+            chosen.push(idx);
+            self.visit(chosen, &next, depth);
+            chosen.pop();
+        }
+    }
+}
+
+// Searches conjunctions of up to four clauses over the sensors, each clause being a disjunction of up
+// to three (possibly negated) sensors. Clauses have to allow every jump that is the only way forward,
+// which keeps the candidates down to a few per clause slot.
+pub fn search(mode: Mode, hulls: &[Hull], max_len: usize) -> Option<Program> {
+    let mut views: HashMap<Vec<bool>, usize> = HashMap::new();
+    let positions: Vec<Vec<usize>> = hulls
+        .iter()
+        .map(|hull| {
+            (0..hull.ground.len())
+                .map(|pos| {
+                    let next = views.len();
+                    *views.entry(hull.sensors(pos, mode)).or_insert(next)
+                })
+                .collect()
+        })
+        .collect();
+
+    let words = views.len().div_ceil(64).max(1);
+    let bit = |idx: usize, table: &mut Vec<u64>| table[idx / 64] |= 1 << (idx % 64);
+    let mut forced = vec![0u64; words];
+    for (hull, positions) in hulls.iter().zip(&positions) {
+        for (pos, view) in positions.iter().enumerate() {
+            if hull.is_ground(pos)
+                && !hull.is_ground(pos + 1)
+                && hull.is_ground(pos + JUMP_DISTANCE)
+            {
+                bit(*view, &mut forced);
+            }
+        }
+    }
+
+    let literal_table = |(sensor, positive): Literal| {
+        let mut table = vec![0u64; words];
+        for (view, idx) in &views {
+            if view[sensor] == positive {
+                bit(*idx, &mut table);
+            }
+        }
+        table
+    };
+
+    let mut clauses = Vec::new();
+    let mut stack: Vec<Vec<Literal>> = (0..mode.sensors())
+        .flat_map(|sensor| [vec![(sensor, true)], vec![(sensor, false)]])
+        .collect();
+    while let Some(clause) = stack.pop() {
+        let table = clause.iter().fold(vec![0u64; words], |table, literal| {
+            table
+                .iter()
+                .zip(literal_table(*literal))
+                .map(|(t, l)| t | l)
+                .collect()
+        });
+        if clause.len() < 3 {
+            let last = clause.last().unwrap().0;
+            for sensor in last + 1..mode.sensors() {
+                for positive in [true, false] {
+                    let mut next = clause.clone();
+                    next.push((sensor, positive));
+                    stack.push(next);
+                }
+            }
+        }
+        if forced.iter().zip(&table).all(|(f, t)| f & !t == 0) {
+            let len = if is_single_positive(&clause) {
+                1
+            } else {
+                clause_instructions(&clause, Register::T).len() + 1
+            };
+            clauses.push((clause, table, len));
+        }
+    }
+    clauses.sort_by_key(|(clause, _, len)| (*len, clause.clone()));
+
+    let mut search = Search {
+        hulls,
+        positions,
+        clauses,
+        max_len: max_len.min(MAX_INSTRUCTIONS),
+        best: None,
+    };
+    let mut jump = vec![u64::MAX; words];
+    if !views.len().is_multiple_of(64) {
+        jump[words - 1] = (1 << (views.len() % 64)) - 1;
+    }
+
+    if search.survives(&vec![0; words]) {
+        return Some(compile(&[], mode));
+    }
+    for depth in 1..=4 {
+        search.visit(&mut Vec::new(), &jump, depth);
+        if let Some((_, chosen)) = &search.best {
+            let clauses: Vec<Vec<Literal>> = chosen
+                .iter()
+                .map(|idx| search.clauses[*idx].0.clone())
+                .collect();
+            return Some(compile(&clauses, mode));
+        }
+    }
+
+    None
+}
+
+pub fn solve(code: &str, mode: Mode) -> Result<(isize, Vec<Hull>), SpringscriptError> {
+    let mut hulls = Vec::new();
+    loop {
+        let program = search(mode, &hulls, MAX_INSTRUCTIONS).ok_or_else(|| SpringscriptError {
+            msg: format!("No program survives all {} hulls!", hulls.len()),
+        })?;
+        match run(code, &program)? {
+            Outcome::Damage(damage) => return Ok((damage, hulls)),
+            // This includes every known hull, searching again would find the same program.
+            Outcome::Fell(hull) if program.survives(&hull) => {
+                return Err(SpringscriptError {
+                    msg: format!("Droid fell on '{hull}' where the program survives!"),
+                })
+            }
+            Outcome::Fell(hull) => hulls.push(hull),
+        }
+    }
+}
@@ -0,0 +1,279 @@
+use std::{
+    collections::{HashMap, HashSet},
+    fmt::Display,
+};
+
+use intcode::{Computer, Fault, InputOutput, State};
+
+const STEP_BUDGET: usize = 1_000_000;
+const CHECKPOINT: &str = "Security Checkpoint";
+const PRESSURE_FLOOR: &str = "Pressure-Sensitive Floor";
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ExploreError {
+    pub msg: String,
+}
+
+impl Display for ExploreError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.msg)
+    }
+}
+
+impl From<Fault> for ExploreError {
+    fn from(value: Fault) -> Self {
+        Self {
+            msg: value.to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Room {
+    pub name: String,
+    pub description: String,
+    pub doors: Vec<String>,
+    pub items: Vec<String>,
+}
+
+impl Room {
+    // Output can describe several rooms, e.g. when the droid is ejected, the last one counts.
+    pub fn parse(output: &str) -> Option<Room> {
+        let start = output.rfind("== ")?;
+        let mut lines = output[start..].lines();
+        let name = lines
+            .next()?
+            .trim_start_matches("== ")
+            .trim_end_matches(" ==")
+            .to_string();
+
+        let mut room = Room {
+            name,
+            ..Default::default()
+        };
+        let mut list = None;
+        for line in lines.map(|l| l.trim()) {
+            match line {
+                "Doors here lead:" => list = Some(&mut room.doors),
+                "Items here:" => list = Some(&mut room.items),
+                "" => list = None,
+                _ => match (line.strip_prefix("- "), &mut list) {
+                    (Some(entry), Some(list)) => list.push(entry.to_string()),
+                    _ if room.description.is_empty() => room.description = line.to_string(),
+                    _ => (),
+                },
+            }
+        }
+
+        Some(room)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Response {
+    Prompt(String),
+    Halted(String),
+    Looping,
+}
+
+impl Response {
+    pub fn output(&self) -> &str {
+        match self {
+            Response::Prompt(output) | Response::Halted(output) => output,
+            Response::Looping => "",
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Droid {
+    computer: Computer,
+    io: InputOutput,
+}
+
+impl From<&str> for Droid {
+    fn from(value: &str) -> Self {
+        Self {
+            computer: Computer::from(value),
+            io: InputOutput::new(&[]),
+        }
+    }
+}
+
+impl Droid {
+    pub fn start(&mut self) -> Result<Response, ExploreError> {
+        self.run()
+    }
+
+    pub fn send(&mut self, command: &str) -> Result<Response, ExploreError> {
+        self.io.write_to_in_q_ascii(&format!("{command}\n"));
+        self.run()
+    }
+
+    // Some items trap the program in an endless loop, so execution is bounded.
+    fn run(&mut self) -> Result<Response, ExploreError> {
+        for _ in 0..STEP_BUDGET {
+            self.computer.step(&mut self.io)?;
+            if self.computer.state != State::Running {
+                let output = self.io.read_from_out_q_ascii().unwrap_or_default();
+                return Ok(match self.computer.state {
+                    State::Halted => Response::Halted(output),
+                    _ => Response::Prompt(output),
+                });
+            }
+        }
+
+        Ok(Response::Looping)
+    }
+}
+
+fn opposite(door: &str) -> &str {
+    match door {
+        "north" => "south",
+        "south" => "north",
+        "east" => "west",
+        "west" => "east",
+        _ => door,
+    }
+}
+
+pub fn password(output: &str) -> Option<isize> {
+    let (_, rest) = output.split_once("typing ")?;
+    rest.split_whitespace().next()?.parse().ok()
+}
+
+#[derive(Debug, Clone)]
+pub struct Explorer {
+    droid: Droid,
+    pub rooms: HashMap<String, Room>,
+    pub deadly: HashSet<String>,
+    pub inventory: Vec<String>,
+    pub checkpoint: Option<(Vec<String>, String)>,
+}
+
+impl Explorer {
+    pub fn new(program: &str) -> Self {
+        Self {
+            droid: Droid::from(program),
+            rooms: HashMap::new(),
+            deadly: HashSet::new(),
+            inventory: Vec::new(),
+            checkpoint: None,
+        }
+    }
+
+    pub fn explore(&mut self) -> Result<(), ExploreError> {
+        let response = self.droid.start()?;
+        let room = Room::parse(response.output()).ok_or_else(|| ExploreError {
+            msg: "No room at the start!".to_string(),
+        })?;
+        self.visit(room, &mut Vec::new())
+    }
+
+    fn visit(&mut self, room: Room, path: &mut Vec<String>) -> Result<(), ExploreError> {
+        self.rooms.insert(room.name.clone(), room.clone());
+
+        for item in &room.items {
+            if self.is_safe(item, &room)? {
+                self.command(&format!("take {item}"))?;
+                self.inventory.push(item.clone());
+            } else {
+                self.deadly.insert(item.clone());
+            }
+        }
+
+        for door in &room.doors {
+            let output = self.command(door)?;
+            let next = Room::parse(&output).ok_or_else(|| ExploreError {
+                msg: format!("No room behind '{door}' in '{}'!", room.name),
+            })?;
+
+            // The pressure-sensitive floor ejects the droid back unless it has the right weight.
+            if next.name == room.name && output.contains(PRESSURE_FLOOR) {
+                self.checkpoint = Some((path.clone(), door.clone()));
+                continue;
+            }
+            if self.rooms.contains_key(&next.name) {
+                self.command(opposite(door))?;
+                continue;
+            }
+
+            path.push(door.clone());
+            self.visit(next, path)?;
+            path.pop();
+            self.command(opposite(door))?;
+        }
+
+        Ok(())
+    }
+
+    // Items are tried on a copy of the droid, deadly ones halt, loop or keep it from moving.
+    fn is_safe(&self, item: &str, room: &Room) -> Result<bool, ExploreError> {
+        let mut droid = self.droid.clone();
+        let Response::Prompt(output) = droid.send(&format!("take {item}"))? else {
+            return Ok(false);
+        };
+        if !output.contains(&format!("You take the {item}.")) {
+            return Ok(false);
+        }
+
+        let Some(door) = room.doors.first() else {
+            return Ok(true);
+        };
+        match droid.send(door)? {
+            Response::Prompt(output) => Ok(Room::parse(&output).is_some()),
+            _ => Ok(false),
+        }
+    }
+
+    fn command(&mut self, command: &str) -> Result<String, ExploreError> {
+        match self.droid.send(command)? {
+            Response::Prompt(output) => Ok(output),
+            _ => Err(ExploreError {
+                msg: format!("Droid lost after '{command}'!"),
+            }),
+        }
+    }
+
+    pub fn solve(&mut self) -> Result<isize, ExploreError> {
+        let (path, door) = self.checkpoint.clone().ok_or_else(|| ExploreError {
+            msg: format!("No '{PRESSURE_FLOOR}' found!"),
+        })?;
+        for step in &path {
+            self.command(step)?;
+        }
+
+        for item in self.inventory.clone() {
+            self.command(&format!("drop {item}"))?;
+        }
+
+        // Walk the subsets in gray code order so each attempt changes a single item.
+        let mut carried = vec![false; self.inventory.len()];
+        for n in 0..1usize << self.inventory.len() {
+            if n > 0 {
+                let idx = n.trailing_zeros() as usize;
+                let item = &self.inventory[idx];
+                let verb = if carried[idx] { "drop" } else { "take" };
+                self.command(&format!("{verb} {item}"))?;
+                carried[idx] = !carried[idx];
+            }
+
+            let mut droid = self.droid.clone();
+            if let Response::Halted(output) = droid.send(&door)? {
+                if let Some(password) = password(&output) {
+                    return Ok(password);
+                }
+            }
+        }
+
+        Err(ExploreError {
+            msg: format!("No item combination passes the '{CHECKPOINT}'!"),
+        })
+    }
+}
+
+pub fn find_password(program: &str) -> Result<isize, ExploreError> {
+    let mut explorer = Explorer::new(program);
+    explorer.explore()?;
+    explorer.solve()
+}
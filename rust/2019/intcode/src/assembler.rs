@@ -0,0 +1,471 @@
+use std::{collections::HashMap, fmt::Display};
+
+use crate::{Mnemonic, OpCode, ParameterMode, PARAMETER_MODE_FLAGS};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AssemblerError {
+    pub line: usize,
+    pub msg: String,
+}
+
+impl Display for AssemblerError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "line {}: {}", self.line, self.msg)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Expr {
+    Number(isize),
+    Label(String, isize),
+}
+
+impl Expr {
+    fn parse(s: &str) -> Result<Self, String> {
+        let s = s.trim();
+        if let Ok(n) = s.parse() {
+            return Ok(Self::Number(n));
+        }
+
+        let (name, offset) = match s.rfind(['+', '-']) {
+            Some(idx) if idx > 0 => {
+                let offset = s[idx..]
+                    .replace(' ', "")
+                    .trim_start_matches('+')
+                    .parse()
+                    .map_err(|_| format!("Illegal offset in '{s}'!"))?;
+                (s[..idx].trim(), offset)
+            }
+            _ => (s, 0),
+        };
+
+        if !is_identifier(name) {
+            return Err(format!("Illegal expression '{s}'!"));
+        }
+        Ok(Self::Label(name.to_string(), offset))
+    }
+
+    fn resolve(&self, labels: &HashMap<String, usize>) -> Result<isize, String> {
+        match self {
+            Self::Number(n) => Ok(*n),
+            Self::Label(name, offset) => {
+                let address = labels
+                    .get(name)
+                    .ok_or_else(|| format!("Unknown label '{name}'!"))?;
+                isize::try_from(*address)
+                    .ok()
+                    .and_then(|address| address.checked_add(*offset))
+                    .ok_or_else(|| format!("Address of '{name}{offset:+}' out of range!"))
+            }
+        }
+    }
+}
+
+fn is_identifier(s: &str) -> bool {
+    let mut chars = s.chars();
+    matches!(chars.next(), Some(c) if c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Arg {
+    parameter_mode: ParameterMode,
+    expr: Expr,
+}
+
+impl Arg {
+    fn parse(s: &str) -> Result<Self, String> {
+        let s = s.trim();
+
+        let (parameter_mode, expr) = if let Some(inner) = s.strip_prefix('#') {
+            (ParameterMode::Immediate, Expr::parse(inner)?)
+        } else if let Some(inner) = s.strip_prefix('[').and_then(|s| s.strip_suffix(']')) {
+            (ParameterMode::Position, Expr::parse(inner)?)
+        } else if let Some(offset) = s.strip_prefix("rb") {
+            let offset = offset.trim();
+            let expr = if offset.is_empty() {
+                Expr::Number(0)
+            } else if let Some(offset) = offset.strip_prefix('+') {
+                Expr::parse(offset)?
+            } else if offset.starts_with('-') {
+                Expr::parse(offset)?
+            } else {
+                return Err(format!("Illegal relative operand '{s}'!"));
+            };
+            (ParameterMode::Relative, expr)
+        } else {
+            return Err(format!(
+                "Operand '{s}' needs a parameter mode ('[pos]', '#imm' or 'rb+off')!"
+            ));
+        };
+
+        Ok(Self {
+            parameter_mode,
+            expr,
+        })
+    }
+
+    fn immediate(expr: Expr) -> Self {
+        Self {
+            parameter_mode: ParameterMode::Immediate,
+            expr,
+        }
+    }
+
+    fn relative(offset: isize) -> Self {
+        Self {
+            parameter_mode: ParameterMode::Relative,
+            expr: Expr::Number(offset),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Item {
+    Instruction(Mnemonic, Vec<Arg>),
+    Data(Vec<Expr>),
+}
+
+impl Item {
+    fn size(&self) -> usize {
+        match self {
+            Self::Instruction(mnemonic, _) => mnemonic.parameter_count() + 1,
+            Self::Data(values) => values.len(),
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Statement {
+    line: usize,
+    address: usize,
+    item: Item,
+}
+
+fn strip_comment(line: &str) -> &str {
+    let mut in_string = false;
+    for (idx, c) in line.char_indices() {
+        match c {
+            '"' => in_string = !in_string,
+            ';' if !in_string => return &line[..idx],
+            _ => (),
+        }
+    }
+    line
+}
+
+fn split_args(s: &str) -> Vec<&str> {
+    let mut args = Vec::new();
+    let mut in_string = false;
+    let mut start = 0;
+    for (idx, c) in s.char_indices() {
+        match c {
+            '"' => in_string = !in_string,
+            ',' if !in_string => {
+                args.push(s[start..idx].trim());
+                start = idx + 1;
+            }
+            _ => (),
+        }
+    }
+    if !s[start..].trim().is_empty() || !args.is_empty() {
+        args.push(s[start..].trim());
+    }
+    args
+}
+
+fn parse_data(args: &str) -> Result<Vec<Expr>, String> {
+    let mut values = Vec::new();
+    for arg in split_args(args) {
+        if let Some(text) = arg.strip_prefix('"').and_then(|a| a.strip_suffix('"')) {
+            let text = text.replace("\\n", "\n");
+            values.extend(text.chars().map(|c| Expr::Number(c as isize)));
+        } else {
+            values.push(Expr::parse(arg)?);
+        }
+    }
+    Ok(values)
+}
+
+fn parse_target(arg: &str) -> Result<Expr, String> {
+    let arg = arg.trim();
+    Expr::parse(arg.strip_prefix('#').unwrap_or(arg))
+}
+
+fn expect_args<'a>(helper: &str, args: &'a str, count: usize) -> Result<Vec<&'a str>, String> {
+    let args = split_args(args);
+    if args.len() != count {
+        return Err(format!(
+            "'{helper}' expects {count} operand(s), got {}!",
+            args.len()
+        ));
+    }
+    Ok(args)
+}
+
+fn parse_items(mnemonic: &str, args: &str, address: usize) -> Result<Vec<Item>, String> {
+    let items = match mnemonic {
+        "db" => vec![Item::Data(parse_data(args)?)],
+        "jmp" => {
+            let args = expect_args(mnemonic, args, 1)?;
+            vec![Item::Instruction(
+                Mnemonic::JumpIfTrue,
+                vec![
+                    Arg::immediate(Expr::Number(1)),
+                    Arg::immediate(parse_target(args[0])?),
+                ],
+            )]
+        }
+        "call" => {
+            let args = expect_args(mnemonic, args, 1)?;
+            let push = Mnemonic::Add.parameter_count() + 1;
+            let jump = Mnemonic::JumpIfTrue.parameter_count() + 1;
+            let return_address = (address + push + jump) as isize;
+            vec![
+                Item::Instruction(
+                    Mnemonic::Add,
+                    vec![
+                        Arg::immediate(Expr::Number(return_address)),
+                        Arg::immediate(Expr::Number(0)),
+                        Arg::relative(0),
+                    ],
+                ),
+                Item::Instruction(
+                    Mnemonic::JumpIfTrue,
+                    vec![
+                        Arg::immediate(Expr::Number(1)),
+                        Arg::immediate(parse_target(args[0])?),
+                    ],
+                ),
+            ]
+        }
+        "ret" => {
+            expect_args(mnemonic, args, 0)?;
+            vec![Item::Instruction(
+                Mnemonic::JumpIfFalse,
+                vec![Arg::immediate(Expr::Number(0)), Arg::relative(0)],
+            )]
+        }
+        "enter" | "leave" => {
+            let args = expect_args(mnemonic, args, 1)?;
+            let Expr::Number(n) = Expr::parse(args[0])? else {
+                return Err(format!("'{mnemonic}' expects a number!"));
+            };
+            let n = if mnemonic == "enter" { n } else { -n };
+            vec![Item::Instruction(
+                Mnemonic::AdjustRelBase,
+                vec![Arg::immediate(Expr::Number(n))],
+            )]
+        }
+        _ => {
+            let mnemonic: Mnemonic = mnemonic.parse().map_err(|e: crate::TryFromError| e.msg)?;
+            let args = split_args(args)
+                .into_iter()
+                .map(Arg::parse)
+                .collect::<Result<Vec<_>, _>>()?;
+            if args.len() != mnemonic.parameter_count() {
+                return Err(format!(
+                    "'{mnemonic}' expects {} operand(s), got {}!",
+                    mnemonic.parameter_count(),
+                    args.len()
+                ));
+            }
+            if let Some(target) = mnemonic.target_parameter() {
+                if args[target].parameter_mode == ParameterMode::Immediate {
+                    return Err(format!(
+                        "Target parameter of {mnemonic} cannot be in immediate mode!"
+                    ));
+                }
+            }
+            vec![Item::Instruction(mnemonic, args)]
+        }
+    };
+
+    Ok(items)
+}
+
+fn parse(source: &str) -> Result<(Vec<Statement>, HashMap<String, usize>), AssemblerError> {
+    let mut statements = Vec::new();
+    let mut labels = HashMap::new();
+    let mut address = 0;
+
+    for (idx, line) in source.lines().enumerate() {
+        let line_no = idx + 1;
+        let err = |msg: String| AssemblerError { line: line_no, msg };
+        let mut line = strip_comment(line).trim();
+
+        while let Some((label, rest)) = line.split_once(':') {
+            let label = label.trim();
+            if !is_identifier(label) {
+                break;
+            }
+            if labels.insert(label.to_string(), address).is_some() {
+                return Err(err(format!("Duplicate label '{label}'!")));
+            }
+            line = rest.trim();
+        }
+
+        if line.is_empty() {
+            continue;
+        }
+
+        let (mnemonic, args) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+        for item in parse_items(mnemonic, args, address).map_err(err)? {
+            let size = item.size();
+            statements.push(Statement {
+                line: line_no,
+                address,
+                item,
+            });
+            address += size;
+        }
+    }
+
+    Ok((statements, labels))
+}
+
+pub fn assemble(source: &str) -> Result<Vec<isize>, AssemblerError> {
+    let (statements, labels) = parse(source)?;
+    let mut program = Vec::new();
+
+    for statement in statements {
+        let err = |msg: String| AssemblerError {
+            line: statement.line,
+            msg,
+        };
+        debug_assert_eq!(statement.address, program.len());
+
+        match statement.item {
+            Item::Data(values) => {
+                for value in values {
+                    program.push(value.resolve(&labels).map_err(err)?);
+                }
+            }
+            Item::Instruction(mnemonic, args) => {
+                let mut parameter_modes = [ParameterMode::default(); PARAMETER_MODE_FLAGS];
+                for (parameter_mode, arg) in parameter_modes.iter_mut().zip(args.iter()) {
+                    *parameter_mode = arg.parameter_mode;
+                }
+                program.push(
+                    OpCode::new(mnemonic, parameter_modes)
+                        .map_err(|e| err(e.msg))?
+                        .encode(),
+                );
+                for arg in args {
+                    program.push(arg.expr.resolve(&labels).map_err(err)?);
+                }
+            }
+        }
+    }
+
+    Ok(program)
+}
+
+pub fn assemble_to_string(source: &str) -> Result<String, AssemblerError> {
+    Ok(assemble(source)?
+        .iter()
+        .map(|v| v.to_string())
+        .collect::<Vec<_>>()
+        .join(","))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{assemble, assemble_to_string, parse_program, Computer, InputOutput, Listing};
+
+    #[test]
+    fn test_examples() {
+        let source = "\
+            input [value]          ; read a number\n\
+            equals [value], #8, [value]\n\
+            output [value]\n\
+            halt\n\
+            value: db -1\n\
+        ";
+        assert_eq!(
+            assemble_to_string(source).unwrap(),
+            "3,9,1008,9,8,9,4,9,99,-1"
+        );
+
+        let source = "\
+            adjust_rel_base #1\n\
+            loop:\n\
+                output rb-1\n\
+                add [counter], #1, [counter]\n\
+                equals [counter], #16, [done]\n\
+                jump_if_false [done], #loop\n\
+                halt\n\
+            counter: db 0\n\
+            done: db 0\n\
+        ";
+        let program = assemble(source).unwrap();
+        assert_eq!(
+            program,
+            [109, 1, 204, -1, 1001, 16, 1, 16, 1008, 16, 16, 17, 1006, 17, 2, 99, 0, 0]
+        );
+    }
+
+    #[test]
+    fn test_helpers() {
+        let source = "\
+            enter 100\n\
+            call double\n\
+            output [result]\n\
+            halt\n\
+            double: enter 2\n\
+                mul [value], #2, [result]\n\
+                leave 2\n\
+                ret\n\
+            value: db 21\n\
+            result: db 0\n\
+            text: db \"Hi\\n\", 0\n\
+        ";
+        let program = assemble(source).unwrap();
+        assert_eq!(&program[2..9], [21101, 9, 0, 0, 1105, 1, 12]);
+        assert_eq!(&program[program.len() - 4..], [72, 105, 10, 0]);
+
+        let mut computer = Computer::from(assemble_to_string(source).unwrap().as_str());
+        let mut io = InputOutput::new(&[]);
+        computer.eval(&mut io).unwrap();
+        assert_eq!(io.out_q, [42]);
+    }
+
+    #[test]
+    fn test_errors() {
+        let err = assemble("add #1, #2, [x]\nfoo #1\n").unwrap_err();
+        assert_eq!(err.line, 2);
+        assert_eq!(err.msg, "Illegal mnemonic: foo!");
+
+        let err = assemble("add #1, #2, #3\n").unwrap_err();
+        assert_eq!(
+            err.msg,
+            "Target parameter of add cannot be in immediate mode!"
+        );
+
+        let err = assemble("output 5\n").unwrap_err();
+        assert_eq!(err.line, 1);
+
+        let err = assemble("output [x]\nhalt\n").unwrap_err();
+        assert_eq!(err.msg, "Unknown label 'x'!");
+
+        let err = assemble("a: halt\na: halt\n").unwrap_err();
+        assert_eq!((err.line, err.msg.as_str()), (2, "Duplicate label 'a'!"));
+
+        let err = assemble("mul #1, [2]\n").unwrap_err();
+        assert_eq!(err.msg, "'mul' expects 3 operand(s), got 2!");
+
+        let err = assemble("halt\na: output #a+9223372036854775807\n").unwrap_err();
+        assert_eq!(err.msg, "Address of 'a+9223372036854775807' out of range!");
+    }
+
+    #[test]
+    fn test_input() {
+        for day in ["day_05", "day_09", "day_13", "day_15", "day_17", "day_25"] {
+            let program = std::fs::read_to_string(format!("../{day}/input/program.txt")).unwrap();
+            let listing = Listing::from(program.as_str());
+
+            let assembled = assemble(&listing.to_string()).unwrap();
+            assert_eq!(assembled, parse_program(&program), "{day}");
+        }
+    }
+}
@@ -0,0 +1,16 @@
+use intcode::assemble_to_string;
+
+fn main() {
+    let path = std::env::args()
+        .nth(1)
+        .unwrap_or_else(|| "input/program.asm".to_string());
+    let source = std::fs::read_to_string(path).unwrap();
+
+    match assemble_to_string(&source) {
+        Ok(program) => println!("{program}"),
+        Err(e) => {
+            eprintln!("ERR: {e}");
+            std::process::exit(1);
+        }
+    }
+}
@@ -0,0 +1,13 @@
+use intcode::{Computer, Debugger, InputOutput};
+
+fn main() {
+    let path = std::env::args()
+        .nth(1)
+        .unwrap_or_else(|| "input/program.txt".to_string());
+    let program = std::fs::read_to_string(path).unwrap();
+
+    let mut debugger = Debugger::new(Computer::from(program.as_str()), InputOutput::new(&[]));
+    debugger
+        .run(std::io::stdin().lock(), std::io::stdout())
+        .expect("Debugger I/O should not fail.");
+}
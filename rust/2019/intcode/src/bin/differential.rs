@@ -0,0 +1,21 @@
+use intcode::{puzzle_corpus, Differential, Generator};
+
+fn main() {
+    let mut args = std::env::args().skip(1);
+    let root = args.next().unwrap_or_else(|| "..".to_string());
+    let count = args.next().map(|s| s.parse().unwrap()).unwrap_or(1000);
+    let seed = args.next().map(|s| s.parse().unwrap()).unwrap_or(1);
+
+    let mut corpus = puzzle_corpus(&root).unwrap();
+    let mut generator = Generator::new(seed);
+    corpus.extend((0..count).map(|n| generator.case(n, 16)));
+
+    let differential = Differential::default();
+    let divergences = differential.check_all(&corpus);
+    for divergence in &divergences {
+        println!("{divergence}");
+        println!("Minimized:");
+        println!("{}", differential.minimize(&divergence.case));
+    }
+    println!("{} cases, {} divergences", corpus.len(), divergences.len());
+}
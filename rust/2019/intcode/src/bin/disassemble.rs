@@ -0,0 +1,10 @@
+use intcode::Listing;
+
+fn main() {
+    let path = std::env::args()
+        .nth(1)
+        .unwrap_or_else(|| "input/program.txt".to_string());
+    let program = std::fs::read_to_string(path).unwrap();
+
+    print!("{}", Listing::from(program.as_str()));
+}
@@ -0,0 +1,20 @@
+use intcode::{parse_program, Computer, InputOutput, Listing, Profile};
+
+fn main() {
+    let mut args = std::env::args().skip(1);
+    let path = args
+        .next()
+        .unwrap_or_else(|| "input/program.txt".to_string());
+    let inputs = args.next().map(|s| parse_program(&s)).unwrap_or_default();
+    let program = std::fs::read_to_string(path).unwrap();
+
+    let mut computer = Computer::from(program.as_str());
+    let mut io = InputOutput::new(&inputs);
+    let mut profile = Profile::new();
+    if let Err(e) = computer.eval_profiled(&mut io, &mut profile) {
+        println!("ERR: {e}");
+    }
+
+    print!("{}", profile.annotate(&Listing::from(program.as_str())));
+    print!("{profile}");
+}
@@ -0,0 +1,194 @@
+use std::ops::Deref;
+
+use crate::{Computer, Device, Fault, FaultReason, OpCode, State};
+
+#[derive(Debug, Clone)]
+pub struct CachedComputer {
+    computer: Computer,
+    decoded: Vec<Option<OpCode>>,
+    program_len: usize,
+}
+
+impl From<Computer> for CachedComputer {
+    fn from(computer: Computer) -> Self {
+        let program_len = computer
+            .memory
+            .non_zero()
+            .last()
+            .map(|(address, _)| address + 1)
+            .unwrap_or_default();
+        let mut cached = Self {
+            computer,
+            decoded: Vec::new(),
+            program_len,
+        };
+        cached.precompile();
+        cached
+    }
+}
+
+impl From<&str> for CachedComputer {
+    fn from(value: &str) -> Self {
+        Self::from(Computer::from(value))
+    }
+}
+
+impl Deref for CachedComputer {
+    type Target = Computer;
+
+    fn deref(&self) -> &Self::Target {
+        &self.computer
+    }
+}
+
+impl CachedComputer {
+    fn precompile(&mut self) {
+        self.decoded = (0..self.program_len)
+            .map(|address| OpCode::try_from(self.computer.memory[address]).ok())
+            .collect();
+    }
+
+    pub fn computer_mut(&mut self) -> &mut Computer {
+        // Writes through here bypass the cache, so it is rebuilt on the next eval.
+        self.decoded.clear();
+        &mut self.computer
+    }
+
+    pub fn into_inner(self) -> Computer {
+        self.computer
+    }
+
+    pub fn eval<D: Device + ?Sized>(&mut self, io: &mut D) -> Result<(), Fault> {
+        loop {
+            self.step(io)?;
+            if self.computer.state != State::Running {
+                return Ok(());
+            }
+        }
+    }
+
+    pub fn step<D: Device + ?Sized>(&mut self, io: &mut D) -> Result<(), Fault> {
+        if self.computer.state == State::Halted {
+            return Err(self.computer.fault(FaultReason::ResumedHalted));
+        }
+        if self.decoded.len() != self.program_len {
+            self.precompile();
+        }
+
+        let computer = &mut self.computer;
+        computer.state = State::Running;
+        let instruction_pointer = computer.instruction_pointer;
+        let opcode = match self.decoded.get(instruction_pointer) {
+            Some(Some(opcode)) => *opcode,
+            _ => OpCode::try_from(computer.memory[instruction_pointer])
+                .map_err(|e| computer.fault(FaultReason::IllegalOpCode(e.msg)))?,
+        };
+
+        let target = opcode.mnemonic.target_parameter().and_then(|parameter| {
+            opcode
+                .get_target_idx(computer, opcode.parameter_modes[parameter], parameter + 1)
+                .ok()
+        });
+
+        opcode.eval(computer, io).map_err(|reason| Fault {
+            instruction_pointer,
+            opcode: opcode.encode(),
+            reason,
+        })?;
+
+        if let Some(target) = target {
+            if let Some(decoded) = self.decoded.get_mut(target) {
+                *decoded = OpCode::try_from(computer.memory[target]).ok();
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{CachedComputer, Computer, InputOutput, State};
+
+    fn compare(program: &str, patch: &[(usize, isize)], inputs: &[isize]) {
+        let mut reference = Computer::from(program);
+        for (address, value) in patch {
+            reference.memory[*address] = *value;
+        }
+        let mut cached = CachedComputer::from(reference.clone());
+        let mut reference_io = InputOutput::new(&[]);
+        let mut cached_io = InputOutput::new(&[]);
+
+        for round in 0..1_000 {
+            let reference_result = reference.eval(&mut reference_io);
+            let cached_result = cached.eval(&mut cached_io);
+
+            assert_eq!(reference_result, cached_result);
+            assert_eq!(reference_io.out_q, cached_io.out_q);
+            assert_eq!(reference.state, cached.state);
+            assert_eq!(reference.instruction_pointer, cached.instruction_pointer);
+            assert_eq!(reference.relative_base, cached.relative_base);
+            assert_eq!(reference.memory, cached.memory);
+
+            if reference.state == State::Halted || reference_result.is_err() || inputs.is_empty() {
+                break;
+            }
+            let input = inputs[round % inputs.len()];
+            reference_io.write_to_in_q(input);
+            cached_io.write_to_in_q(input);
+        }
+    }
+
+    fn ascii(s: &str) -> Vec<isize> {
+        s.chars().map(|c| c as isize).collect()
+    }
+
+    #[test]
+    fn test_examples() {
+        compare("1101,100,-1,4,0", &[], &[]);
+        compare("3,9,8,9,10,9,4,9,99,-1,8", &[], &[8]);
+        compare("1101,1,1,3,98", &[], &[]);
+        compare("109,-5,204,0,99", &[], &[]);
+
+        compare("1101,0,99,4,1101,1,1,0", &[], &[]);
+
+        let mut cached = CachedComputer::from("1101,0,99,4,1101,1,1,0");
+        cached.eval(&mut InputOutput::new(&[])).unwrap();
+        assert_eq!(cached.state, State::Halted);
+        assert_eq!(cached.instruction_pointer, 4);
+        assert_eq!(cached.memory[0], 1101);
+
+        let mut cached = CachedComputer::from("1101,100,-1,4,0");
+        cached.computer_mut().memory[0] = 99;
+        cached.eval(&mut InputOutput::new(&[])).unwrap();
+        assert_eq!(cached.instruction_pointer, 0);
+        assert_eq!(cached.into_inner().memory[4], 0);
+    }
+
+    #[test]
+    fn test_input() {
+        let program =
+            |day: &str| std::fs::read_to_string(format!("../{day}/input/program.txt")).unwrap();
+
+        compare(&program("day_02"), &[(1, 12), (2, 2)], &[]);
+        compare(&program("day_05"), &[], &[1]);
+        compare(&program("day_05"), &[], &[5]);
+        compare(&program("day_07"), &[], &[4, 0]);
+        compare(&program("day_07"), &[], &[9, 0, 1, 2]);
+        compare(&program("day_09"), &[], &[1]);
+        compare(&program("day_09"), &[], &[2]);
+        compare(&program("day_11"), &[], &[0, 1, 1]);
+        compare(&program("day_13"), &[(0, 2)], &[0, -1, 1, 1]);
+        compare(&program("day_15"), &[], &[1, 4, 2, 3, 3]);
+        compare(&program("day_17"), &[], &[]);
+        compare(
+            &program("day_17"),
+            &[(0, 2)],
+            &ascii("A,B\nR,8\nL,4\nL,6\nn\n"),
+        );
+        compare(&program("day_19"), &[], &[10, 20, 5]);
+        compare(&program("day_21"), &[], &ascii("NOT A J\nWALK\n"));
+        compare(&program("day_23"), &[], &[7, -1]);
+        compare(&program("day_25"), &[], &ascii("north\ninv\n"));
+    }
+}
@@ -0,0 +1,195 @@
+use std::fmt::Display;
+
+use crate::{Device, Fault, FaultReason, Memory, OpCode, Profile};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum State {
+    Running,
+    WaitingForInput,
+    Halted,
+}
+
+#[derive(Debug, Clone)]
+pub struct Computer {
+    pub state: State,
+    pub memory: Memory,
+    pub instruction_pointer: usize,
+    pub relative_base: isize,
+}
+
+impl Display for Computer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "Computer:")?;
+        writeln!(f, "  State: {:?}", self.state)?;
+        writeln!(f, "  Instruction Pointer: {}", self.instruction_pointer)?;
+        writeln!(
+            f,
+            "  Memory around ip: {:?}",
+            self.memory.read(self.instruction_pointer, 4)
+        )?;
+        writeln!(f, "  Relative Base: {}", self.relative_base)?;
+
+        let window_start = usize::try_from(self.relative_base.saturating_sub(3).max(0)).unwrap();
+        writeln!(
+            f,
+            "  Memory around rel: {:?}",
+            self.memory.read(window_start, 7)
+        )?;
+
+        Ok(())
+    }
+}
+
+pub fn parse_program(program: &str) -> Vec<isize> {
+    program
+        .trim()
+        .split(',')
+        .map(|s| s.trim().parse().unwrap())
+        .collect()
+}
+
+impl From<&[isize]> for Computer {
+    fn from(value: &[isize]) -> Self {
+        Self {
+            state: State::Running,
+            memory: Memory::from(value),
+            instruction_pointer: 0,
+            relative_base: 0,
+        }
+    }
+}
+
+impl From<&str> for Computer {
+    fn from(value: &str) -> Self {
+        Self::from(parse_program(value).as_slice())
+    }
+}
+
+impl Computer {
+    pub fn eval<D: Device + ?Sized>(&mut self, io: &mut D) -> Result<(), Fault> {
+        if self.state == State::Halted {
+            return Err(self.fault(FaultReason::ResumedHalted));
+        }
+
+        self.state = State::Running;
+        while self.state == State::Running {
+            self.step(io)?;
+        }
+
+        Ok(())
+    }
+
+    pub fn eval_profiled<D: Device + ?Sized>(
+        &mut self,
+        io: &mut D,
+        profile: &mut Profile,
+    ) -> Result<(), Fault> {
+        if self.state == State::Halted {
+            return Err(self.fault(FaultReason::ResumedHalted));
+        }
+
+        self.state = State::Running;
+        while self.state == State::Running {
+            profile.step(self, io)?;
+        }
+
+        Ok(())
+    }
+
+    pub fn step<D: Device + ?Sized>(&mut self, io: &mut D) -> Result<(), Fault> {
+        if self.state == State::Halted {
+            return Err(self.fault(FaultReason::ResumedHalted));
+        }
+
+        let opcode = OpCode::try_from(self.memory[self.instruction_pointer])
+            .map_err(|e| self.fault(FaultReason::IllegalOpCode(e.msg)))?;
+
+        let instruction_pointer = self.instruction_pointer;
+        self.state = State::Running;
+        opcode.eval(self, io).map_err(|reason| Fault {
+            instruction_pointer,
+            opcode: opcode.encode(),
+            reason,
+        })
+    }
+
+    pub(crate) fn fault(&self, reason: FaultReason) -> Fault {
+        Fault {
+            instruction_pointer: self.instruction_pointer,
+            opcode: self.memory[self.instruction_pointer],
+            reason,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Computer, InputOutput, State};
+
+    #[test]
+    fn test_examples() {
+        let program = "1002,4,3,4,33";
+        let mut computer = Computer::from(program);
+        let mut io = InputOutput::new(&[]);
+        computer.eval(&mut io).unwrap();
+        assert_eq!(computer.state, State::Halted);
+        assert_eq!(computer.memory[4], 99);
+        assert_eq!(computer.instruction_pointer, 4);
+
+        let program = "109,1,204,-1,1001,100,1,100,1008,100,16,101,1006,101,0,99";
+        let mut computer = Computer::from(program);
+        let mut io = InputOutput::new(&[]);
+        computer.eval(&mut io).unwrap();
+        assert_eq!(
+            io.out_q,
+            [109, 1, 204, -1, 1001, 100, 1, 100, 1008, 100, 16, 101, 1006, 101, 0, 99]
+        );
+
+        let program = "1102,34915192,34915192,7,4,7,99,0";
+        let mut computer = Computer::from(program);
+        let mut io = InputOutput::new(&[]);
+        computer.eval(&mut io).unwrap();
+        assert_eq!(io.out_q, [1219070632396864]);
+    }
+
+    #[test]
+    fn test_waiting_for_input() {
+        let program = "3,9,8,9,10,9,4,9,99,-1,8";
+        let mut computer = Computer::from(program);
+        let mut io = InputOutput::new(&[]);
+        computer.eval(&mut io).unwrap();
+        assert_eq!(computer.state, State::WaitingForInput);
+        assert_eq!(computer.instruction_pointer, 0);
+
+        io.write_to_in_q(8);
+        computer.eval(&mut io).unwrap();
+        assert_eq!(computer.state, State::Halted);
+        assert_eq!(io.out_q, [1]);
+    }
+
+    #[test]
+    fn test_step() {
+        let mut computer = Computer::from("1101,100,-1,4,0");
+        let mut io = InputOutput::new(&[]);
+        computer.step(&mut io).unwrap();
+        assert_eq!(computer.state, State::Running);
+        assert_eq!(computer.instruction_pointer, 4);
+        assert_eq!(computer.memory[4], 99);
+
+        computer.step(&mut io).unwrap();
+        assert_eq!(computer.state, State::Halted);
+        assert_eq!(computer.instruction_pointer, 4);
+    }
+
+    #[test]
+    fn test_illegal_opcode() {
+        let mut computer = Computer::from("1101,1,1,3,98");
+        let mut io = InputOutput::new(&[]);
+        let err = computer.eval(&mut io).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Fault at 0004 (opcode 98): Illegal mnemonic: 98!"
+        );
+        assert_eq!(computer.instruction_pointer, 4);
+    }
+}
@@ -0,0 +1,476 @@
+use std::{
+    collections::BTreeSet,
+    fmt::Display,
+    io::{BufRead, Write},
+};
+
+use crate::{Computer, Fault, InputOutput, Instruction, Memory, State};
+
+const DEBUGGER_PROMPT: &str = "(icdb) ";
+const DEBUGGER_MAX_CELLS: usize = 1024;
+const DEBUGGER_HELP: &str = "\
+Commands:
+  s, step [n]          execute n instructions (default 1)
+  c, continue          run until breakpoint, watchpoint, input wait or halt
+  b, break <addr>      set breakpoint
+  d, delete <addr>     remove breakpoint
+  w, watch <addr>      set watchpoint on memory cell
+  u, unwatch <addr>    remove watchpoint
+  i, input <v>,...     append values to the input queue
+  a, ascii <text>      append text plus newline to the input queue
+  o, output            drain and show the output queue
+  io                   show input and output queues
+  x <addr> [n]         examine n memory cells (default 1, at most 1024)
+  set <addr> <value>   write memory cell
+  l, list [n]          disassemble n instructions at ip (default 5)
+  r, regs              show computer state
+  h, help              show this help
+  q, quit              leave the debugger
+";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StopReason {
+    Step,
+    Breakpoint(usize),
+    Watchpoint {
+        address: usize,
+        old: isize,
+        new: isize,
+    },
+    WaitingForInput,
+    Halted,
+    Fault(Fault),
+}
+
+impl Display for StopReason {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Step => write!(f, "Stepped."),
+            Self::Breakpoint(address) => write!(f, "Breakpoint at {address:04}."),
+            Self::Watchpoint { address, old, new } => {
+                write!(f, "Watchpoint at {address:04}: {old} -> {new}.")
+            }
+            Self::WaitingForInput => write!(f, "Waiting for input."),
+            Self::Halted => write!(f, "Halted."),
+            Self::Fault(fault) => write!(f, "{fault}"),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Debugger {
+    pub computer: Computer,
+    pub io: InputOutput,
+    pub breakpoints: BTreeSet<usize>,
+    pub watchpoints: BTreeSet<usize>,
+}
+
+impl Debugger {
+    pub fn new(computer: Computer, io: InputOutput) -> Self {
+        Self {
+            computer,
+            io,
+            breakpoints: BTreeSet::new(),
+            watchpoints: BTreeSet::new(),
+        }
+    }
+
+    pub fn current_instruction(&self) -> Option<Instruction> {
+        Instruction::fetch(&self.computer.memory, self.computer.instruction_pointer)
+    }
+
+    pub fn step(&mut self) -> StopReason {
+        if self.computer.state == State::Halted {
+            return StopReason::Halted;
+        }
+
+        let watched: Vec<(usize, isize)> = self
+            .watchpoints
+            .iter()
+            .map(|a| (*a, self.computer.memory[*a]))
+            .collect();
+
+        if let Err(e) = self.computer.step(&mut self.io) {
+            return StopReason::Fault(e);
+        }
+
+        for (address, old) in watched {
+            let new = self.computer.memory[address];
+            if new != old {
+                return StopReason::Watchpoint { address, old, new };
+            }
+        }
+
+        match self.computer.state {
+            State::Halted => StopReason::Halted,
+            State::WaitingForInput => StopReason::WaitingForInput,
+            State::Running => StopReason::Step,
+        }
+    }
+
+    pub fn cont(&mut self) -> StopReason {
+        loop {
+            let reason = self.step();
+            if reason != StopReason::Step {
+                return reason;
+            }
+            if self
+                .breakpoints
+                .contains(&self.computer.instruction_pointer)
+            {
+                return StopReason::Breakpoint(self.computer.instruction_pointer);
+            }
+        }
+    }
+
+    fn format_location(&self) -> String {
+        let ip = self.computer.instruction_pointer;
+        match self.current_instruction() {
+            Some(instruction) => format!("{ip:04}: {instruction}"),
+            None => format!("{ip:04}: <illegal {}>", self.computer.memory[ip]),
+        }
+    }
+
+    fn list(&self, n: usize) -> Vec<String> {
+        let mut lines = Vec::with_capacity(n);
+        let mut address = self.computer.instruction_pointer;
+
+        for _ in 0..n {
+            let marker = if self.breakpoints.contains(&address) {
+                '*'
+            } else {
+                ' '
+            };
+            match Instruction::fetch(&self.computer.memory, address) {
+                Some(instruction) => {
+                    lines.push(format!("{marker} {address:04}: {instruction}"));
+                    address = instruction.next_address();
+                }
+                None => {
+                    lines.push(format!(
+                        "{marker} {address:04}: db {}",
+                        self.computer.memory[address]
+                    ));
+                    address += 1;
+                }
+            }
+        }
+
+        lines
+    }
+
+    fn parse_address(&self, arg: Option<&str>) -> Result<usize, String> {
+        let arg = arg.ok_or("Missing address!")?;
+        arg.parse::<usize>()
+            .map_err(|_| format!("Illegal address '{arg}'!"))
+    }
+
+    fn execute(&mut self, line: &str) -> Result<Vec<String>, String> {
+        let line = line.trim();
+        let (cmd, args) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+        let mut args_iter = args.split_whitespace();
+
+        let lines = match cmd {
+            "" => vec![],
+            "s" | "step" => {
+                let n = match args_iter.next() {
+                    Some(n) => n.parse().map_err(|_| format!("Illegal count '{n}'!"))?,
+                    None => 1,
+                };
+                let mut reason = StopReason::Step;
+                for _ in 0..n {
+                    reason = self.step();
+                    if reason != StopReason::Step {
+                        break;
+                    }
+                }
+                vec![reason.to_string(), self.format_location()]
+            }
+            "c" | "continue" => {
+                let reason = self.cont();
+                vec![reason.to_string(), self.format_location()]
+            }
+            "b" | "break" => {
+                let address = self.parse_address(args_iter.next())?;
+                self.breakpoints.insert(address);
+                vec![format!("Breakpoint set at {address:04}.")]
+            }
+            "d" | "delete" => {
+                let address = self.parse_address(args_iter.next())?;
+                if !self.breakpoints.remove(&address) {
+                    return Err(format!("No breakpoint at {address:04}!"));
+                }
+                vec![format!("Breakpoint at {address:04} removed.")]
+            }
+            "w" | "watch" => {
+                let address = self.parse_address(args_iter.next())?;
+                self.watchpoints.insert(address);
+                vec![format!("Watchpoint set at {address:04}.")]
+            }
+            "u" | "unwatch" => {
+                let address = self.parse_address(args_iter.next())?;
+                if !self.watchpoints.remove(&address) {
+                    return Err(format!("No watchpoint at {address:04}!"));
+                }
+                vec![format!("Watchpoint at {address:04} removed.")]
+            }
+            "i" | "input" => {
+                let values = args
+                    .split(',')
+                    .map(|v| v.trim().parse::<isize>())
+                    .collect::<Result<Vec<_>, _>>()
+                    .map_err(|_| format!("Illegal input '{args}'!"))?;
+                for value in &values {
+                    self.io.write_to_in_q(*value);
+                }
+                vec![format!("Queued {} value(s).", values.len())]
+            }
+            "a" | "ascii" => {
+                let queued = self.io.in_q.len();
+                self.io.write_to_in_q_ascii(args);
+                self.io.write_to_in_q_ascii("\n");
+                vec![format!("Queued {} value(s).", self.io.in_q.len() - queued)]
+            }
+            "o" | "output" => {
+                let values: Vec<isize> = self.io.out_q.drain(..).collect();
+                let is_text = values
+                    .iter()
+                    .all(|v| *v == '\n' as isize || (' ' as isize..='~' as isize).contains(v));
+                if values.is_empty() {
+                    vec![]
+                } else if is_text {
+                    let text: String = values.iter().map(|v| *v as u8 as char).collect();
+                    text.lines().map(|l| l.to_string()).collect()
+                } else {
+                    vec![values
+                        .iter()
+                        .map(|v| v.to_string())
+                        .collect::<Vec<_>>()
+                        .join(",")]
+                }
+            }
+            "io" => self.io.to_string().lines().map(|l| l.to_string()).collect(),
+            "x" => {
+                let address = self.parse_address(args_iter.next())?;
+                let n = match args_iter.next() {
+                    Some(n) => n.parse().map_err(|_| format!("Illegal count '{n}'!"))?,
+                    None => 1,
+                };
+                if n > DEBUGGER_MAX_CELLS {
+                    return Err(format!("Count {n} exceeds {DEBUGGER_MAX_CELLS} cells!"));
+                }
+                Memory::offset(address, n.saturating_sub(1))
+                    .map_err(|_| format!("Illegal range {address}+{n}!"))?;
+                (address..address + n)
+                    .map(|a| format!("{a:04}: {}", self.computer.memory[a]))
+                    .collect()
+            }
+            "set" => {
+                let address = self.parse_address(args_iter.next())?;
+                Memory::offset(address, 0).map_err(|reason| reason.to_string())?;
+                let value = args_iter.next().ok_or("Missing value!")?;
+                let value = value
+                    .parse()
+                    .map_err(|_| format!("Illegal value '{value}'!"))?;
+                self.computer.memory[address] = value;
+                vec![format!("{address:04}: {value}")]
+            }
+            "l" | "list" => {
+                let n = match args_iter.next() {
+                    Some(n) => n.parse().map_err(|_| format!("Illegal count '{n}'!"))?,
+                    None => 5,
+                };
+                self.list(n)
+            }
+            "r" | "regs" => self
+                .computer
+                .to_string()
+                .lines()
+                .map(|l| l.to_string())
+                .collect(),
+            "h" | "help" => DEBUGGER_HELP.lines().map(|l| l.to_string()).collect(),
+            _ => return Err(format!("Unknown command '{cmd}'! Try 'help'.")),
+        };
+
+        Ok(lines)
+    }
+
+    pub fn run<R: BufRead, W: Write>(&mut self, input: R, mut output: W) -> std::io::Result<()> {
+        writeln!(output, "{}", self.format_location())?;
+        write!(output, "{DEBUGGER_PROMPT}")?;
+        output.flush()?;
+
+        for line in input.lines() {
+            let line = line?;
+            if matches!(line.trim(), "q" | "quit") {
+                break;
+            }
+
+            match self.execute(&line) {
+                Ok(lines) => {
+                    for l in lines {
+                        writeln!(output, "{l}")?;
+                    }
+                }
+                Err(msg) => writeln!(output, "ERR: {msg}")?,
+            }
+            write!(output, "{DEBUGGER_PROMPT}")?;
+            output.flush()?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{assemble_to_string, Computer, Debugger, InputOutput, Mnemonic, StopReason};
+
+    fn countdown() -> Computer {
+        let source = "\
+            input [counter]\n\
+            loop:\n\
+                output [counter]\n\
+                add [counter], #-1, [counter]\n\
+                jump_if_true [counter], #loop\n\
+                halt\n\
+            counter: db 0\n\
+        ";
+        Computer::from(assemble_to_string(source).unwrap().as_str())
+    }
+
+    #[test]
+    fn test_step_and_breakpoints() {
+        let mut debugger = Debugger::new(countdown(), InputOutput::new(&[]));
+        assert_eq!(
+            debugger.current_instruction().unwrap().opcode.mnemonic,
+            Mnemonic::Input
+        );
+        assert_eq!(debugger.step(), StopReason::WaitingForInput);
+        assert_eq!(debugger.computer.instruction_pointer, 0);
+
+        debugger.io.write_to_in_q(3);
+        assert_eq!(debugger.step(), StopReason::Step);
+        assert_eq!(debugger.computer.instruction_pointer, 2);
+
+        debugger.breakpoints.insert(8);
+        assert_eq!(debugger.cont(), StopReason::Breakpoint(8));
+        assert_eq!(debugger.io.out_q, [3]);
+        assert_eq!(debugger.cont(), StopReason::Breakpoint(8));
+        assert_eq!(debugger.io.out_q, [3, 2]);
+
+        debugger.breakpoints.clear();
+        debugger.watchpoints.insert(12);
+        assert_eq!(
+            debugger.cont(),
+            StopReason::Watchpoint {
+                address: 12,
+                old: 1,
+                new: 0
+            }
+        );
+        debugger.watchpoints.clear();
+        assert_eq!(debugger.cont(), StopReason::Halted);
+        assert_eq!(debugger.io.out_q, [3, 2, 1]);
+        assert_eq!(debugger.step(), StopReason::Halted);
+    }
+
+    #[test]
+    fn test_fault() {
+        let computer = Computer::from("1101,1,1,3,98");
+        let mut debugger = Debugger::new(computer, InputOutput::new(&[]));
+        assert_eq!(
+            debugger.cont().to_string(),
+            "Fault at 0004 (opcode 98): Illegal mnemonic: 98!"
+        );
+    }
+
+    #[test]
+    fn test_ascii() {
+        let mut debugger = Debugger::new(countdown(), InputOutput::new(&[7]));
+        assert_eq!(
+            debugger.execute("ascii né"),
+            Ok(vec!["Queued 3 value(s).".to_string()])
+        );
+        assert_eq!(debugger.io.in_q, [7, 'n' as isize, 'é' as isize, 10]);
+    }
+
+    #[test]
+    fn test_memory_commands() {
+        let mut debugger = Debugger::new(countdown(), InputOutput::new(&[]));
+        assert_eq!(
+            debugger.execute("x 9223372036854775806 2"),
+            Ok(vec![
+                "9223372036854775806: 0".to_string(),
+                "9223372036854775807: 0".to_string()
+            ])
+        );
+        assert_eq!(
+            debugger.execute("x 18446744073709551615 2"),
+            Err("Illegal range 18446744073709551615+2!".to_string())
+        );
+        assert_eq!(
+            debugger.execute("x 0 1000000000000"),
+            Err("Count 1000000000000 exceeds 1024 cells!".to_string())
+        );
+        assert_eq!(
+            debugger.execute("set 9223372036854775808 1"),
+            Err("Address limit exceeded after 9223372036854775808!".to_string())
+        );
+        assert_eq!(debugger.computer.memory.allocated_pages(), 1);
+    }
+
+    #[test]
+    fn test_command_loop() {
+        let mut debugger = Debugger::new(countdown(), InputOutput::new(&[]));
+        let script = "\
+            list 3\n\
+            break 8\n\
+            continue\n\
+            input 2\n\
+            continue\n\
+            x 12\n\
+            watch 12\n\
+            delete 8\n\
+            c\n\
+            set 12 0\n\
+            unwatch 12\n\
+            c\n\
+            output\n\
+            frobnicate\n\
+            quit\n\
+            step\n\
+        ";
+        let mut output = Vec::new();
+        debugger.run(script.as_bytes(), &mut output).unwrap();
+        let output = String::from_utf8(output).unwrap();
+
+        assert_eq!(
+            output.lines().collect::<Vec<_>>(),
+            [
+                "0000: input [12]",
+                "(icdb)   0000: input [12]",
+                "  0002: output [12]",
+                "  0004: add [12], #-1, [12]",
+                "(icdb) Breakpoint set at 0008.",
+                "(icdb) Waiting for input.",
+                "0000: input [12]",
+                "(icdb) Queued 1 value(s).",
+                "(icdb) Breakpoint at 0008.",
+                "0008: jump_if_true [12], #2",
+                "(icdb) 0012: 1",
+                "(icdb) Watchpoint set at 0012.",
+                "(icdb) Breakpoint at 0008 removed.",
+                "(icdb) Watchpoint at 0012: 1 -> 0.",
+                "0008: jump_if_true [12], #2",
+                "(icdb) 0012: 0",
+                "(icdb) Watchpoint at 0012 removed.",
+                "(icdb) Halted.",
+                "0011: halt",
+                "(icdb) 2,1",
+                "(icdb) ERR: Unknown command 'frobnicate'! Try 'help'.",
+                "(icdb) ",
+            ]
+        );
+    }
+}
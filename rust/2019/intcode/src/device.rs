@@ -0,0 +1,332 @@
+use std::{
+    collections::VecDeque,
+    fmt::Display,
+    io::{BufRead, Write},
+    str::FromStr,
+    sync::mpsc::{Receiver, RecvTimeoutError, Sender},
+    time::Duration,
+};
+
+use crate::{InputOutput, TryFromError};
+
+pub trait Device {
+    fn read_in(&mut self) -> Option<isize>;
+    fn write_out(&mut self, data: isize);
+}
+
+impl Device for InputOutput {
+    fn read_in(&mut self) -> Option<isize> {
+        InputOutput::read_in(self)
+    }
+
+    fn write_out(&mut self, data: isize) {
+        InputOutput::write_out(self, data)
+    }
+}
+
+#[derive(Debug)]
+pub struct ChannelDevice {
+    pub rx: Receiver<isize>,
+    pub tx: Sender<isize>,
+    pub timeout: Option<Duration>,
+    pub idle_value: Option<isize>,
+}
+
+impl ChannelDevice {
+    pub fn new(rx: Receiver<isize>, tx: Sender<isize>) -> Self {
+        Self {
+            rx,
+            tx,
+            timeout: None,
+            idle_value: None,
+        }
+    }
+
+    pub fn with_idle_value(mut self, timeout: Duration, idle_value: isize) -> Self {
+        self.timeout = Some(timeout);
+        self.idle_value = Some(idle_value);
+        self
+    }
+}
+
+impl Device for ChannelDevice {
+    fn read_in(&mut self) -> Option<isize> {
+        match self.timeout {
+            None => self.rx.recv().ok(),
+            Some(timeout) => match self.rx.recv_timeout(timeout) {
+                Ok(data) => Some(data),
+                Err(RecvTimeoutError::Timeout) => self.idle_value,
+                Err(RecvTimeoutError::Disconnected) => None,
+            },
+        }
+    }
+
+    fn write_out(&mut self, data: isize) {
+        self.tx.send(data).unwrap_or(()); // don't worry about receivers having hung up
+    }
+}
+
+#[derive(Debug)]
+pub struct AsciiTerminal<R: BufRead, W: Write> {
+    input: R,
+    output: W,
+    line: VecDeque<isize>,
+    pub escape: Option<char>,
+    pub command: Option<String>,
+}
+
+impl<R: BufRead, W: Write> AsciiTerminal<R, W> {
+    pub fn new(input: R, output: W) -> Self {
+        Self {
+            input,
+            output,
+            line: VecDeque::new(),
+            escape: None,
+            command: None,
+        }
+    }
+
+    pub fn with_escape(mut self, escape: char) -> Self {
+        self.escape = Some(escape);
+        self
+    }
+
+    pub fn output(&self) -> &W {
+        &self.output
+    }
+}
+
+impl<R: BufRead, W: Write> Device for AsciiTerminal<R, W> {
+    fn read_in(&mut self) -> Option<isize> {
+        if self.line.is_empty() {
+            self.output
+                .flush()
+                .expect("Failed to flush terminal output!");
+
+            let mut buf = String::new();
+            if self.input.read_line(&mut buf).ok()? == 0 {
+                return None;
+            }
+            if !buf.ends_with('\n') {
+                buf.push('\n');
+            }
+
+            // Escaped lines are handed back to the caller instead of the program.
+            if let Some(command) = self.escape.and_then(|e| buf.strip_prefix(e)) {
+                self.command = Some(command.trim().to_string());
+                return None;
+            }
+
+            self.line.extend(buf.chars().map(|c| c as isize));
+        }
+
+        self.line.pop_front()
+    }
+
+    fn write_out(&mut self, data: isize) {
+        match u8::try_from(data) {
+            Ok(c) if c.is_ascii() => write!(self.output, "{}", c as char),
+            _ => writeln!(self.output, "{data}"),
+        }
+        .expect("Failed to write terminal output!");
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Event {
+    In(isize),
+    Out(isize),
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Transcript<D: Device> {
+    pub device: D,
+    pub events: Vec<Event>,
+}
+
+impl<D: Device> Transcript<D> {
+    pub fn new(device: D) -> Self {
+        Self {
+            device,
+            events: Vec::new(),
+        }
+    }
+
+    pub fn inputs(&self) -> Vec<isize> {
+        inputs(&self.events)
+    }
+
+    pub fn outputs(&self) -> Vec<isize> {
+        outputs(&self.events)
+    }
+
+    pub fn replay(&self) -> InputOutput {
+        InputOutput::new(&self.inputs())
+    }
+}
+
+fn inputs(events: &[Event]) -> Vec<isize> {
+    events
+        .iter()
+        .filter_map(|e| match e {
+            Event::In(data) => Some(*data),
+            Event::Out(_) => None,
+        })
+        .collect()
+}
+
+fn outputs(events: &[Event]) -> Vec<isize> {
+    events
+        .iter()
+        .filter_map(|e| match e {
+            Event::Out(data) => Some(*data),
+            Event::In(_) => None,
+        })
+        .collect()
+}
+
+impl<D: Device> Device for Transcript<D> {
+    fn read_in(&mut self) -> Option<isize> {
+        let data = self.device.read_in()?;
+        self.events.push(Event::In(data));
+        Some(data)
+    }
+
+    fn write_out(&mut self, data: isize) {
+        self.events.push(Event::Out(data));
+        self.device.write_out(data);
+    }
+}
+
+impl<D: Device> Display for Transcript<D> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for event in &self.events {
+            match event {
+                Event::In(data) => writeln!(f, "< {data}")?,
+                Event::Out(data) => writeln!(f, "> {data}")?,
+            }
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for Transcript<InputOutput> {
+    type Err = TryFromError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let events = s
+            .lines()
+            .filter(|l| !l.trim().is_empty())
+            .map(|l| {
+                let err = || TryFromError {
+                    msg: format!("Illegal transcript line '{l}'!"),
+                };
+                let (direction, data) = l.trim().split_once(' ').ok_or_else(err)?;
+                let data = data.parse().map_err(|_| err())?;
+                match direction {
+                    "<" => Ok(Event::In(data)),
+                    ">" => Ok(Event::Out(data)),
+                    _ => Err(err()),
+                }
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(Self {
+            device: InputOutput::new(&inputs(&events)),
+            events,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{sync::mpsc, thread, time::Duration};
+
+    use crate::{AsciiTerminal, ChannelDevice, Computer, InputOutput, State, Transcript};
+
+    #[test]
+    fn test_examples() {
+        let program = "3,9,8,9,10,9,4,9,99,-1,8";
+
+        let mut transcript = Transcript::new(InputOutput::new(&[8]));
+        let mut computer = Computer::from(program);
+        computer.eval(&mut transcript).unwrap();
+        assert_eq!(transcript.to_string(), "< 8\n> 1\n");
+        assert_eq!(transcript.device.out_q, [1]);
+
+        let recorded: Transcript<InputOutput> = "< 8\n> 1\n".parse().unwrap();
+        let mut replay = recorded.replay();
+        let mut computer = Computer::from(program);
+        computer.eval(&mut replay).unwrap();
+        assert_eq!(Vec::from(replay.out_q), [1]);
+        assert_eq!(recorded.outputs(), [1]);
+
+        assert_eq!(
+            "? 1".parse::<Transcript<InputOutput>>().unwrap_err().msg,
+            "Illegal transcript line '? 1'!"
+        );
+    }
+
+    #[test]
+    fn test_channels() {
+        let (in_tx, in_rx) = mpsc::channel();
+        let (out_tx, out_rx) = mpsc::channel();
+
+        let handle = thread::spawn(move || {
+            let mut device = ChannelDevice::new(in_rx, out_tx);
+            let mut computer = Computer::from("3,100,4,100,1105,1,0");
+            computer.eval(&mut device).unwrap();
+            computer.state
+        });
+
+        for i in 1..=3 {
+            in_tx.send(i).unwrap();
+            assert_eq!(out_rx.recv().unwrap(), i);
+        }
+        drop(in_tx);
+        assert_eq!(handle.join().unwrap(), State::WaitingForInput);
+
+        let (_in_tx, in_rx) = mpsc::channel();
+        let (out_tx, out_rx) = mpsc::channel();
+        let mut device =
+            ChannelDevice::new(in_rx, out_tx).with_idle_value(Duration::from_millis(1), -1);
+        let mut computer = Computer::from("3,100,4,100,99");
+        computer.eval(&mut device).unwrap();
+        assert_eq!(out_rx.recv().unwrap(), -1);
+    }
+
+    #[test]
+    fn test_terminal() {
+        let program = "3,100,4,100,1008,100,10,101,1006,101,0,104,1000,99";
+        let input = "hi\n!save x\n";
+        let mut terminal = AsciiTerminal::new(input.as_bytes(), Vec::new()).with_escape('!');
+        let mut computer = Computer::from(program);
+
+        computer.eval(&mut terminal).unwrap();
+        assert_eq!(computer.state, State::Halted);
+        assert_eq!(String::from_utf8_lossy(terminal.output()), "hi\n1000\n");
+
+        let mut computer = Computer::from(program);
+        computer.eval(&mut terminal).unwrap();
+        assert_eq!(computer.state, State::WaitingForInput);
+        assert_eq!(terminal.command.take(), Some("save x".to_string()));
+
+        computer.eval(&mut terminal).unwrap();
+        assert_eq!(computer.state, State::WaitingForInput);
+        assert_eq!(terminal.command, None);
+    }
+
+    #[test]
+    fn test_input() {
+        let program = std::fs::read_to_string("../day_09/input/program.txt").unwrap();
+        let mut transcript = Transcript::new(InputOutput::new(&[1]));
+        let mut computer = Computer::from(program.as_str());
+        computer.eval(&mut transcript).unwrap();
+
+        let recorded: Transcript<InputOutput> = transcript.to_string().parse().unwrap();
+        let mut replay = recorded.replay();
+        let mut computer = Computer::from(program.as_str());
+        computer.eval(&mut replay).unwrap();
+        assert_eq!(Vec::from(replay.out_q), recorded.outputs());
+    }
+}
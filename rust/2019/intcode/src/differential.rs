@@ -0,0 +1,558 @@
+use std::{fmt::Display, path::Path};
+
+use crate::{
+    parse_program, CachedComputer, Computer, Debugger, Fault, InputOutput, Memory, Mnemonic,
+    OpCode, ParameterMode, Profile, State, StopReason,
+};
+
+pub const DIFFERENTIAL_STEP_BUDGET: usize = 10_000_000;
+const PUZZLE_INPUTS: [&[isize]; 4] = [&[], &[1], &[2], &[5]];
+const RANDOM_DATA_SIZE: usize = 8;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Outcome {
+    pub result: Result<(), Fault>,
+    pub state: State,
+    pub outputs: Vec<isize>,
+    pub memory: Memory,
+    pub instruction_pointer: usize,
+    pub relative_base: isize,
+}
+
+impl Outcome {
+    pub fn new(computer: Computer, io: InputOutput, result: Result<(), Fault>) -> Self {
+        Self {
+            result,
+            state: computer.state,
+            outputs: io.out_q.into(),
+            memory: computer.memory,
+            instruction_pointer: computer.instruction_pointer,
+            relative_base: computer.relative_base,
+        }
+    }
+}
+
+impl Display for Outcome {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{:?} at {:04}, relative base {}, outputs {:?}",
+            self.state, self.instruction_pointer, self.relative_base, self.outputs
+        )?;
+        if let Err(fault) = &self.result {
+            write!(f, ", {fault}")?;
+        }
+        Ok(())
+    }
+}
+
+pub trait Interpreter {
+    fn name(&self) -> &str;
+    fn run(&self, computer: Computer, inputs: &[isize]) -> Outcome;
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Reference;
+
+impl Interpreter for Reference {
+    fn name(&self) -> &str {
+        "reference"
+    }
+
+    fn run(&self, mut computer: Computer, inputs: &[isize]) -> Outcome {
+        let mut io = InputOutput::new(inputs);
+        let result = computer.eval(&mut io);
+        Outcome::new(computer, io, result)
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Cached;
+
+impl Interpreter for Cached {
+    fn name(&self) -> &str {
+        "cached"
+    }
+
+    fn run(&self, computer: Computer, inputs: &[isize]) -> Outcome {
+        let mut cached = CachedComputer::from(computer);
+        let mut io = InputOutput::new(inputs);
+        let result = cached.eval(&mut io);
+        Outcome::new(cached.into_inner(), io, result)
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Profiled;
+
+impl Interpreter for Profiled {
+    fn name(&self) -> &str {
+        "profiled"
+    }
+
+    fn run(&self, mut computer: Computer, inputs: &[isize]) -> Outcome {
+        let mut io = InputOutput::new(inputs);
+        let result = computer.eval_profiled(&mut io, &mut Profile::new());
+        Outcome::new(computer, io, result)
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Stepped;
+
+impl Interpreter for Stepped {
+    fn name(&self) -> &str {
+        "debugger"
+    }
+
+    fn run(&self, computer: Computer, inputs: &[isize]) -> Outcome {
+        let mut debugger = Debugger::new(computer, InputOutput::new(inputs));
+        let result = match debugger.cont() {
+            StopReason::Fault(fault) => Err(fault),
+            _ => Ok(()),
+        };
+        Outcome::new(debugger.computer, debugger.io, result)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Case {
+    pub name: String,
+    pub program: Vec<isize>,
+    pub inputs: Vec<isize>,
+}
+
+impl Display for Case {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "{} with inputs {:?}", self.name, self.inputs)?;
+        let program: Vec<String> = self.program.iter().map(|v| v.to_string()).collect();
+        write!(f, "{}", program.join(","))
+    }
+}
+
+pub fn puzzle_corpus<P: AsRef<Path>>(root: P) -> std::io::Result<Vec<Case>> {
+    let mut days: Vec<_> = std::fs::read_dir(root)?
+        .map(|entry| entry.map(|e| e.path()))
+        .collect::<Result<_, _>>()?;
+    days.sort();
+
+    let mut corpus = Vec::new();
+    for day in days {
+        let path = day.join("input").join("program.txt");
+        if !path.is_file() {
+            continue;
+        }
+        let program = parse_program(&std::fs::read_to_string(&path)?);
+        let name = day.file_name().unwrap_or_default().to_string_lossy();
+        for inputs in PUZZLE_INPUTS {
+            corpus.push(Case {
+                name: name.to_string(),
+                program: program.clone(),
+                inputs: inputs.to_vec(),
+            });
+        }
+    }
+
+    Ok(corpus)
+}
+
+// Xorshift, so a seed reproduces the same corpus everywhere.
+#[derive(Debug, Clone)]
+pub struct Generator {
+    state: u64,
+}
+
+impl Generator {
+    pub fn new(seed: u64) -> Self {
+        Self { state: seed.max(1) }
+    }
+
+    fn next(&mut self) -> u64 {
+        self.state ^= self.state << 13;
+        self.state ^= self.state >> 7;
+        self.state ^= self.state << 17;
+        self.state
+    }
+
+    fn below(&mut self, n: usize) -> usize {
+        (self.next() % n as u64) as usize
+    }
+
+    fn value(&mut self) -> isize {
+        match self.below(8) {
+            0 => self.next() as i32 as isize,
+            _ => self.below(21) as isize - 10,
+        }
+    }
+
+    // Instructions jump forward only and mostly write behind the code, but some writes land in
+    // the code itself to exercise self-modification.
+    pub fn program(&mut self, instructions: usize) -> Vec<isize> {
+        let mnemonics: Vec<Mnemonic> = (0..instructions)
+            .map(|_| Mnemonic::ALL[self.below(Mnemonic::ALL.len() - 1)])
+            .chain([Mnemonic::Halt])
+            .collect();
+        let addresses: Vec<usize> = mnemonics
+            .iter()
+            .scan(0, |address, mnemonic| {
+                let start = *address;
+                *address += mnemonic.instruction_pointer_offset().max(1);
+                Some(start)
+            })
+            .collect();
+        let data = addresses.last().unwrap() + 1;
+
+        let mut program = Vec::new();
+        for (idx, mnemonic) in mnemonics.iter().enumerate() {
+            let mut parameter_modes = [ParameterMode::Position; 3];
+            let mut parameters = Vec::new();
+            for (parameter, parameter_mode) in parameter_modes
+                .iter_mut()
+                .enumerate()
+                .take(mnemonic.parameter_count())
+            {
+                let is_target = mnemonic.target_parameter() == Some(parameter);
+                let is_jump = parameter == 1
+                    && matches!(mnemonic, Mnemonic::JumpIfTrue | Mnemonic::JumpIfFalse);
+
+                let (mode, value) = if is_jump {
+                    let target = addresses[idx + 1 + self.below(addresses.len() - idx - 1)];
+                    (ParameterMode::Immediate, target as isize)
+                } else {
+                    match self.below(if is_target { 2 } else { 3 }) {
+                        0 if is_target && self.below(8) == 0 => {
+                            (ParameterMode::Position, self.below(data) as isize)
+                        }
+                        0 => (
+                            ParameterMode::Position,
+                            (data + self.below(RANDOM_DATA_SIZE)) as isize,
+                        ),
+                        1 => (
+                            ParameterMode::Relative,
+                            self.below(RANDOM_DATA_SIZE) as isize,
+                        ),
+                        _ => (ParameterMode::Immediate, self.value()),
+                    }
+                };
+                *parameter_mode = mode;
+                parameters.push(value);
+            }
+
+            program.push(
+                OpCode {
+                    mnemonic: *mnemonic,
+                    parameter_modes,
+                }
+                .encode(),
+            );
+            program.extend(parameters);
+        }
+        program.extend((0..RANDOM_DATA_SIZE).map(|_| self.value()));
+
+        program
+    }
+
+    pub fn case(&mut self, n: usize, instructions: usize) -> Case {
+        let program = self.program(instructions);
+        let inputs = (0..self.below(4)).map(|_| self.value()).collect();
+        Case {
+            name: format!("random #{n}"),
+            program,
+            inputs,
+        }
+    }
+}
+
+fn cells(case: &mut Case, inputs: bool) -> &mut Vec<isize> {
+    if inputs {
+        &mut case.inputs
+    } else {
+        &mut case.program
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Divergence {
+    pub case: Case,
+    pub outcomes: Vec<(String, Outcome)>,
+}
+
+impl Divergence {
+    pub fn diverging(&self) -> Vec<&str> {
+        let (_, expected) = &self.outcomes[0];
+        self.outcomes
+            .iter()
+            .filter(|(_, outcome)| outcome != expected)
+            .map(|(name, _)| name.as_str())
+            .collect()
+    }
+}
+
+impl Display for Divergence {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "{}", self.case)?;
+        for (name, outcome) in &self.outcomes {
+            writeln!(f, "  {name}: {outcome}")?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Verdict {
+    Agree,
+    Inconclusive,
+    Diverge(Divergence),
+}
+
+pub struct Differential {
+    pub interpreters: Vec<Box<dyn Interpreter>>,
+    pub budget: usize,
+}
+
+impl Default for Differential {
+    fn default() -> Self {
+        Self {
+            interpreters: vec![
+                Box::new(Reference),
+                Box::new(Cached),
+                Box::new(Profiled),
+                Box::new(Stepped),
+            ],
+            budget: DIFFERENTIAL_STEP_BUDGET,
+        }
+    }
+}
+
+impl Differential {
+    pub fn with(mut self, interpreter: impl Interpreter + 'static) -> Self {
+        self.interpreters.push(Box::new(interpreter));
+        self
+    }
+
+    // Not every interpreter can be bounded, so the reference decides whether a case is worth
+    // comparing: it has to stop within the budget.
+    fn conclusive(&self, computer: &Computer, inputs: &[isize]) -> bool {
+        let mut computer = computer.clone();
+        let mut io = InputOutput::new(inputs);
+        for _ in 0..self.budget {
+            if computer.step(&mut io).is_err() || computer.state != State::Running {
+                return true;
+            }
+        }
+        false
+    }
+
+    pub fn check(&self, case: &Case) -> Verdict {
+        let computer = Computer::from(case.program.as_slice());
+        if !self.conclusive(&computer, &case.inputs) {
+            return Verdict::Inconclusive;
+        }
+
+        let outcomes: Vec<(String, Outcome)> = self
+            .interpreters
+            .iter()
+            .map(|i| (i.name().to_string(), i.run(computer.clone(), &case.inputs)))
+            .collect();
+        if outcomes.windows(2).all(|w| w[0].1 == w[1].1) {
+            Verdict::Agree
+        } else {
+            Verdict::Diverge(Divergence {
+                case: case.clone(),
+                outcomes,
+            })
+        }
+    }
+
+    pub fn check_all<'a>(&self, corpus: impl IntoIterator<Item = &'a Case>) -> Vec<Divergence> {
+        corpus
+            .into_iter()
+            .filter_map(|case| match self.check(case) {
+                Verdict::Diverge(divergence) => Some(divergence),
+                _ => None,
+            })
+            .collect()
+    }
+
+    fn diverges(&self, case: &Case) -> bool {
+        matches!(self.check(case), Verdict::Diverge(_))
+    }
+
+    // Delta debugging: keep or drop ever smaller chunks of cells and inputs, then shrink the
+    // remaining values, until no single change keeps the divergence.
+    pub fn minimize(&self, case: &Case) -> Case {
+        let mut best = case.clone();
+        if !self.diverges(&best) {
+            return best;
+        }
+
+        loop {
+            let mut changed = false;
+            for inputs in [false, true] {
+                // Halving sizes plus the instruction lengths, so whole instructions can go.
+                let len = cells(&mut best, inputs).len();
+                let mut sizes: Vec<usize> = std::iter::successors(Some(len.div_ceil(2)), |s| {
+                    Some(s / 2).filter(|s| *s > 0)
+                })
+                .chain(1..=4)
+                .filter(|s| *s > 0)
+                .collect();
+                sizes.sort_by(|a, b| b.cmp(a));
+                sizes.dedup();
+
+                for size in sizes {
+                    let len = cells(&mut best, inputs).len();
+                    for start in (0..len).step_by(size) {
+                        let mut candidate = best.clone();
+                        let values = cells(&mut candidate, inputs);
+                        *values = values[start..(start + size).min(len)].to_vec();
+                        if size < len && self.diverges(&candidate) {
+                            best = candidate;
+                            changed = true;
+                            break;
+                        }
+                    }
+
+                    let mut start = 0;
+                    while start < cells(&mut best, inputs).len() {
+                        let mut candidate = best.clone();
+                        let values = cells(&mut candidate, inputs);
+                        values.drain(start..(start + size).min(values.len()));
+                        if self.diverges(&candidate) {
+                            best = candidate;
+                            changed = true;
+                        } else {
+                            start += size;
+                        }
+                    }
+                }
+
+                for idx in 0..cells(&mut best, inputs).len() {
+                    let value = cells(&mut best, inputs)[idx];
+                    for simpler in [0, 1, value / 2] {
+                        if simpler.abs() >= value.abs() {
+                            continue;
+                        }
+                        let mut candidate = best.clone();
+                        cells(&mut candidate, inputs)[idx] = simpler;
+                        if self.diverges(&candidate) {
+                            best = candidate;
+                            changed = true;
+                            break;
+                        }
+                    }
+                }
+            }
+
+            if !changed {
+                return best;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        Case, Computer, Differential, Generator, InputOutput, Interpreter, Outcome, Verdict,
+    };
+
+    // Day 07 used to run on a computer without a relative base.
+    struct NoRelativeBase;
+
+    impl Interpreter for NoRelativeBase {
+        fn name(&self) -> &str {
+            "no relative base"
+        }
+
+        fn run(&self, mut computer: Computer, inputs: &[isize]) -> Outcome {
+            let mut io = InputOutput::new(inputs);
+            let mut result = Ok(());
+            while computer.state == crate::State::Running {
+                result = computer.step(&mut io);
+                computer.relative_base = 0;
+                if result.is_err() {
+                    break;
+                }
+            }
+            Outcome::new(computer, io, result)
+        }
+    }
+
+    #[test]
+    fn test_examples() {
+        let differential = Differential {
+            budget: 10_000,
+            ..Default::default()
+        };
+        let case = Case {
+            name: "echo".to_string(),
+            program: vec![3, 9, 8, 9, 10, 9, 4, 9, 99, -1, 8],
+            inputs: vec![8],
+        };
+        assert_eq!(differential.check(&case), Verdict::Agree);
+
+        let case = Case {
+            name: "loop".to_string(),
+            program: vec![1105, 1, 0],
+            inputs: vec![],
+        };
+        assert_eq!(differential.check(&case), Verdict::Inconclusive);
+
+        for program in [
+            vec![1101, isize::MAX, 1, 0, 99],
+            vec![109, isize::MAX, 109, 1, 99],
+            vec![1101, 1, 1, 1 << 62, 99],
+        ] {
+            let case = Case {
+                name: "fault".to_string(),
+                program,
+                inputs: vec![],
+            };
+            assert_eq!(differential.check(&case), Verdict::Agree);
+        }
+
+        let mut generator = Generator::new(7);
+        assert_eq!(generator.program(4), Generator::new(7).program(4));
+
+        let corpus: Vec<Case> = (0..300).map(|n| generator.case(n, 12)).collect();
+        assert!(differential.check_all(&corpus).is_empty());
+
+        let differential = differential.with(NoRelativeBase);
+        let divergences = differential.check_all(&corpus);
+        assert!(!divergences.is_empty());
+        assert_eq!(divergences[0].diverging(), ["no relative base"]);
+
+        let minimized = differential.minimize(&divergences[0].case);
+        assert!(matches!(
+            differential.check(&minimized),
+            Verdict::Diverge(_)
+        ));
+        assert_eq!(minimized.program, [109, 1]);
+        assert!(minimized.inputs.is_empty());
+
+        let Verdict::Diverge(divergence) = differential.check(&minimized) else {
+            unreachable!();
+        };
+        assert_eq!(
+            divergence.to_string(),
+            "\
+random #0 with inputs []
+109,1
+  reference: Running at 0002, relative base 1, outputs [], Fault at 0002 (opcode 0): Illegal mnemonic: 0!
+  cached: Running at 0002, relative base 1, outputs [], Fault at 0002 (opcode 0): Illegal mnemonic: 0!
+  profiled: Running at 0002, relative base 1, outputs [], Fault at 0002 (opcode 0): Illegal mnemonic: 0!
+  debugger: Running at 0002, relative base 1, outputs [], Fault at 0002 (opcode 0): Illegal mnemonic: 0!
+  no relative base: Running at 0002, relative base 0, outputs [], Fault at 0002 (opcode 0): Illegal mnemonic: 0!
+"
+        );
+    }
+
+    #[test]
+    fn test_input() {
+        let differential = Differential::default();
+        let corpus = crate::puzzle_corpus("..").unwrap();
+        assert_eq!(corpus.len(), 4 * 12);
+        assert!(differential.check_all(&corpus).is_empty());
+    }
+}
@@ -0,0 +1,500 @@
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap, HashSet, VecDeque},
+    fmt::Display,
+};
+
+use crate::{
+    computer::parse_program, Memory, Mnemonic, OpCode, ParameterMode, PARAMETER_MODE_FLAGS,
+};
+
+const LISTING_COMMENT_COLUMN: usize = 40;
+const LISTING_DATA_PER_LINE: usize = 8;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Operand {
+    Position(isize),
+    Immediate(isize),
+    Relative(isize),
+}
+
+impl Operand {
+    pub const fn new(parameter_mode: ParameterMode, value: isize) -> Self {
+        match parameter_mode {
+            ParameterMode::Position => Self::Position(value),
+            ParameterMode::Immediate => Self::Immediate(value),
+            ParameterMode::Relative => Self::Relative(value),
+        }
+    }
+
+    pub const fn value(&self) -> isize {
+        match self {
+            Self::Position(v) | Self::Immediate(v) | Self::Relative(v) => *v,
+        }
+    }
+
+    pub const fn parameter_mode(&self) -> ParameterMode {
+        match self {
+            Self::Position(_) => ParameterMode::Position,
+            Self::Immediate(_) => ParameterMode::Immediate,
+            Self::Relative(_) => ParameterMode::Relative,
+        }
+    }
+}
+
+impl Display for Operand {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Position(p) => write!(f, "[{p}]"),
+            Self::Immediate(v) => write!(f, "#{v}"),
+            Self::Relative(o) if *o < 0 => write!(f, "rb{o}"),
+            Self::Relative(o) => write!(f, "rb+{o}"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Flow {
+    Next,
+    Branch(Option<usize>),
+    Jump(Option<usize>),
+    Halt,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Instruction {
+    pub address: usize,
+    pub opcode: OpCode,
+    pub operands: Vec<Operand>,
+}
+
+impl Instruction {
+    pub fn decode(memory: &[isize], address: usize) -> Option<Self> {
+        let opcode = OpCode::try_from(*memory.get(address)?).ok()?;
+        let parameters =
+            memory.get(address + 1..address + 1 + opcode.mnemonic.parameter_count())?;
+        let operands = parameters
+            .iter()
+            .zip(opcode.parameter_modes)
+            .map(|(value, parameter_mode)| Operand::new(parameter_mode, *value))
+            .collect();
+
+        Some(Self {
+            address,
+            opcode,
+            operands,
+        })
+    }
+
+    pub fn fetch(memory: &Memory, address: usize) -> Option<Self> {
+        let window = memory.read(address, PARAMETER_MODE_FLAGS + 1);
+        let mut instruction = Self::decode(&window, 0)?;
+        instruction.address = address;
+        Some(instruction)
+    }
+
+    pub fn size(&self) -> usize {
+        self.operands.len() + 1
+    }
+
+    pub fn next_address(&self) -> usize {
+        self.address + self.size()
+    }
+
+    pub fn raw(&self) -> Vec<isize> {
+        let mut raw = Vec::with_capacity(self.size());
+        raw.push(self.opcode.encode());
+        raw.extend(self.operands.iter().map(|o| o.value()));
+        raw
+    }
+
+    pub fn flow(&self) -> Flow {
+        let target = || match self.operands[1] {
+            Operand::Immediate(t) => usize::try_from(t).ok(),
+            _ => None,
+        };
+
+        match (self.opcode.mnemonic, self.operands.first()) {
+            (Mnemonic::Halt, _) => Flow::Halt,
+            (Mnemonic::JumpIfTrue, Some(Operand::Immediate(c))) if *c != 0 => Flow::Jump(target()),
+            (Mnemonic::JumpIfTrue, Some(Operand::Immediate(_))) => Flow::Next,
+            (Mnemonic::JumpIfFalse, Some(Operand::Immediate(c))) if *c == 0 => Flow::Jump(target()),
+            (Mnemonic::JumpIfFalse, Some(Operand::Immediate(_))) => Flow::Next,
+            (Mnemonic::JumpIfTrue | Mnemonic::JumpIfFalse, _) => Flow::Branch(target()),
+            _ => Flow::Next,
+        }
+    }
+
+    fn constant_result(&self) -> Option<isize> {
+        match (self.opcode.mnemonic, self.operands.as_slice()) {
+            (Mnemonic::Add, [Operand::Immediate(a), Operand::Immediate(b), _]) => a.checked_add(*b),
+            (Mnemonic::Mul, [Operand::Immediate(a), Operand::Immediate(b), _]) => a.checked_mul(*b),
+            _ => None,
+        }
+    }
+}
+
+impl Display for Instruction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.opcode.mnemonic)?;
+        let mut sep = " ";
+        for operand in &self.operands {
+            write!(f, "{sep}{operand}")?;
+            sep = ", ";
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Listing {
+    pub program: Vec<isize>,
+    pub instructions: BTreeMap<usize, Instruction>,
+    pub labels: BTreeMap<usize, String>,
+    pub calls: BTreeSet<usize>,
+}
+
+impl From<&str> for Listing {
+    fn from(value: &str) -> Self {
+        Self::from(parse_program(value).as_slice())
+    }
+}
+
+impl From<&[isize]> for Listing {
+    fn from(value: &[isize]) -> Self {
+        let mut listing = Self {
+            program: value.to_vec(),
+            instructions: BTreeMap::new(),
+            labels: BTreeMap::new(),
+            calls: BTreeSet::new(),
+        };
+        let (branch_targets, call_targets) = listing.walk_reachable_code();
+        listing.generate_labels(&branch_targets, &call_targets);
+        listing
+    }
+}
+
+impl Listing {
+    fn walk_reachable_code(&mut self) -> (BTreeSet<usize>, BTreeSet<usize>) {
+        let mut branch_targets = BTreeSet::new();
+        let mut call_targets = BTreeSet::new();
+        let mut occupied = HashSet::new();
+        let mut constant_before = HashMap::new();
+
+        let mut q = VecDeque::new();
+        q.push_back(0);
+
+        while let Some(address) = q.pop_front() {
+            if occupied.contains(&address) {
+                continue;
+            }
+            let Some(instruction) = Instruction::decode(&self.program, address) else {
+                continue;
+            };
+            let next = instruction.next_address();
+            if (address..next).any(|a| occupied.contains(&a)) {
+                continue;
+            }
+            occupied.extend(address..next);
+
+            if let Some(constant) = instruction.constant_result() {
+                constant_before.insert(next, constant);
+            }
+
+            match instruction.flow() {
+                Flow::Next => q.push_back(next),
+                Flow::Halt => (),
+                Flow::Branch(target) => {
+                    q.push_back(next);
+                    if let Some(target) = target {
+                        q.push_back(target);
+                        branch_targets.insert(target);
+                    }
+                }
+                Flow::Jump(target) => {
+                    // a jump right after pushing its own return address is a subroutine call
+                    let is_call = constant_before.get(&address) == Some(&(next as isize));
+                    if is_call {
+                        self.calls.insert(address);
+                        q.push_back(next);
+                    }
+                    if let Some(target) = target {
+                        q.push_back(target);
+                        if is_call {
+                            call_targets.insert(target);
+                        } else {
+                            branch_targets.insert(target);
+                        }
+                    }
+                }
+            }
+
+            self.instructions.insert(address, instruction);
+        }
+
+        (branch_targets, call_targets)
+    }
+
+    fn generate_labels(
+        &mut self,
+        branch_targets: &BTreeSet<usize>,
+        call_targets: &BTreeSet<usize>,
+    ) {
+        for target in branch_targets {
+            if self.is_code(*target) {
+                self.labels.insert(*target, format!("lbl_{target:04}"));
+            }
+        }
+        for target in call_targets {
+            if self.is_code(*target) {
+                self.labels.insert(*target, format!("fn_{target:04}"));
+            }
+        }
+
+        let variables: BTreeSet<usize> = self
+            .instructions
+            .values()
+            .flat_map(|i| i.operands.iter())
+            .filter_map(|o| match o {
+                Operand::Position(p) => usize::try_from(*p).ok(),
+                _ => None,
+            })
+            .filter(|p| *p < self.program.len() && !self.is_inside_code(*p))
+            .collect();
+        for variable in variables {
+            self.labels.insert(variable, format!("var_{variable:04}"));
+        }
+
+        self.labels.insert(0, "start".to_string());
+    }
+
+    pub fn is_code(&self, address: usize) -> bool {
+        self.instructions.contains_key(&address)
+    }
+
+    pub fn is_inside_code(&self, address: usize) -> bool {
+        self.instructions
+            .range(..=address)
+            .next_back()
+            .map(|(_, i)| address < i.next_address())
+            .unwrap_or(false)
+    }
+
+    pub fn label(&self, address: usize) -> Option<&str> {
+        self.labels.get(&address).map(|l| l.as_str())
+    }
+
+    fn format_operand(&self, instruction: &Instruction, idx: usize) -> String {
+        let operand = instruction.operands[idx];
+        let is_jump_target = matches!(
+            instruction.opcode.mnemonic,
+            Mnemonic::JumpIfTrue | Mnemonic::JumpIfFalse
+        ) && idx == 1;
+
+        let label = usize::try_from(operand.value())
+            .ok()
+            .and_then(|address| self.label(address));
+        match (operand, label) {
+            (Operand::Immediate(_), Some(label)) if is_jump_target => format!("#{label}"),
+            (Operand::Position(_), Some(label)) if !self.is_code(operand.value() as usize) => {
+                format!("[{label}]")
+            }
+            _ => operand.to_string(),
+        }
+    }
+
+    fn format_instruction(&self, instruction: &Instruction) -> String {
+        let operands = (0..instruction.operands.len())
+            .map(|idx| self.format_operand(instruction, idx))
+            .collect::<Vec<_>>()
+            .join(", ");
+        if operands.is_empty() {
+            instruction.opcode.mnemonic.to_string()
+        } else {
+            format!("{} {operands}", instruction.opcode.mnemonic)
+        }
+    }
+
+    fn next_boundary(&self, address: usize) -> usize {
+        let next_code = self
+            .instructions
+            .range(address..)
+            .next()
+            .map(|(a, _)| *a)
+            .unwrap_or(self.program.len());
+        let next_label = self
+            .labels
+            .range(address + 1..)
+            .next()
+            .map(|(a, _)| *a)
+            .unwrap_or(self.program.len());
+
+        next_code
+            .min(next_label)
+            .min(address + LISTING_DATA_PER_LINE)
+            .min(self.program.len())
+    }
+
+    pub fn lines(&self) -> Vec<(Option<usize>, String)> {
+        let mut lines = Vec::new();
+        let mut address = 0;
+
+        while address < self.program.len() {
+            if let Some(label) = self.label(address) {
+                lines.push((None, format!("{label}:")));
+            }
+
+            if let Some(instruction) = self.instructions.get(&address) {
+                let text = format!("    {}", self.format_instruction(instruction));
+                let mut line = format!(
+                    "{text:<LISTING_COMMENT_COLUMN$} ; {address:04} - {}",
+                    join(&instruction.raw())
+                );
+                match instruction.flow() {
+                    Flow::Jump(_) if self.calls.contains(&address) => line.push_str(" - call"),
+                    Flow::Jump(None) | Flow::Branch(None) => line.push_str(" - dynamic jump"),
+                    _ => (),
+                }
+                lines.push((Some(address), line));
+                address = instruction.next_address();
+            } else {
+                let end = self.next_boundary(address);
+                let text = format!(
+                    "    db {}",
+                    self.program[address..end]
+                        .iter()
+                        .map(|v| v.to_string())
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                );
+                lines.push((
+                    Some(address),
+                    format!("{text:<LISTING_COMMENT_COLUMN$} ; {address:04}"),
+                ));
+                address = end;
+            }
+        }
+
+        lines
+    }
+}
+
+fn join(values: &[isize]) -> String {
+    values
+        .iter()
+        .map(|v| v.to_string())
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+impl Display for Listing {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (_, line) in self.lines() {
+            writeln!(f, "{line}")?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Flow, Instruction, Listing, Mnemonic, Operand};
+
+    #[test]
+    fn test_decode() {
+        let instruction = Instruction::decode(&[21101, 37, 0, -2], 0).unwrap();
+        assert_eq!(instruction.opcode.mnemonic, Mnemonic::Add);
+        assert_eq!(
+            instruction.operands,
+            [
+                Operand::Immediate(37),
+                Operand::Immediate(0),
+                Operand::Relative(-2)
+            ]
+        );
+        assert_eq!(instruction.raw(), [21101, 37, 0, -2]);
+        assert_eq!(instruction.to_string(), "add #37, #0, rb-2");
+        assert_eq!(instruction.flow(), Flow::Next);
+
+        assert_eq!(Instruction::decode(&[1101, 1, 2], 0), None);
+        assert_eq!(Instruction::decode(&[11101, 1, 2, 3], 0), None);
+
+        let instruction = Instruction::decode(&[1105, 1, 42], 0).unwrap();
+        assert_eq!(instruction.flow(), Flow::Jump(Some(42)));
+        let instruction = Instruction::decode(&[1006, 7, 42], 0).unwrap();
+        assert_eq!(instruction.flow(), Flow::Branch(Some(42)));
+        let instruction = Instruction::decode(&[2106, 0, 0], 0).unwrap();
+        assert_eq!(instruction.flow(), Flow::Jump(None));
+    }
+
+    #[test]
+    fn test_examples() {
+        let program = "3,12,6,12,15,1,13,14,13,4,13,99,-1,0,1,9";
+        let listing = Listing::from(program);
+        assert_eq!(
+            listing.instructions.keys().copied().collect::<Vec<_>>(),
+            [0, 2, 5, 9, 11]
+        );
+        assert_eq!(listing.label(9), None);
+        assert_eq!(listing.label(12), Some("var_0012"));
+        assert_eq!(
+            listing.to_string(),
+            "\
+start:
+    input [var_0012]                     ; 0000 - 3,12
+    jump_if_false [var_0012], [var_0015] ; 0002 - 6,12,15 - dynamic jump
+    add [var_0013], [var_0014], [var_0013] ; 0005 - 1,13,14,13
+    output [var_0013]                    ; 0009 - 4,13
+    halt                                 ; 0011 - 99
+var_0012:
+    db -1                                ; 0012
+var_0013:
+    db 0                                 ; 0013
+var_0014:
+    db 1                                 ; 0014
+var_0015:
+    db 9                                 ; 0015
+"
+        );
+    }
+
+    #[test]
+    fn test_calls() {
+        let program = "109,100,21101,0,9,0,1105,1,12,4,101,99,204,1,2106,0,0";
+        let listing = Listing::from(program);
+        assert!(listing.calls.contains(&6));
+        assert!(listing.is_code(9));
+        assert_eq!(listing.label(12), Some("fn_0012"));
+
+        let text = listing.to_string();
+        assert!(text.contains("jump_if_true #1, #fn_0012"));
+        assert!(text.contains("; 0006 - 1105,1,12 - call"));
+        assert!(text.contains("; 0014 - 2106,0,0 - dynamic jump"));
+    }
+
+    #[test]
+    fn test_overflow() {
+        let listing = Listing::from("1101,9223372036854775807,1,0,99");
+        assert_eq!(listing.instructions.len(), 2);
+        assert!(listing
+            .to_string()
+            .starts_with("start:\n    add #9223372036854775807, #1, "));
+    }
+
+    #[test]
+    fn test_input() {
+        let program = std::fs::read_to_string("../day_09/input/program.txt").unwrap();
+        let listing = Listing::from(program.as_str());
+        assert!(listing.is_code(0));
+        assert!(!listing.is_code(63));
+        assert_eq!(listing.instructions.len(), 94);
+
+        for instruction in listing.instructions.values() {
+            let raw = instruction.raw();
+            assert_eq!(
+                &listing.program[instruction.address..instruction.next_address()],
+                raw.as_slice()
+            );
+        }
+    }
+}
@@ -0,0 +1,171 @@
+use std::fmt::Display;
+
+use crate::Mnemonic;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FaultReason {
+    IllegalOpCode(String),
+    ImmediateTarget(Mnemonic),
+    NegativeAddress(isize),
+    AddressLimit(usize),
+    Overflow,
+    ResumedHalted,
+}
+
+impl Display for FaultReason {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::IllegalOpCode(msg) => write!(f, "{msg}"),
+            Self::ImmediateTarget(mnemonic) => write!(
+                f,
+                "Target parameter of {mnemonic} cannot be in immediate mode!"
+            ),
+            Self::NegativeAddress(address) => write!(f, "Negative address {address}!"),
+            Self::AddressLimit(address) => write!(f, "Address limit exceeded after {address}!"),
+            Self::Overflow => write!(f, "Arithmetic overflow!"),
+            Self::ResumedHalted => write!(f, "Halted program can't be resumed!"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Fault {
+    pub instruction_pointer: usize,
+    pub opcode: isize,
+    pub reason: FaultReason,
+}
+
+impl Display for Fault {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Fault at {:04} (opcode {}): {}",
+            self.instruction_pointer, self.opcode, self.reason
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        Computer, Fault, FaultReason, InputOutput, Mnemonic, OpCode, ParameterMode,
+        PARAMETER_MODE_FLAGS,
+    };
+
+    #[test]
+    fn test_examples() {
+        let mut io = InputOutput::new(&[]);
+
+        let mut computer = Computer::from("1101,1,1,3,98");
+        let fault = computer.eval(&mut io).unwrap_err();
+        assert_eq!(
+            fault,
+            Fault {
+                instruction_pointer: 4,
+                opcode: 98,
+                reason: FaultReason::IllegalOpCode("Illegal mnemonic: 98!".to_string())
+            }
+        );
+        assert_eq!(
+            fault.to_string(),
+            "Fault at 0004 (opcode 98): Illegal mnemonic: 98!"
+        );
+
+        let mut computer = Computer::from("11101,1,1,3,99");
+        assert_eq!(
+            computer.eval(&mut io).unwrap_err().reason,
+            FaultReason::IllegalOpCode(
+                "Target parameter of add cannot be in immediate mode!".to_string()
+            )
+        );
+
+        let mut computer = Computer::from("1006,5,-2,99,0,0");
+        assert_eq!(
+            computer.eval(&mut io).unwrap_err(),
+            Fault {
+                instruction_pointer: 0,
+                opcode: 1006,
+                reason: FaultReason::NegativeAddress(-2)
+            }
+        );
+
+        let mut computer = Computer::from("99");
+        computer.eval(&mut io).unwrap();
+        assert_eq!(
+            computer.eval(&mut io).unwrap_err().reason,
+            FaultReason::ResumedHalted
+        );
+        assert_eq!(
+            computer.step(&mut io).unwrap_err().reason,
+            FaultReason::ResumedHalted
+        );
+    }
+
+    #[test]
+    fn test_overflow() {
+        let mut io = InputOutput::new(&[]);
+
+        let mut computer = Computer::from("1101,9223372036854775807,1,0,99");
+        assert_eq!(
+            computer.eval(&mut io).unwrap_err(),
+            Fault {
+                instruction_pointer: 0,
+                opcode: 1101,
+                reason: FaultReason::Overflow
+            }
+        );
+
+        let mut computer = Computer::from("1102,4611686018427387904,-3,0,99");
+        assert_eq!(
+            computer.eval(&mut io).unwrap_err().reason,
+            FaultReason::Overflow
+        );
+
+        let mut computer = Computer::from("109,9223372036854775807,109,1,99");
+        let fault = computer.eval(&mut io).unwrap_err();
+        assert_eq!(fault.instruction_pointer, 2);
+        assert_eq!(fault.reason, FaultReason::Overflow);
+        assert_eq!(
+            fault.to_string(),
+            "Fault at 0002 (opcode 109): Arithmetic overflow!"
+        );
+
+        let mut computer = Computer::from("109,-1,22201,0,0,0,99");
+        assert_eq!(
+            computer.eval(&mut io).unwrap_err().reason,
+            FaultReason::NegativeAddress(-1)
+        );
+
+        let mut computer = Computer::from("109,1,203,9223372036854775807,99");
+        let mut input = InputOutput::new(&[1]);
+        assert_eq!(
+            computer.eval(&mut input).unwrap_err().reason,
+            FaultReason::Overflow
+        );
+
+        let mut computer =
+            Computer::from("1101,4,0,9223372036854775807,1105,1,9223372036854775807");
+        assert_eq!(
+            computer.eval(&mut io).unwrap_err(),
+            Fault {
+                instruction_pointer: 9_223_372_036_854_775_807,
+                opcode: 4,
+                reason: FaultReason::AddressLimit(9_223_372_036_854_775_807)
+            }
+        );
+    }
+
+    #[test]
+    fn test_immediate_target() {
+        let opcode = OpCode {
+            mnemonic: Mnemonic::Add,
+            parameter_modes: [ParameterMode::Immediate; PARAMETER_MODE_FLAGS],
+        };
+        let mut computer = Computer::from("11101,1,1,3,99");
+        let mut io = InputOutput::new(&[]);
+        assert_eq!(
+            opcode.eval(&mut computer, &mut io).unwrap_err(),
+            FaultReason::ImmediateTarget(Mnemonic::Add)
+        );
+    }
+}
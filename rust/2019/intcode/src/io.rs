@@ -0,0 +1,110 @@
+use std::{collections::VecDeque, fmt::Display};
+
+#[derive(Debug, Clone, Default)]
+pub struct InputOutput {
+    pub in_q: VecDeque<isize>,
+    pub out_q: VecDeque<isize>,
+}
+
+impl Display for InputOutput {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "IO:")?;
+        writeln!(f, "  i_q: {:?}", self.in_q)?;
+        writeln!(f, "  o_q: {:?}", self.out_q)?;
+        Ok(())
+    }
+}
+
+impl InputOutput {
+    pub fn new(initial_in: &[isize]) -> Self {
+        Self {
+            in_q: initial_in.iter().copied().collect(),
+            out_q: VecDeque::new(),
+        }
+    }
+
+    pub fn read_in(&mut self) -> Option<isize> {
+        self.in_q.pop_front()
+    }
+
+    pub fn write_out(&mut self, data: isize) {
+        self.out_q.push_back(data);
+    }
+
+    pub fn write_to_in_q(&mut self, i: isize) {
+        self.in_q.push_back(i)
+    }
+
+    pub fn write_to_in_q_ascii(&mut self, data: &str) {
+        for c in data.chars() {
+            self.write_to_in_q(c as isize);
+        }
+    }
+
+    pub fn read_from_out_q(&mut self) -> Option<isize> {
+        self.out_q.pop_front()
+    }
+
+    pub fn read_from_out_q_ascii(&mut self) -> Option<String> {
+        let mut s = String::new();
+
+        while let Some(i) = self.out_q.pop_front() {
+            if !(0..=255).contains(&i) {
+                self.out_q.push_front(i);
+                break;
+            }
+            s.push(char::from_u32(i as u32).unwrap());
+        }
+
+        if !s.is_empty() {
+            Some(s)
+        } else {
+            None
+        }
+    }
+
+    pub fn debug(&self) {
+        for (name, q) in [("in_q", &self.in_q), ("out_q", &self.out_q)] {
+            let mut sep = "";
+            let mut ascii = false;
+            println!("{name}:");
+            for i in q {
+                print!("{sep}");
+                if (0..=255).contains(i) {
+                    if !ascii {
+                        print!("ASCII: ");
+                        ascii = true;
+                    }
+                    print!("{}", char::from_u32(*i as u32).unwrap());
+                    sep = "";
+                } else {
+                    print!("isize: {}", *i);
+                    sep = "\n";
+                }
+            }
+            print!("{sep}");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::InputOutput;
+
+    #[test]
+    fn test_ascii() {
+        let mut io = InputOutput::new(&[]);
+        io.write_to_in_q_ascii("NOT A J\n");
+        assert_eq!(io.in_q.len(), 8);
+        assert_eq!(io.read_in(), Some('N' as isize));
+
+        io.write_out('o' as isize);
+        io.write_out('k' as isize);
+        io.write_out(1141857182);
+        io.write_out('!' as isize);
+        assert_eq!(io.read_from_out_q_ascii(), Some("ok".to_string()));
+        assert_eq!(io.read_from_out_q_ascii(), None);
+        assert_eq!(io.read_from_out_q(), Some(1141857182));
+        assert_eq!(io.read_from_out_q_ascii(), Some("!".to_string()));
+    }
+}
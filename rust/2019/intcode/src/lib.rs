@@ -0,0 +1,37 @@
+mod assembler;
+mod cached;
+mod computer;
+mod debugger;
+mod device;
+mod differential;
+mod disassembler;
+mod fault;
+mod io;
+mod maze;
+mod memory;
+mod network;
+mod opcode;
+mod profiler;
+mod snapshot;
+
+pub use assembler::{assemble, assemble_to_string, AssemblerError};
+pub use cached::CachedComputer;
+pub use computer::{parse_program, Computer, State};
+pub use debugger::{Debugger, StopReason};
+pub use device::{AsciiTerminal, ChannelDevice, Device, Event, Transcript};
+pub use differential::{
+    puzzle_corpus, Cached, Case, Differential, Divergence, Generator, Interpreter, Outcome,
+    Profiled, Reference, Stepped, Verdict, DIFFERENTIAL_STEP_BUDGET,
+};
+pub use disassembler::{Flow, Instruction, Listing, Operand};
+pub use fault::{Fault, FaultReason};
+pub use io::InputOutput;
+pub use maze::{Maze, MazeError, MazeMapper, MazePos, MazeProtocol, MAZE_MAX_COMMANDS, MAZE_STEPS};
+pub use memory::{Memory, PAGE_SIZE};
+pub use network::{
+    FirstPacket, Nat, NatAction, Network, Packet, Trace, TraceEvent, WakeOnIdle, NAT_ADDRESS,
+    NETWORK_MAX_ROUNDS, NETWORK_MAX_STEPS,
+};
+pub use opcode::{Mnemonic, OpCode, ParameterMode, TryFromError, PARAMETER_MODE_FLAGS};
+pub use profiler::{BranchCount, Profile};
+pub use snapshot::{Snapshot, SnapshotError};
@@ -0,0 +1,373 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    fmt::Display,
+};
+
+use crate::{CachedComputer, Fault, InputOutput};
+
+pub type MazePos = (isize, isize);
+
+// North, south, west and east, the order of `MazeProtocol::commands`.
+pub const MAZE_STEPS: [MazePos; 4] = [(0, -1), (0, 1), (-1, 0), (1, 0)];
+pub const MAZE_MAX_COMMANDS: usize = 100_000;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MazeError {
+    pub msg: String,
+}
+
+impl Display for MazeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.msg)
+    }
+}
+
+impl From<Fault> for MazeError {
+    fn from(value: Fault) -> Self {
+        Self {
+            msg: value.to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MazeProtocol {
+    pub commands: [isize; 4],
+    pub wall: isize,
+    pub start: isize,
+}
+
+impl Default for MazeProtocol {
+    fn default() -> Self {
+        Self {
+            commands: [1, 2, 3, 4],
+            wall: 0,
+            start: 1,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Maze {
+    pub tiles: HashMap<MazePos, isize>,
+    pub wall: isize,
+}
+
+impl Maze {
+    pub fn step((x, y): MazePos, dir: usize) -> MazePos {
+        (x + MAZE_STEPS[dir].0, y + MAZE_STEPS[dir].1)
+    }
+
+    pub fn is_open(&self, pos: MazePos) -> bool {
+        matches!(self.tiles.get(&pos), Some(status) if *status != self.wall)
+    }
+
+    pub fn find(&self, status: isize) -> Option<MazePos> {
+        self.tiles
+            .iter()
+            .filter(|(_, s)| **s == status)
+            .map(|(pos, _)| *pos)
+            .min()
+    }
+
+    pub fn shortest_path(&self, from: MazePos, to: MazePos) -> Option<Vec<usize>> {
+        self.search(from, |pos| pos == to).map(|(_, path)| path)
+    }
+
+    pub fn flood_fill(&self, from: MazePos) -> HashMap<MazePos, usize> {
+        let mut distances = HashMap::from([(from, 0)]);
+        let mut q = VecDeque::from([from]);
+
+        while let Some(pos) = q.pop_front() {
+            let distance = distances[&pos];
+            for dir in 0..MAZE_STEPS.len() {
+                let next = Self::step(pos, dir);
+                if self.is_open(next) && !distances.contains_key(&next) {
+                    distances.insert(next, distance + 1);
+                    q.push_back(next);
+                }
+            }
+        }
+
+        distances
+    }
+
+    // Breadth first over open tiles, returns the first tile accepted by `goal` and the way there.
+    fn search(
+        &self,
+        from: MazePos,
+        mut goal: impl FnMut(MazePos) -> bool,
+    ) -> Option<(MazePos, Vec<usize>)> {
+        let mut came_from: HashMap<MazePos, Option<(MazePos, usize)>> =
+            HashMap::from([(from, None)]);
+        let mut q = VecDeque::from([from]);
+
+        while let Some(pos) = q.pop_front() {
+            if goal(pos) {
+                let mut path = Vec::new();
+                let mut current = pos;
+                while let Some((previous, dir)) = came_from[&current] {
+                    path.push(dir);
+                    current = previous;
+                }
+                path.reverse();
+                return Some((pos, path));
+            }
+
+            for dir in 0..MAZE_STEPS.len() {
+                let next = Self::step(pos, dir);
+                if self.is_open(next) && !came_from.contains_key(&next) {
+                    came_from.insert(next, Some((pos, dir)));
+                    q.push_back(next);
+                }
+            }
+        }
+
+        None
+    }
+}
+
+impl Display for Maze {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let (min_x, max_x) = (
+            self.tiles.keys().map(|p| p.0).min().unwrap_or_default(),
+            self.tiles.keys().map(|p| p.0).max().unwrap_or_default(),
+        );
+        let (min_y, max_y) = (
+            self.tiles.keys().map(|p| p.1).min().unwrap_or_default(),
+            self.tiles.keys().map(|p| p.1).max().unwrap_or_default(),
+        );
+
+        for y in min_y..=max_y {
+            for x in min_x..=max_x {
+                match self.tiles.get(&(x, y)) {
+                    None => write!(f, " ")?,
+                    Some(status) if *status == self.wall => write!(f, "#")?,
+                    Some(status) => write!(f, "{status}")?,
+                }
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct MazeMapper {
+    computer: CachedComputer,
+    io: InputOutput,
+    pub protocol: MazeProtocol,
+    pub maze: Maze,
+    pub pos: MazePos,
+    pub log: Vec<isize>,
+    pub max_commands: usize,
+}
+
+impl MazeMapper {
+    pub fn new(program: &str) -> Self {
+        Self::with_protocol(program, MazeProtocol::default())
+    }
+
+    pub fn with_protocol(program: &str, protocol: MazeProtocol) -> Self {
+        Self {
+            computer: CachedComputer::from(program),
+            io: InputOutput::new(&[]),
+            protocol,
+            maze: Maze {
+                tiles: HashMap::from([((0, 0), protocol.start)]),
+                wall: protocol.wall,
+            },
+            pos: (0, 0),
+            log: Vec::new(),
+            max_commands: MAZE_MAX_COMMANDS,
+        }
+    }
+
+    pub fn command(&mut self, dir: usize) -> Result<isize, MazeError> {
+        let command = self.protocol.commands[dir];
+        self.io.write_to_in_q(command);
+        self.log.push(command);
+        self.computer.eval(&mut self.io)?;
+        let status = self.io.read_from_out_q().ok_or_else(|| MazeError {
+            msg: "Droid didn't report a status!".to_string(),
+        })?;
+
+        let next = Maze::step(self.pos, dir);
+        self.maze.tiles.insert(next, status);
+        if status != self.protocol.wall {
+            self.pos = next;
+        }
+        Ok(status)
+    }
+
+    // Heads for the closest known tile next to unexplored space, so backtracking takes the shortest
+    // way instead of retracing every step. A maze without bounds would never be done, so the
+    // number of commands is limited.
+    pub fn explore(&mut self) -> Result<(), MazeError> {
+        loop {
+            let maze = &self.maze;
+            let frontier = maze.search(self.pos, |pos| {
+                (0..MAZE_STEPS.len()).any(|dir| !maze.tiles.contains_key(&Maze::step(pos, dir)))
+            });
+            let Some((target, path)) = frontier else {
+                return Ok(());
+            };
+
+            if self.log.len() + path.len() >= self.max_commands {
+                return Err(MazeError {
+                    msg: format!("Maze not explored within {} commands!", self.max_commands),
+                });
+            }
+            for dir in path {
+                self.command(dir)?;
+            }
+            let dir = (0..MAZE_STEPS.len())
+                .find(|dir| !self.maze.tiles.contains_key(&Maze::step(target, *dir)))
+                .unwrap();
+            self.command(dir)?;
+        }
+    }
+
+    pub fn replay(&mut self, commands: &[isize]) -> Result<(), MazeError> {
+        for command in commands {
+            let dir = self
+                .protocol
+                .commands
+                .iter()
+                .position(|c| c == command)
+                .ok_or_else(|| MazeError {
+                    msg: format!("Command {command} not part of the protocol!"),
+                })?;
+            self.command(dir)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{assemble_to_string, Maze, MazeMapper, MazeProtocol};
+
+    // A droid in a 3x2 room, x in 0..=2 and y in 0..=1, starting in the top left corner.
+    const ROOM: &str = "\
+        loop:\n\
+            input [dir]\n\
+            equals [dir], #1, [is_north]\n\
+            jump_if_true [is_north], #north\n\
+            equals [dir], #2, [is_south]\n\
+            jump_if_true [is_south], #south\n\
+            equals [dir], #3, [is_west]\n\
+            jump_if_true [is_west], #west\n\
+            add [x], #1, [nx]\n\
+            less_than [nx], #3, [ok]\n\
+            jump_if_false [ok], #wall\n\
+            add [nx], #0, [x]\n\
+            jmp moved\n\
+        west:\n\
+            jump_if_false [x], #wall\n\
+            add [x], #-1, [x]\n\
+            jmp moved\n\
+        north:\n\
+            jump_if_false [y], #wall\n\
+            add [y], #-1, [y]\n\
+            jmp moved\n\
+        south:\n\
+            jump_if_true [y], #wall\n\
+            add [y], #1, [y]\n\
+        moved:\n\
+            mul [x], [y], [found]\n\
+            equals [found], #2, [found]\n\
+            jump_if_true [found], #oxygen\n\
+            output #1\n\
+            jmp loop\n\
+        oxygen:\n\
+            output #2\n\
+            jmp loop\n\
+        wall:\n\
+            output #0\n\
+            jmp loop\n\
+        dir: db 0\n\
+        is_north: db 0\n\
+        is_south: db 0\n\
+        is_west: db 0\n\
+        nx: db 0\n\
+        ok: db 0\n\
+        found: db 0\n\
+        x: db 0\n\
+        y: db 0\n\
+    ";
+
+    #[test]
+    fn test_examples() {
+        let program = assemble_to_string(ROOM).unwrap();
+        let mut mapper = MazeMapper::new(&program);
+        mapper.explore().unwrap();
+
+        assert_eq!(mapper.maze.to_string(), " ### \n#111#\n#112#\n ### \n");
+        assert_eq!(mapper.maze.find(2), Some((2, 1)));
+        assert_eq!(mapper.maze.shortest_path((0, 0), (2, 1)).unwrap().len(), 3);
+        assert_eq!(mapper.maze.shortest_path((0, 0), (5, 5)), None);
+
+        let distances = mapper.maze.flood_fill((2, 1));
+        assert_eq!(distances.len(), 6);
+        assert_eq!(distances.values().max(), Some(&3));
+
+        let mut replay = MazeMapper::new(&program);
+        replay.replay(&mapper.log).unwrap();
+        assert_eq!(replay.maze, mapper.maze);
+        assert_eq!(replay.pos, mapper.pos);
+
+        let protocol = MazeProtocol {
+            commands: [2, 1, 4, 3],
+            ..Default::default()
+        };
+        let mut mirrored = MazeMapper::with_protocol(&program, protocol);
+        mirrored.explore().unwrap();
+        assert_eq!(mirrored.maze.find(2), Some((-2, -1)));
+        assert_eq!(Maze::step((0, 0), 3), (1, 0));
+    }
+
+    #[test]
+    fn test_errors() {
+        let mut mapper = MazeMapper::new("99");
+        assert_eq!(
+            mapper.command(0).unwrap_err().msg,
+            "Droid didn't report a status!"
+        );
+        assert_eq!(
+            mapper.command(0).unwrap_err().msg,
+            "Fault at 0000 (opcode 99): Halted program can't be resumed!"
+        );
+
+        let mut mapper = MazeMapper::new("3,100,104,1,1105,1,0");
+        assert_eq!(
+            mapper.replay(&[1, 5]).unwrap_err().msg,
+            "Command 5 not part of the protocol!"
+        );
+
+        mapper.max_commands = 50;
+        assert_eq!(
+            mapper.explore().unwrap_err().msg,
+            "Maze not explored within 50 commands!"
+        );
+        assert!(mapper.log.len() <= 50);
+    }
+
+    #[test]
+    fn test_input() {
+        let program = std::fs::read_to_string("../day_15/input/program.txt").unwrap();
+        let mut mapper = MazeMapper::new(&program);
+        mapper.explore().unwrap();
+
+        let oxygen = mapper.maze.find(2).unwrap();
+        assert_eq!(
+            mapper.maze.shortest_path((0, 0), oxygen).unwrap().len(),
+            232
+        );
+        assert_eq!(mapper.maze.flood_fill(oxygen).values().max(), Some(&320));
+
+        let mut replay = MazeMapper::new(&program);
+        replay.replay(&mapper.log).unwrap();
+        assert_eq!(replay.maze, mapper.maze);
+    }
+}
@@ -0,0 +1,188 @@
+use std::{
+    collections::BTreeMap,
+    fmt::Debug,
+    ops::{Index, IndexMut},
+    sync::Arc,
+};
+
+use crate::FaultReason;
+
+pub const PAGE_SIZE: usize = 1024;
+// Addresses beyond this can't be written as an Intcode value.
+pub const MAX_ADDRESS: usize = isize::MAX as usize;
+
+type Page = [isize; PAGE_SIZE];
+
+static ZERO: isize = 0;
+
+#[derive(Clone, Default)]
+pub struct Memory {
+    pages: BTreeMap<usize, Arc<Page>>,
+}
+
+impl Memory {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn address(value: isize) -> Result<usize, FaultReason> {
+        usize::try_from(value).map_err(|_| FaultReason::NegativeAddress(value))
+    }
+
+    pub fn offset(address: usize, offset: usize) -> Result<usize, FaultReason> {
+        address
+            .checked_add(offset)
+            .filter(|a| *a <= MAX_ADDRESS)
+            .ok_or(FaultReason::AddressLimit(address))
+    }
+
+    pub fn get(&self, address: usize) -> isize {
+        self[address]
+    }
+
+    pub fn set(&mut self, address: usize, value: isize) {
+        self[address] = value;
+    }
+
+    pub fn read(&self, address: usize, n: usize) -> Vec<isize> {
+        // Clamped at the end of the address space.
+        (0..n)
+            .map_while(|i| address.checked_add(i))
+            .map(|a| self[a])
+            .collect()
+    }
+
+    pub fn allocated_pages(&self) -> usize {
+        self.pages.len()
+    }
+
+    pub fn non_zero(&self) -> impl Iterator<Item = (usize, isize)> + '_ {
+        self.pages.iter().flat_map(|(page_idx, page)| {
+            page.iter()
+                .enumerate()
+                .filter(|(_, v)| **v != 0)
+                .map(move |(offset, v)| (page_idx * PAGE_SIZE + offset, *v))
+        })
+    }
+}
+
+impl From<&[isize]> for Memory {
+    fn from(values: &[isize]) -> Self {
+        let mut memory = Self::new();
+        for (address, value) in values.iter().enumerate() {
+            if *value != 0 {
+                memory[address] = *value;
+            }
+        }
+        memory
+    }
+}
+
+impl Index<usize> for Memory {
+    type Output = isize;
+
+    fn index(&self, address: usize) -> &Self::Output {
+        match self.pages.get(&(address / PAGE_SIZE)) {
+            Some(page) => &page[address % PAGE_SIZE],
+            None => &ZERO,
+        }
+    }
+}
+
+impl IndexMut<usize> for Memory {
+    fn index_mut(&mut self, address: usize) -> &mut Self::Output {
+        let page = self
+            .pages
+            .entry(address / PAGE_SIZE)
+            .or_insert_with(|| Arc::new([0; PAGE_SIZE]));
+        &mut Arc::make_mut(page)[address % PAGE_SIZE]
+    }
+}
+
+impl PartialEq for Memory {
+    fn eq(&self, other: &Self) -> bool {
+        self.non_zero().eq(other.non_zero())
+    }
+}
+
+impl Eq for Memory {}
+
+impl Debug for Memory {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_map().entries(self.non_zero()).finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Computer, FaultReason, InputOutput, Memory, PAGE_SIZE};
+
+    #[test]
+    fn test_examples() {
+        let mut memory = Memory::from([1, 0, 3].as_slice());
+        assert_eq!(memory.read(0, 4), [1, 0, 3, 0]);
+        assert_eq!(memory.allocated_pages(), 1);
+
+        assert_eq!(memory[5_000_000], 0);
+        assert_eq!(memory.allocated_pages(), 1);
+
+        memory[5_000_000] = 7;
+        assert_eq!(memory.get(5_000_000), 7);
+        assert_eq!(memory.allocated_pages(), 2);
+        assert_eq!(
+            memory.non_zero().collect::<Vec<_>>(),
+            [(0, 1), (2, 3), (5_000_000, 7)]
+        );
+
+        let mut copy = memory.clone();
+        copy.set(1, 2);
+        assert_eq!(memory[1], 0);
+        assert_eq!(copy[1], 2);
+        assert_ne!(memory, copy);
+
+        copy.set(1, 0);
+        copy.set(PAGE_SIZE * 3, 0);
+        assert_eq!(memory, copy);
+
+        assert_eq!(
+            Memory::address(-1).unwrap_err(),
+            FaultReason::NegativeAddress(-1)
+        );
+    }
+
+    #[test]
+    fn test_negative_address() {
+        let mut computer = Computer::from("1,-1,0,0,99");
+        let mut io = InputOutput::new(&[]);
+        assert_eq!(
+            computer.eval(&mut io).unwrap_err().reason,
+            FaultReason::NegativeAddress(-1)
+        );
+
+        let mut computer = Computer::from("109,-5,204,0,99");
+        assert_eq!(
+            computer.eval(&mut io).unwrap_err().reason,
+            FaultReason::NegativeAddress(-5)
+        );
+    }
+
+    #[test]
+    fn test_far_address() {
+        let mut computer = Computer::from("1101,1,1,4611686018427387904,99");
+        let mut io = InputOutput::new(&[]);
+        computer.eval(&mut io).unwrap();
+        assert_eq!(computer.memory[4_611_686_018_427_387_904], 2);
+        assert_eq!(computer.memory.allocated_pages(), 2);
+        assert_eq!(computer.memory.read(usize::MAX, 2), [0]);
+    }
+
+    #[test]
+    fn test_input() {
+        let program = std::fs::read_to_string("../day_09/input/program.txt").unwrap();
+        let mut computer = Computer::from(program.as_str());
+        let mut io = InputOutput::new(&[1]);
+        computer.eval(&mut io).unwrap();
+        assert_eq!(io.out_q.len(), 1);
+        assert!(computer.memory.allocated_pages() < 4);
+    }
+}
@@ -0,0 +1,451 @@
+use std::{collections::VecDeque, fmt::Display, str::FromStr};
+
+use crate::{CachedComputer, Device, Fault, State, TryFromError};
+
+pub const NAT_ADDRESS: usize = 255;
+const NETWORK_PACKET_SIZE: usize = 3;
+const NETWORK_IDLE_ROUNDS: usize = 2;
+pub const NETWORK_MAX_ROUNDS: usize = 100_000;
+pub const NETWORK_MAX_STEPS: usize = 100_000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Packet {
+    pub source: usize,
+    pub destination: usize,
+    pub x: isize,
+    pub y: isize,
+}
+
+impl Display for Packet {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} -> {} {},{}",
+            self.source, self.destination, self.x, self.y
+        )
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NatAction {
+    Continue,
+    Send(Packet),
+    Stop(isize),
+}
+
+pub trait Nat {
+    fn receive(&mut self, packet: Packet) -> NatAction;
+    fn idle(&mut self) -> NatAction;
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct FirstPacket;
+
+impl Nat for FirstPacket {
+    fn receive(&mut self, packet: Packet) -> NatAction {
+        NatAction::Stop(packet.y)
+    }
+
+    fn idle(&mut self) -> NatAction {
+        NatAction::Continue
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct WakeOnIdle {
+    pub last_received: Option<Packet>,
+    pub last_sent: Option<Packet>,
+}
+
+impl Nat for WakeOnIdle {
+    fn receive(&mut self, packet: Packet) -> NatAction {
+        self.last_received = Some(packet);
+        NatAction::Continue
+    }
+
+    fn idle(&mut self) -> NatAction {
+        let Some(packet) = self.last_received else {
+            return NatAction::Continue;
+        };
+        if self.last_sent.map(|p| p.y) == Some(packet.y) {
+            return NatAction::Stop(packet.y);
+        }
+
+        let packet = Packet {
+            source: NAT_ADDRESS,
+            destination: 0,
+            ..packet
+        };
+        self.last_sent = Some(packet);
+        NatAction::Send(packet)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TraceEvent {
+    Packet { round: usize, packet: Packet },
+    Idle { round: usize },
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Trace {
+    pub events: Vec<TraceEvent>,
+}
+
+impl Trace {
+    pub fn replay<N: Nat + ?Sized>(&self, nat: &mut N) -> Option<isize> {
+        for event in &self.events {
+            let action = match event {
+                TraceEvent::Packet { packet, .. } if packet.destination == NAT_ADDRESS => {
+                    nat.receive(*packet)
+                }
+                TraceEvent::Packet { .. } => NatAction::Continue,
+                TraceEvent::Idle { .. } => nat.idle(),
+            };
+            if let NatAction::Stop(value) = action {
+                return Some(value);
+            }
+        }
+
+        None
+    }
+}
+
+impl Display for Trace {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for event in &self.events {
+            match event {
+                TraceEvent::Packet { round, packet } => writeln!(f, "{round:04} {packet}")?,
+                TraceEvent::Idle { round } => writeln!(f, "{round:04} idle")?,
+            }
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for Trace {
+    type Err = TryFromError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let parse_event = |line: &str| -> Option<TraceEvent> {
+            let (round, rest) = line.split_once(' ')?;
+            let round = round.parse().ok()?;
+            if rest == "idle" {
+                return Some(TraceEvent::Idle { round });
+            }
+
+            let (source, rest) = rest.split_once(" -> ")?;
+            let (destination, data) = rest.split_once(' ')?;
+            let (x, y) = data.split_once(',')?;
+            Some(TraceEvent::Packet {
+                round,
+                packet: Packet {
+                    source: source.parse().ok()?,
+                    destination: destination.parse().ok()?,
+                    x: x.parse().ok()?,
+                    y: y.parse().ok()?,
+                },
+            })
+        };
+
+        let events = s
+            .lines()
+            .map(|l| l.trim())
+            .filter(|l| !l.is_empty())
+            .map(|l| {
+                parse_event(l).ok_or_else(|| TryFromError {
+                    msg: format!("Illegal trace line '{l}'!"),
+                })
+            })
+            .collect::<Result<_, _>>()?;
+
+        Ok(Self { events })
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+struct NetworkInterface {
+    in_q: VecDeque<isize>,
+    out_q: Vec<isize>,
+    sent: Vec<(usize, isize, isize)>,
+}
+
+impl Device for NetworkInterface {
+    fn read_in(&mut self) -> Option<isize> {
+        self.in_q.pop_front()
+    }
+
+    fn write_out(&mut self, data: isize) {
+        self.out_q.push(data);
+        if self.out_q.len() == NETWORK_PACKET_SIZE {
+            // A packet to a negative address can't be delivered anywhere, it is dropped.
+            if let Ok(destination) = usize::try_from(self.out_q[0]) {
+                self.sent.push((destination, self.out_q[1], self.out_q[2]));
+            }
+            self.out_q.clear();
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Network {
+    machines: Vec<(CachedComputer, NetworkInterface)>,
+    pub idle_rounds: usize,
+    pub max_rounds: usize,
+    pub max_steps: usize,
+    pub round: usize,
+    pub trace: Trace,
+    idle_count: usize,
+}
+
+impl Network {
+    pub fn new(program: &str, n: usize) -> Self {
+        let computer = CachedComputer::from(program);
+        let machines = (0..n)
+            .map(|address| {
+                let nic = NetworkInterface {
+                    in_q: VecDeque::from([address as isize]),
+                    ..Default::default()
+                };
+                (computer.clone(), nic)
+            })
+            .collect();
+
+        Self {
+            machines,
+            idle_rounds: NETWORK_IDLE_ROUNDS,
+            max_rounds: NETWORK_MAX_ROUNDS,
+            max_steps: NETWORK_MAX_STEPS,
+            round: 0,
+            trace: Trace::default(),
+            idle_count: 0,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.machines.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.machines.is_empty()
+    }
+
+    pub fn run<N: Nat + ?Sized>(&mut self, nat: &mut N) -> Result<Option<isize>, Fault> {
+        self.run_for(nat, self.max_rounds)
+    }
+
+    // Gives up with Ok(None) once all machines halted or `rounds` rounds passed without a result.
+    // Within a round every machine runs until it waits for input, halts or used `max_steps`.
+    pub fn run_for<N: Nat + ?Sized>(
+        &mut self,
+        nat: &mut N,
+        rounds: usize,
+    ) -> Result<Option<isize>, Fault> {
+        for _ in 0..rounds {
+            if self
+                .machines
+                .iter()
+                .all(|(computer, _)| computer.state == State::Halted)
+            {
+                break;
+            }
+            if let Some(value) = self.run_round(nat)? {
+                return Ok(Some(value));
+            }
+        }
+
+        Ok(None)
+    }
+
+    pub fn run_round<N: Nat + ?Sized>(&mut self, nat: &mut N) -> Result<Option<isize>, Fault> {
+        self.round += 1;
+        let max_steps = self.max_steps;
+        let mut idle = true;
+
+        for address in 0..self.machines.len() {
+            let (computer, nic) = &mut self.machines[address];
+            if computer.state == State::Halted {
+                continue;
+            }
+
+            let starved = nic.in_q.is_empty();
+            if starved {
+                nic.in_q.push_back(-1);
+            }
+            for _ in 0..max_steps {
+                computer.step(nic)?;
+                if computer.state != State::Running {
+                    break;
+                }
+            }
+
+            let sent = std::mem::take(&mut nic.sent);
+            if !starved || !sent.is_empty() || computer.state == State::Running {
+                idle = false;
+            }
+            for (destination, x, y) in sent {
+                let packet = Packet {
+                    source: address,
+                    destination,
+                    x,
+                    y,
+                };
+                if let Some(value) = self.deliver(packet, nat) {
+                    return Ok(Some(value));
+                }
+            }
+        }
+
+        self.idle_count = if idle { self.idle_count + 1 } else { 0 };
+        if self.idle_count >= self.idle_rounds {
+            self.idle_count = 0;
+            self.trace
+                .events
+                .push(TraceEvent::Idle { round: self.round });
+            let action = nat.idle();
+            return Ok(self.act(action, nat));
+        }
+
+        Ok(None)
+    }
+
+    fn deliver<N: Nat + ?Sized>(&mut self, packet: Packet, nat: &mut N) -> Option<isize> {
+        self.trace.events.push(TraceEvent::Packet {
+            round: self.round,
+            packet,
+        });
+
+        if packet.destination == NAT_ADDRESS {
+            let action = nat.receive(packet);
+            self.act(action, nat)
+        } else if let Some((_, nic)) = self.machines.get_mut(packet.destination) {
+            nic.in_q.extend([packet.x, packet.y]);
+            None
+        } else {
+            None
+        }
+    }
+
+    fn act<N: Nat + ?Sized>(&mut self, action: NatAction, nat: &mut N) -> Option<isize> {
+        match action {
+            NatAction::Continue => None,
+            NatAction::Send(packet) => self.deliver(packet, nat),
+            NatAction::Stop(value) => Some(value),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        assemble_to_string, network::NetworkInterface, Device, FirstPacket, Network, Packet, Trace,
+        TraceEvent, WakeOnIdle,
+    };
+
+    const ECHO: &str = "\
+            input [address]\n\
+            jump_if_true [address], #receive\n\
+            output #255\n\
+            output #1\n\
+            output #5\n\
+        receive:\n\
+            input [x]\n\
+            equals [x], #-1, [empty]\n\
+            jump_if_true [empty], #receive\n\
+            input [y]\n\
+            output #255\n\
+            output [x]\n\
+            output [y]\n\
+            jmp receive\n\
+        address: db 0\n\
+        x: db 0\n\
+        y: db 0\n\
+        empty: db 0\n\
+    ";
+
+    #[test]
+    fn test_examples() {
+        let program = assemble_to_string(ECHO).unwrap();
+
+        let mut network = Network::new(&program, 3);
+        assert_eq!(network.run(&mut FirstPacket), Ok(Some(5)));
+        assert_eq!(network.trace.to_string(), "0001 0 -> 255 1,5\n");
+
+        // The NAT keeps its address even in networks that large.
+        let mut network = Network::new(&program, 300);
+        assert_eq!(network.run(&mut FirstPacket), Ok(Some(5)));
+
+        let mut network = Network::new(&program, 3);
+        assert_eq!(network.run(&mut WakeOnIdle::default()), Ok(Some(5)));
+        assert_eq!(
+            network.trace.to_string(),
+            "\
+0001 0 -> 255 1,5
+0003 idle
+0003 255 -> 0 1,5
+0004 0 -> 255 1,5
+0006 idle
+"
+        );
+
+        let trace: Trace = network.trace.to_string().parse().unwrap();
+        assert_eq!(trace, network.trace);
+        assert_eq!(
+            trace.events[2],
+            TraceEvent::Packet {
+                round: 3,
+                packet: Packet {
+                    source: 255,
+                    destination: 0,
+                    x: 1,
+                    y: 5
+                }
+            }
+        );
+        assert_eq!(trace.replay(&mut WakeOnIdle::default()), Some(5));
+        assert_eq!(trace.replay(&mut FirstPacket), Some(5));
+
+        assert_eq!(
+            "0001 0 -> 255".parse::<Trace>().unwrap_err().msg,
+            "Illegal trace line '0001 0 -> 255'!"
+        );
+    }
+
+    #[test]
+    fn test_budget() {
+        let mut network = Network::new("3,10,1105,1,0", 2);
+        assert_eq!(network.run_for(&mut FirstPacket, 10), Ok(None));
+        assert_eq!(network.round, 10);
+
+        network.max_rounds = 5;
+        assert_eq!(network.run(&mut WakeOnIdle::default()), Ok(None));
+        assert_eq!(network.round, 15);
+
+        let mut network = Network::new("1105,1,0", 2);
+        network.max_steps = 1_000;
+        assert_eq!(network.run_for(&mut FirstPacket, 3), Ok(None));
+        assert_eq!(network.round, 3);
+
+        let mut network = Network::new("99", 2);
+        assert_eq!(network.run(&mut FirstPacket), Ok(None));
+        assert_eq!(network.round, 1);
+
+        let mut nic = NetworkInterface::default();
+        for value in [-1, 2, 3, 255, 4, 5] {
+            nic.write_out(value);
+        }
+        assert_eq!(nic.sent, [(255, 4, 5)]);
+    }
+
+    #[test]
+    fn test_input() {
+        let program = std::fs::read_to_string("../day_23/input/program.txt").unwrap();
+
+        let mut network = Network::new(&program, 50);
+        assert_eq!(network.run(&mut WakeOnIdle::default()), Ok(Some(17091)));
+
+        let trace: Trace = network.trace.to_string().parse().unwrap();
+        assert_eq!(trace.replay(&mut FirstPacket), Some(24954));
+        assert_eq!(trace.replay(&mut WakeOnIdle::default()), Some(17091));
+    }
+}
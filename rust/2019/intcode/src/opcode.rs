@@ -0,0 +1,457 @@
+use std::{fmt::Display, str::FromStr};
+
+use crate::{computer::State, Computer, Device, FaultReason, Memory};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct TryFromError {
+    pub msg: String,
+}
+
+pub const PARAMETER_MODE_FLAGS: usize = 3;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ParameterMode {
+    #[default]
+    Position,
+    Immediate,
+    Relative,
+}
+
+impl ParameterMode {
+    pub const fn code(&self) -> isize {
+        match self {
+            Self::Position => 0,
+            Self::Immediate => 1,
+            Self::Relative => 2,
+        }
+    }
+}
+
+impl TryFrom<isize> for ParameterMode {
+    type Error = TryFromError;
+
+    fn try_from(value: isize) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(Self::Position),
+            1 => Ok(Self::Immediate),
+            2 => Ok(Self::Relative),
+            x => Err(TryFromError {
+                msg: format!("Illegal ParamterMode: {x}!"),
+            }),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Mnemonic {
+    Add,
+    Mul,
+    Input,
+    Output,
+    JumpIfTrue,
+    JumpIfFalse,
+    LessThan,
+    Equals,
+    AdjustRelBase,
+    Halt,
+}
+
+impl Mnemonic {
+    pub const ALL: [Mnemonic; 10] = [
+        Self::Add,
+        Self::Mul,
+        Self::Input,
+        Self::Output,
+        Self::JumpIfTrue,
+        Self::JumpIfFalse,
+        Self::LessThan,
+        Self::Equals,
+        Self::AdjustRelBase,
+        Self::Halt,
+    ];
+
+    pub const fn instruction_pointer_offset(&self) -> usize {
+        match self {
+            Self::Add | Self::Mul | Self::LessThan | Self::Equals => 4,
+            Self::Input | Self::Output | Self::AdjustRelBase => 2,
+            Self::Halt => 0,
+            Self::JumpIfTrue | Self::JumpIfFalse => 3,
+        }
+    }
+
+    pub const fn parameter_count(&self) -> usize {
+        match self {
+            Self::Add | Self::Mul | Self::LessThan | Self::Equals => 3,
+            Self::Input | Self::Output | Self::AdjustRelBase => 1,
+            Self::Halt => 0,
+            Self::JumpIfTrue | Self::JumpIfFalse => 2,
+        }
+    }
+
+    pub const fn target_parameter(&self) -> Option<usize> {
+        match self {
+            Self::Add | Self::Mul | Self::LessThan | Self::Equals => Some(2),
+            Self::Input => Some(0),
+            Self::Output
+            | Self::JumpIfTrue
+            | Self::JumpIfFalse
+            | Self::AdjustRelBase
+            | Self::Halt => None,
+        }
+    }
+
+    pub const fn code(&self) -> isize {
+        match self {
+            Self::Add => 1,
+            Self::Mul => 2,
+            Self::Input => 3,
+            Self::Output => 4,
+            Self::JumpIfTrue => 5,
+            Self::JumpIfFalse => 6,
+            Self::LessThan => 7,
+            Self::Equals => 8,
+            Self::AdjustRelBase => 9,
+            Self::Halt => 99,
+        }
+    }
+
+    pub const fn name(&self) -> &'static str {
+        match self {
+            Self::Add => "add",
+            Self::Mul => "mul",
+            Self::Input => "input",
+            Self::Output => "output",
+            Self::JumpIfTrue => "jump_if_true",
+            Self::JumpIfFalse => "jump_if_false",
+            Self::LessThan => "less_than",
+            Self::Equals => "equals",
+            Self::AdjustRelBase => "adjust_rel_base",
+            Self::Halt => "halt",
+        }
+    }
+}
+
+impl FromStr for Mnemonic {
+    type Err = TryFromError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let normalized = s.replace('_', "").to_lowercase();
+        Self::ALL
+            .into_iter()
+            .find(|m| m.name().replace('_', "") == normalized)
+            .ok_or_else(|| TryFromError {
+                msg: format!("Illegal mnemonic: {s}!"),
+            })
+    }
+}
+
+impl Display for Mnemonic {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+impl TryFrom<isize> for Mnemonic {
+    type Error = TryFromError;
+
+    fn try_from(value: isize) -> Result<Self, Self::Error> {
+        match value {
+            1 => Ok(Self::Add),
+            2 => Ok(Self::Mul),
+            3 => Ok(Self::Input),
+            4 => Ok(Self::Output),
+            5 => Ok(Self::JumpIfTrue),
+            6 => Ok(Self::JumpIfFalse),
+            7 => Ok(Self::LessThan),
+            8 => Ok(Self::Equals),
+            9 => Ok(Self::AdjustRelBase),
+            99 => Ok(Self::Halt),
+            x => Err(TryFromError {
+                msg: format!("Illegal mnemonic: {x}!"),
+            }),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct OpCode {
+    pub mnemonic: Mnemonic,
+    pub parameter_modes: [ParameterMode; PARAMETER_MODE_FLAGS],
+}
+
+impl OpCode {
+    pub fn new(
+        mnemonic: Mnemonic,
+        parameter_modes: [ParameterMode; PARAMETER_MODE_FLAGS],
+    ) -> Result<Self, TryFromError> {
+        if let Some(target) = mnemonic.target_parameter() {
+            if parameter_modes[target] == ParameterMode::Immediate {
+                return Err(TryFromError {
+                    msg: format!("Target parameter of {mnemonic} cannot be in immediate mode!"),
+                });
+            }
+        }
+
+        Ok(Self {
+            mnemonic,
+            parameter_modes,
+        })
+    }
+
+    pub fn encode(&self) -> isize {
+        self.parameter_modes
+            .iter()
+            .rev()
+            .fold(0, |acc, parameter_mode| acc * 10 + parameter_mode.code())
+            * 100
+            + self.mnemonic.code()
+    }
+
+    pub(crate) fn get_paramter(
+        computer: &Computer,
+        parameter_mode: ParameterMode,
+        offset: usize,
+    ) -> Result<isize, FaultReason> {
+        let offset = Memory::offset(computer.instruction_pointer, offset)?;
+        let memory = &computer.memory;
+        let parameter = memory[offset];
+        Ok(match parameter_mode {
+            ParameterMode::Position => memory[Memory::address(parameter)?],
+            ParameterMode::Immediate => parameter,
+            ParameterMode::Relative => {
+                memory[Memory::address(Self::relative(computer, parameter)?)?]
+            }
+        })
+    }
+
+    pub(crate) fn get_target_idx(
+        &self,
+        computer: &Computer,
+        parameter_mode: ParameterMode,
+        offset: usize,
+    ) -> Result<usize, FaultReason> {
+        let offset = Memory::offset(computer.instruction_pointer, offset)?;
+        let memory = &computer.memory;
+        let parameter = memory[offset];
+        let p = match parameter_mode {
+            ParameterMode::Position => parameter,
+            ParameterMode::Relative => Self::relative(computer, parameter)?,
+            ParameterMode::Immediate => return Err(FaultReason::ImmediateTarget(self.mnemonic)),
+        };
+
+        Memory::address(p)
+    }
+
+    fn relative(computer: &Computer, parameter: isize) -> Result<isize, FaultReason> {
+        computer
+            .relative_base
+            .checked_add(parameter)
+            .ok_or(FaultReason::Overflow)
+    }
+
+    fn advance(&self, computer: &mut Computer) -> Result<(), FaultReason> {
+        computer.instruction_pointer = Memory::offset(
+            computer.instruction_pointer,
+            self.mnemonic.instruction_pointer_offset(),
+        )?;
+
+        Ok(())
+    }
+
+    fn eval_add(&self, computer: &mut Computer) -> Result<(), FaultReason> {
+        let param_1 = Self::get_paramter(computer, self.parameter_modes[0], 1)?;
+        let param_2 = Self::get_paramter(computer, self.parameter_modes[1], 2)?;
+        let target_idx = self.get_target_idx(computer, self.parameter_modes[2], 3)?;
+        computer.memory[target_idx] = param_1.checked_add(param_2).ok_or(FaultReason::Overflow)?;
+        self.advance(computer)?;
+
+        Ok(())
+    }
+
+    fn eval_mul(&self, computer: &mut Computer) -> Result<(), FaultReason> {
+        let param_1 = Self::get_paramter(computer, self.parameter_modes[0], 1)?;
+        let param_2 = Self::get_paramter(computer, self.parameter_modes[1], 2)?;
+        let target_idx = self.get_target_idx(computer, self.parameter_modes[2], 3)?;
+        computer.memory[target_idx] = param_1.checked_mul(param_2).ok_or(FaultReason::Overflow)?;
+        self.advance(computer)?;
+
+        Ok(())
+    }
+
+    fn eval_input<D: Device + ?Sized>(
+        &self,
+        computer: &mut Computer,
+        io: &mut D,
+    ) -> Result<(), FaultReason> {
+        let target_idx = self.get_target_idx(computer, self.parameter_modes[0], 1)?;
+
+        match io.read_in() {
+            None => computer.state = State::WaitingForInput,
+            Some(next) => {
+                computer.memory[target_idx] = next;
+                self.advance(computer)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn eval_output<D: Device + ?Sized>(
+        &self,
+        computer: &mut Computer,
+        io: &mut D,
+    ) -> Result<(), FaultReason> {
+        let param_1 = Self::get_paramter(computer, self.parameter_modes[0], 1)?;
+        io.write_out(param_1);
+        self.advance(computer)?;
+
+        Ok(())
+    }
+
+    fn eval_jump_if_true(&self, computer: &mut Computer) -> Result<(), FaultReason> {
+        let param_1 = Self::get_paramter(computer, self.parameter_modes[0], 1)?;
+        let param_2 = Self::get_paramter(computer, self.parameter_modes[1], 2)?;
+
+        if param_1 != 0 {
+            computer.instruction_pointer = Memory::address(param_2)?;
+        } else {
+            self.advance(computer)?;
+        }
+
+        Ok(())
+    }
+
+    fn eval_jump_if_false(&self, computer: &mut Computer) -> Result<(), FaultReason> {
+        let param_1 = Self::get_paramter(computer, self.parameter_modes[0], 1)?;
+        let param_2 = Self::get_paramter(computer, self.parameter_modes[1], 2)?;
+
+        if param_1 == 0 {
+            computer.instruction_pointer = Memory::address(param_2)?;
+        } else {
+            self.advance(computer)?;
+        }
+
+        Ok(())
+    }
+
+    fn eval_less_than(&self, computer: &mut Computer) -> Result<(), FaultReason> {
+        let param_1 = Self::get_paramter(computer, self.parameter_modes[0], 1)?;
+        let param_2 = Self::get_paramter(computer, self.parameter_modes[1], 2)?;
+        let target_idx = self.get_target_idx(computer, self.parameter_modes[2], 3)?;
+        computer.memory[target_idx] = isize::from(param_1 < param_2);
+        self.advance(computer)?;
+
+        Ok(())
+    }
+
+    fn eval_equals(&self, computer: &mut Computer) -> Result<(), FaultReason> {
+        let param_1 = Self::get_paramter(computer, self.parameter_modes[0], 1)?;
+        let param_2 = Self::get_paramter(computer, self.parameter_modes[1], 2)?;
+        let target_idx = self.get_target_idx(computer, self.parameter_modes[2], 3)?;
+        computer.memory[target_idx] = isize::from(param_1 == param_2);
+        self.advance(computer)?;
+
+        Ok(())
+    }
+
+    fn eval_adjust_rel_base(&self, computer: &mut Computer) -> Result<(), FaultReason> {
+        let param_1 = Self::get_paramter(computer, self.parameter_modes[0], 1)?;
+        computer.relative_base = Self::relative(computer, param_1)?;
+        self.advance(computer)?;
+
+        Ok(())
+    }
+
+    pub(crate) fn eval<D: Device + ?Sized>(
+        &self,
+        computer: &mut Computer,
+        io: &mut D,
+    ) -> Result<(), FaultReason> {
+        match self.mnemonic {
+            Mnemonic::Add => self.eval_add(computer),
+            Mnemonic::Mul => self.eval_mul(computer),
+            Mnemonic::Input => self.eval_input(computer, io),
+            Mnemonic::Output => self.eval_output(computer, io),
+            Mnemonic::JumpIfTrue => self.eval_jump_if_true(computer),
+            Mnemonic::JumpIfFalse => self.eval_jump_if_false(computer),
+            Mnemonic::LessThan => self.eval_less_than(computer),
+            Mnemonic::Equals => self.eval_equals(computer),
+            Mnemonic::AdjustRelBase => self.eval_adjust_rel_base(computer),
+            Mnemonic::Halt => {
+                computer.state = State::Halted;
+                Ok(())
+            }
+        }
+    }
+}
+
+impl TryFrom<isize> for OpCode {
+    type Error = TryFromError;
+
+    fn try_from(mut value: isize) -> Result<Self, Self::Error> {
+        let mut parameter_modes = [ParameterMode::default(); PARAMETER_MODE_FLAGS];
+
+        let mnemonic: Mnemonic = (value % 100).try_into()?;
+        value /= 100;
+        for parameter_mode in &mut parameter_modes {
+            *parameter_mode = (value % 10).try_into()?;
+            value /= 10;
+        }
+
+        Self::new(mnemonic, parameter_modes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Mnemonic, OpCode, ParameterMode};
+
+    #[test]
+    fn test_decode() {
+        let opcode = OpCode::try_from(1002).unwrap();
+        assert_eq!(opcode.mnemonic, Mnemonic::Mul);
+        assert_eq!(
+            opcode.parameter_modes,
+            [
+                ParameterMode::Position,
+                ParameterMode::Immediate,
+                ParameterMode::Position
+            ]
+        );
+
+        let opcode = OpCode::try_from(21101).unwrap();
+        assert_eq!(opcode.mnemonic, Mnemonic::Add);
+        assert_eq!(
+            opcode.parameter_modes,
+            [
+                ParameterMode::Immediate,
+                ParameterMode::Immediate,
+                ParameterMode::Relative
+            ]
+        );
+        assert_eq!(opcode.encode(), 21101);
+
+        for mnemonic in Mnemonic::ALL {
+            assert_eq!(Mnemonic::try_from(mnemonic.code()).unwrap(), mnemonic);
+            assert_eq!(mnemonic.name().parse::<Mnemonic>().unwrap(), mnemonic);
+        }
+        assert_eq!(
+            "JumpIfTrue".parse::<Mnemonic>().unwrap(),
+            Mnemonic::JumpIfTrue
+        );
+        assert!("jmp".parse::<Mnemonic>().is_err());
+
+        assert_eq!(
+            OpCode::try_from(42).unwrap_err().msg,
+            "Illegal mnemonic: 42!"
+        );
+        assert_eq!(
+            OpCode::try_from(301).unwrap_err().msg,
+            "Illegal ParamterMode: 3!"
+        );
+        assert_eq!(
+            OpCode::try_from(10001).unwrap_err().msg,
+            "Target parameter of add cannot be in immediate mode!"
+        );
+    }
+}
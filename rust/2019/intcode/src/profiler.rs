@@ -0,0 +1,248 @@
+use std::{collections::BTreeMap, fmt::Display};
+
+use crate::{Computer, Device, Fault, Listing, Mnemonic, OpCode, State};
+
+const PROFILE_HOT_SPOTS: usize = 10;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct BranchCount {
+    pub taken: usize,
+    pub not_taken: usize,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Profile {
+    pub executions: BTreeMap<usize, usize>,
+    pub mnemonics: BTreeMap<Mnemonic, usize>,
+    pub branches: BTreeMap<usize, BranchCount>,
+}
+
+impl Profile {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn total(&self) -> usize {
+        self.mnemonics.values().sum()
+    }
+
+    pub fn step<D: Device + ?Sized>(
+        &mut self,
+        computer: &mut Computer,
+        io: &mut D,
+    ) -> Result<(), Fault> {
+        let address = computer.instruction_pointer;
+        let opcode = OpCode::try_from(computer.memory[address]).ok();
+        let condition = match opcode {
+            Some(opcode)
+                if matches!(
+                    opcode.mnemonic,
+                    Mnemonic::JumpIfTrue | Mnemonic::JumpIfFalse
+                ) =>
+            {
+                OpCode::get_paramter(computer, opcode.parameter_modes[0], 1).ok()
+            }
+            _ => None,
+        };
+
+        computer.step(io)?;
+
+        let Some(opcode) = opcode else {
+            return Ok(());
+        };
+        // An input instruction without input is retried later, it hasn't executed yet.
+        if computer.state == State::WaitingForInput {
+            return Ok(());
+        }
+
+        *self.executions.entry(address).or_default() += 1;
+        *self.mnemonics.entry(opcode.mnemonic).or_default() += 1;
+        if let Some(condition) = condition {
+            let branch = self.branches.entry(address).or_default();
+            if (condition != 0) == (opcode.mnemonic == Mnemonic::JumpIfTrue) {
+                branch.taken += 1;
+            } else {
+                branch.not_taken += 1;
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn merge(&mut self, other: &Profile) {
+        for (address, count) in &other.executions {
+            *self.executions.entry(*address).or_default() += count;
+        }
+        for (mnemonic, count) in &other.mnemonics {
+            *self.mnemonics.entry(*mnemonic).or_default() += count;
+        }
+        for (address, count) in &other.branches {
+            let branch = self.branches.entry(*address).or_default();
+            branch.taken += count.taken;
+            branch.not_taken += count.not_taken;
+        }
+    }
+
+    pub fn hot_spots(&self, n: usize) -> Vec<(usize, usize)> {
+        let mut hot_spots: Vec<(usize, usize)> =
+            self.executions.iter().map(|(a, c)| (*a, *c)).collect();
+        hot_spots.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+        hot_spots.truncate(n);
+        hot_spots
+    }
+
+    pub fn annotate(&self, listing: &Listing) -> String {
+        let mut report = String::new();
+
+        for (address, line) in listing.lines() {
+            let count = match address {
+                Some(address) if listing.is_code(address) => match self.executions.get(&address) {
+                    Some(count) => count.to_string(),
+                    None => "-".to_string(),
+                },
+                _ => String::new(),
+            };
+            report.push_str(&format!("{count:>10} | {line}"));
+            if let Some(branch) = address.and_then(|a| self.branches.get(&a)) {
+                report.push_str(&format!(
+                    " - taken {} / not taken {}",
+                    branch.taken, branch.not_taken
+                ));
+            }
+            report.push('\n');
+        }
+
+        let reached = listing
+            .instructions
+            .keys()
+            .filter(|a| self.executions.contains_key(a))
+            .count();
+        report.push_str(&format!(
+            "coverage: {reached}/{} instructions\n",
+            listing.instructions.len()
+        ));
+
+        let outside: Vec<String> = self
+            .executions
+            .keys()
+            .filter(|a| !listing.is_code(**a))
+            .map(|a| format!("{a:04}"))
+            .collect();
+        if !outside.is_empty() {
+            report.push_str(&format!(
+                "executed outside listing: {}\n",
+                outside.join(", ")
+            ));
+        }
+
+        report
+    }
+}
+
+impl Display for Profile {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "instructions: {}", self.total())?;
+        for (mnemonic, count) in &self.mnemonics {
+            writeln!(f, "  {:<16}{count:>10}", mnemonic.name())?;
+        }
+        writeln!(f, "hot spots:")?;
+        for (address, count) in self.hot_spots(PROFILE_HOT_SPOTS) {
+            writeln!(f, "  {address:04}{count:>22}")?;
+        }
+        writeln!(f, "branches:")?;
+        for (address, branch) in &self.branches {
+            writeln!(
+                f,
+                "  {address:04} taken {} / not taken {}",
+                branch.taken, branch.not_taken
+            )?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{BranchCount, Computer, InputOutput, Listing, Mnemonic, Profile};
+
+    #[test]
+    fn test_examples() {
+        let program = "3,9,8,9,10,9,4,9,99,-1,8";
+        let mut computer = Computer::from(program);
+        let mut io = InputOutput::new(&[]);
+        let mut profile = Profile::new();
+
+        computer.eval_profiled(&mut io, &mut profile).unwrap();
+        assert_eq!(profile.total(), 0);
+
+        io.write_to_in_q(8);
+        computer.eval_profiled(&mut io, &mut profile).unwrap();
+        assert_eq!(io.out_q, [1]);
+        assert_eq!(profile.total(), 4);
+        assert_eq!(profile.executions.get(&0), Some(&1));
+        assert_eq!(profile.mnemonics.get(&Mnemonic::Equals), Some(&1));
+
+        let listing = Listing::from(program);
+        assert_eq!(
+            profile.annotate(&listing),
+            "           | start:
+         1 |     input [var_0009]                     ; 0000 - 3,9
+         1 |     equals [var_0009], [var_0010], [var_0009] ; 0002 - 8,9,10,9
+         1 |     output [var_0009]                    ; 0006 - 4,9
+         1 |     halt                                 ; 0008 - 99
+           | var_0009:
+           |     db -1                                ; 0009
+           | var_0010:
+           |     db 8                                 ; 0010
+coverage: 4/4 instructions
+"
+        );
+    }
+
+    #[test]
+    fn test_branches() {
+        let program = "1101,0,3,20,1001,20,-1,20,1005,20,4,99";
+        let mut computer = Computer::from(program);
+        let mut io = InputOutput::new(&[]);
+        let mut profile = Profile::new();
+        computer.eval_profiled(&mut io, &mut profile).unwrap();
+
+        assert_eq!(
+            profile.branches.get(&8),
+            Some(&BranchCount {
+                taken: 2,
+                not_taken: 1
+            })
+        );
+        assert_eq!(profile.hot_spots(2), [(4, 3), (8, 3)]);
+
+        let mut merged = profile.clone();
+        merged.merge(&profile);
+        assert_eq!(merged.total(), 2 * profile.total());
+        assert_eq!(merged.branches.get(&8).unwrap().taken, 4);
+
+        let report = profile.to_string();
+        assert!(report.starts_with("instructions: 8\n"));
+        assert!(report.contains("  0008 taken 2 / not taken 1\n"));
+    }
+
+    #[test]
+    fn test_input() {
+        let program = std::fs::read_to_string("../day_09/input/program.txt").unwrap();
+        let mut computer = Computer::from(program.as_str());
+        let mut io = InputOutput::new(&[1]);
+        let mut profile = Profile::new();
+        computer.eval_profiled(&mut io, &mut profile).unwrap();
+        assert_eq!(io.out_q, [2171728567]);
+
+        let mut reference = Computer::from(program.as_str());
+        let mut reference_io = InputOutput::new(&[1]);
+        reference.eval(&mut reference_io).unwrap();
+        assert_eq!(reference.memory, computer.memory);
+
+        let listing = Listing::from(program.as_str());
+        let report = profile.annotate(&listing);
+        assert!(report.contains("coverage: "));
+        assert!(profile.total() > listing.instructions.len());
+    }
+}
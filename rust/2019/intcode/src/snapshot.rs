@@ -0,0 +1,285 @@
+use std::{collections::VecDeque, fmt::Display, path::Path, str::FromStr};
+
+use crate::{Computer, InputOutput, Memory, State};
+
+const SNAPSHOT_MAGIC: &str = "intcode-snapshot";
+const SNAPSHOT_VERSION: usize = 1;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SnapshotError {
+    pub msg: String,
+}
+
+impl Display for SnapshotError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.msg)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Snapshot {
+    pub computer: Computer,
+    pub io: InputOutput,
+}
+
+impl Snapshot {
+    pub fn new(computer: &Computer, io: &InputOutput) -> Self {
+        Self {
+            computer: computer.clone(),
+            io: io.clone(),
+        }
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> std::io::Result<()> {
+        std::fs::write(path, self.to_string())
+    }
+
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, SnapshotError> {
+        let path = path.as_ref();
+        let data = std::fs::read_to_string(path).map_err(|e| SnapshotError {
+            msg: format!("Failed to read snapshot '{}': {e}!", path.display()),
+        })?;
+        data.parse()
+    }
+}
+
+fn state_name(state: State) -> &'static str {
+    match state {
+        State::Running => "running",
+        State::WaitingForInput => "waiting_for_input",
+        State::Halted => "halted",
+    }
+}
+
+fn join(values: impl Iterator<Item = isize>) -> String {
+    values.map(|v| v.to_string()).collect::<Vec<_>>().join(",")
+}
+
+fn write_queue(
+    f: &mut std::fmt::Formatter<'_>,
+    key: &str,
+    queue: &VecDeque<isize>,
+) -> std::fmt::Result {
+    if queue.is_empty() {
+        writeln!(f, "{key}")
+    } else {
+        writeln!(f, "{key} {}", join(queue.iter().copied()))
+    }
+}
+
+fn write_run(f: &mut std::fmt::Formatter<'_>, address: usize, run: &[isize]) -> std::fmt::Result {
+    if run.is_empty() {
+        return Ok(());
+    }
+    writeln!(f, "@{address} {}", join(run.iter().copied()))
+}
+
+impl Display for Snapshot {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let computer = &self.computer;
+
+        writeln!(f, "{SNAPSHOT_MAGIC} {SNAPSHOT_VERSION}")?;
+        writeln!(f, "state {}", state_name(computer.state))?;
+        writeln!(f, "ip {}", computer.instruction_pointer)?;
+        writeln!(f, "rb {}", computer.relative_base)?;
+        write_queue(f, "in", &self.io.in_q)?;
+        write_queue(f, "out", &self.io.out_q)?;
+
+        let mut run: Vec<isize> = Vec::new();
+        let mut run_start = 0;
+        for (address, value) in computer.memory.non_zero() {
+            if run_start + run.len() != address {
+                write_run(f, run_start, &run)?;
+                run.clear();
+                run_start = address;
+            }
+            run.push(value);
+        }
+        write_run(f, run_start, &run)?;
+
+        Ok(())
+    }
+}
+
+fn parse_values(s: &str) -> Result<Vec<isize>, String> {
+    if s.is_empty() {
+        return Ok(Vec::new());
+    }
+    s.split(',')
+        .map(|v| v.parse().map_err(|_| format!("Illegal value '{v}'!")))
+        .collect()
+}
+
+fn parse_number<T: FromStr>(s: &str) -> Result<T, String> {
+    s.parse().map_err(|_| format!("Illegal number '{s}'!"))
+}
+
+impl FromStr for Snapshot {
+    type Err = SnapshotError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut lines = s.lines().enumerate();
+
+        let header = lines.next().map(|(_, l)| l).unwrap_or_default();
+        let version = match header.split_once(' ') {
+            Some((SNAPSHOT_MAGIC, version)) => version,
+            _ => {
+                return Err(SnapshotError {
+                    msg: "Not an intcode snapshot!".to_string(),
+                })
+            }
+        };
+        if version != SNAPSHOT_VERSION.to_string() {
+            return Err(SnapshotError {
+                msg: format!("Unsupported snapshot version '{version}'!"),
+            });
+        }
+
+        let mut computer = Computer {
+            state: State::Running,
+            memory: Memory::new(),
+            instruction_pointer: 0,
+            relative_base: 0,
+        };
+        let mut io = InputOutput::new(&[]);
+
+        for (idx, line) in lines {
+            let err = |msg: String| SnapshotError {
+                msg: format!("line {}: {msg}", idx + 1),
+            };
+            let (key, value) = line.split_once(' ').unwrap_or((line, ""));
+
+            match key {
+                "state" => {
+                    computer.state = match value {
+                        "running" => State::Running,
+                        "waiting_for_input" => State::WaitingForInput,
+                        "halted" => State::Halted,
+                        _ => return Err(err(format!("Illegal state '{value}'!"))),
+                    }
+                }
+                "ip" => computer.instruction_pointer = parse_number(value).map_err(err)?,
+                "rb" => computer.relative_base = parse_number(value).map_err(err)?,
+                "in" => io.in_q = VecDeque::from(parse_values(value).map_err(err)?),
+                "out" => io.out_q = VecDeque::from(parse_values(value).map_err(err)?),
+                _ if key.starts_with('@') => {
+                    let address: usize = parse_number(&key[1..]).map_err(err)?;
+                    for (offset, value) in parse_values(value).map_err(err)?.into_iter().enumerate()
+                    {
+                        let address = Memory::offset(address, offset)
+                            .map_err(|reason| err(reason.to_string()))?;
+                        computer.memory[address] = value;
+                    }
+                }
+                "" => (),
+                _ => return Err(err(format!("Unknown key '{key}'!"))),
+            }
+        }
+
+        Ok(Self { computer, io })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Computer, InputOutput, Snapshot, State};
+
+    #[test]
+    fn test_examples() {
+        let mut computer = Computer::from("3,9,8,9,10,9,4,9,99,-1,8");
+        let mut io = InputOutput::new(&[]);
+        computer.eval(&mut io).unwrap();
+        io.write_out(42);
+
+        let text = Snapshot::new(&computer, &io).to_string();
+        assert_eq!(
+            text,
+            "\
+intcode-snapshot 1
+state waiting_for_input
+ip 0
+rb 0
+in
+out 42
+@0 3,9,8,9,10,9,4,9,99,-1,8
+"
+        );
+
+        let mut snapshot: Snapshot = text.parse().unwrap();
+        assert_eq!(snapshot.computer.state, State::WaitingForInput);
+        assert_eq!(snapshot.computer.memory, computer.memory);
+        assert_eq!(snapshot.io.out_q, [42]);
+
+        snapshot.io.write_to_in_q(8);
+        snapshot.computer.eval(&mut snapshot.io).unwrap();
+        assert_eq!(snapshot.io.out_q, [42, 1]);
+    }
+
+    #[test]
+    fn test_errors() {
+        let err = "hello".parse::<Snapshot>().unwrap_err();
+        assert_eq!(err.msg, "Not an intcode snapshot!");
+
+        let err = "intcode-snapshot 2\n".parse::<Snapshot>().unwrap_err();
+        assert_eq!(err.msg, "Unsupported snapshot version '2'!");
+
+        let err = "intcode-snapshot 1\nip x\n"
+            .parse::<Snapshot>()
+            .unwrap_err();
+        assert_eq!(err.msg, "line 2: Illegal number 'x'!");
+
+        let err = "intcode-snapshot 1\n@9223372036854775807 1,2\n"
+            .parse::<Snapshot>()
+            .unwrap_err();
+        assert_eq!(
+            err.msg,
+            "line 2: Address limit exceeded after 9223372036854775807!"
+        );
+
+        let err = "intcode-snapshot 1\n@18446744073709551615 1\n"
+            .parse::<Snapshot>()
+            .unwrap_err();
+        assert_eq!(
+            err.msg,
+            "line 2: Address limit exceeded after 18446744073709551615!"
+        );
+
+        let err = "intcode-snapshot 1\nsize 4\n"
+            .parse::<Snapshot>()
+            .unwrap_err();
+        assert_eq!(err.msg, "line 2: Unknown key 'size'!");
+
+        let snapshot = "intcode-snapshot 1\n@2 1,2,3\n"
+            .parse::<Snapshot>()
+            .unwrap();
+        assert_eq!(snapshot.computer.memory.read(0, 6), [0, 0, 1, 2, 3, 0]);
+    }
+
+    #[test]
+    fn test_input() {
+        let program = std::fs::read_to_string("../day_25/input/program.txt").unwrap();
+        let mut computer = Computer::from(program.as_str());
+        let mut io = InputOutput::new(&[]);
+        computer.eval(&mut io).unwrap();
+        let intro = io.read_from_out_q_ascii().unwrap();
+        io.write_to_in_q_ascii("inv\n");
+
+        let path =
+            std::env::temp_dir().join(format!("intcode-snapshot-{}.txt", std::process::id()));
+        Snapshot::new(&computer, &io).save(&path).unwrap();
+        let mut snapshot = Snapshot::load(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert!(intro.contains("Command?"));
+
+        computer.eval(&mut io).unwrap();
+        snapshot.computer.eval(&mut snapshot.io).unwrap();
+        assert_eq!(snapshot.io.out_q, io.out_q);
+        assert_eq!(snapshot.computer.memory, computer.memory);
+        assert_eq!(
+            snapshot.computer.instruction_pointer,
+            computer.instruction_pointer
+        );
+        assert_eq!(snapshot.computer.relative_base, computer.relative_base);
+    }
+}